@@ -1,4 +1,5 @@
 DROP TRIGGER IF EXISTS update_timestamp_trigger ON init_completion CASCADE;
+DROP TRIGGER IF EXISTS update_timestamp_trigger ON miner_notifications CASCADE;
 DROP TRIGGER IF EXISTS update_timestamp_trigger ON earnings CASCADE;
 DROP TRIGGER IF EXISTS update_timestamp_trigger ON rewards CASCADE;
 DROP TRIGGER IF EXISTS update_timestamp_trigger ON claims CASCADE;
@@ -12,6 +13,8 @@ DROP TRIGGER IF EXISTS update_timestamp_trigger ON miners CASCADE;
 DROP FUNCTION IF EXISTS update_timestamp() CASCADE;
 
 DROP TABLE IF EXISTS init_completion;
+DROP TABLE IF EXISTS submission_attempts;
+DROP TABLE IF EXISTS miner_notifications;
 DROP TABLE IF EXISTS earnings;
 DROP TABLE IF EXISTS rewards;
 DROP TABLE IF EXISTS claims;
@@ -0,0 +1,22 @@
+-- Applied at every start, so pools initialized before miner notifications existed get the table too.
+-- Postgres only, miner notifications are not available on sqlite pools.
+-- Email targets are only delivered to once the miner confirmed the code sent to the address.
+CREATE TABLE IF NOT EXISTS miner_notifications (
+  id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  miner_id BIGINT NOT NULL,
+  channel VARCHAR(16) NOT NULL,
+  target VARCHAR(512) NOT NULL,
+  events VARCHAR(128) NOT NULL,
+  offline_mins INT DEFAULT 10 NOT NULL,
+  verified BOOLEAN DEFAULT false NOT NULL,
+  verify_code VARCHAR(64),
+  created TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE OR REPLACE TRIGGER update_timestamp_trigger
+BEFORE UPDATE ON miner_notifications
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+CREATE UNIQUE INDEX IF NOT EXISTS uniq_miner_notifications_miner_id ON miner_notifications (miner_id ASC);
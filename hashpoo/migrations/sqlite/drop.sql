@@ -9,6 +9,7 @@ DROP TRIGGER IF EXISTS pools_update_timestamp_trigger;
 DROP TRIGGER IF EXISTS members_update_timestamp_trigger;
 DROP TRIGGER IF EXISTS miners_update_timestamp_trigger;
 
+DROP TABLE IF EXISTS submission_attempts;
 DROP TABLE IF EXISTS earnings;
 DROP TABLE IF EXISTS rewards;
 DROP TABLE IF EXISTS claims;
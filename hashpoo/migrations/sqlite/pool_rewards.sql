@@ -0,0 +1,3 @@
+-- Applied at every start, so pools initialized before per pool rewards existed get the index too.
+-- One rewards balance per miner and pool, each pool pays claims from its own proof.
+CREATE UNIQUE INDEX IF NOT EXISTS uniq_rewards_miner_pool_ids ON rewards (miner_id, pool_id);
@@ -0,0 +1,25 @@
+-- Applied at every start, so pools initialized before submission telemetry existed get the table too.
+-- One row per mine transaction submission attempt, landed or not.
+CREATE TABLE IF NOT EXISTS submission_attempts (
+    id INTEGER PRIMARY KEY,
+    pool_id INT NOT NULL,
+    challenge_id BIGINT,
+    attempt INT NOT NULL,
+    method VARCHAR(16) NOT NULL,
+    difficulty INT NOT NULL,
+    priority_fee BIGINT DEFAULT 0 NOT NULL,
+    cu_limit INT NOT NULL,
+    tip BIGINT DEFAULT 0 NOT NULL,
+    blockhash VARCHAR(64),
+    signature VARCHAR(200),
+    sent_at_ms BIGINT NOT NULL,
+    confirmed_at_ms BIGINT,
+    slot BIGINT,
+    outcome VARCHAR(16) NOT NULL,
+    error_code VARCHAR(32),
+    error TEXT,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS indx_submission_attempts_pool_id_created ON submission_attempts (pool_id ASC, created DESC);
+CREATE INDEX IF NOT EXISTS indx_submission_attempts_challenge_id ON submission_attempts (challenge_id ASC);
@@ -0,0 +1,370 @@
+use {
+    crate::{
+        database::{Database, PoweredByDbms},
+        pools::{PoolInstance, Pools},
+        signed_request::SignedRequests,
+        AppState, ClaimsQueue, MessageInternalAllClients, PubkeyParam, ADMIN_PUBKEY,
+        OPERATOR_PAUSED, POWERED_BY_DBMS,
+    },
+    axum::{
+        body::{to_bytes, Body},
+        extract::{ws::Message, Query, Request, State},
+        http::StatusCode,
+        middleware::Next,
+        response::{IntoResponse, Response},
+        Extension, Json,
+    },
+    axum_extra::{
+        headers::{authorization::Basic, Authorization},
+        TypedHeader,
+    },
+    base64::{prelude::BASE64_STANDARD, Engine},
+    futures::SinkExt,
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashSet,
+        net::SocketAddr,
+        str::FromStr,
+        sync::{atomic::Ordering::Relaxed, Arc},
+    },
+    tokio::sync::{mpsc::UnboundedSender, Mutex, RwLock},
+    tracing::{error, info},
+};
+
+// MI: broadcast texts are the only admin bodies
+const MAX_BODY_LEN: usize = 64 * 1024;
+
+type AdminResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+#[derive(Deserialize)]
+pub struct AdminAuthParams {
+    timestamp: u64,
+}
+
+#[derive(Deserialize)]
+pub struct SocketAddrParam {
+    addr: String,
+}
+
+#[derive(Serialize)]
+pub struct AdminAck {
+    message: String,
+}
+
+#[derive(Serialize)]
+pub struct AdminSocket {
+    addr: String,
+    pubkey: String,
+    miner_id: i64,
+    pool: String,
+}
+
+#[derive(Serialize)]
+pub struct AdminContribution {
+    pubkey: String,
+    miner_id: i64,
+    difficulty: u32,
+    hashpower: u64,
+}
+
+#[derive(Serialize)]
+pub struct AdminNonceRange {
+    pubkey: String,
+    start: u64,
+    end: u64,
+}
+
+#[derive(Serialize)]
+pub struct AdminClaim {
+    pubkey: String,
+    receiver_pubkey: String,
+    amount: u64,
+}
+
+#[derive(Serialize)]
+pub struct AdminPool {
+    authority: String,
+    paused: bool,
+    challenge: String,
+    best_difficulty: u32,
+    ready_clients: Vec<String>,
+    contributions: Vec<AdminContribution>,
+    nonce_ranges: Vec<AdminNonceRange>,
+}
+
+#[derive(Serialize)]
+pub struct AdminState {
+    operator_paused: bool,
+    sockets: Vec<AdminSocket>,
+    pools: Vec<AdminPool>,
+    claims_queue: Vec<AdminClaim>,
+}
+
+/// Admin requests are authenticated the same way miners are: basic auth with the operator pubkey
+/// as username and, as password, its signature over the method, path and query, body digest and
+/// `timestamp` query param of the request. Each signature is accepted once.
+pub async fn require_operator(
+    State(signed_requests): State<Arc<SignedRequests>>,
+    TypedHeader(auth_header): TypedHeader<Authorization<Basic>>,
+    Query(auth): Query<AdminAuthParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, MAX_BODY_LEN).await else {
+        return (StatusCode::PAYLOAD_TOO_LARGE, "Body too large".to_string()).into_response();
+    };
+    let path_and_query =
+        parts.uri.path_and_query().map(|path| path.as_str()).unwrap_or(parts.uri.path());
+
+    let signer = match signed_requests.verify(
+        &auth_header,
+        &parts.method,
+        path_and_query,
+        &body,
+        auth.timestamp,
+    ) {
+        Ok(signer) => signer,
+        Err(e) => return e.into_response(),
+    };
+    if !signer.eq(ADMIN_PUBKEY.get().unwrap()) {
+        return (StatusCode::UNAUTHORIZED, "Invalid operator pubkey".to_string()).into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+fn ack(message: String) -> AdminResult<AdminAck> {
+    info!(target: "server_log", "Admin: {}", message);
+    Ok(Json(AdminAck { message }))
+}
+
+async fn close_sockets(
+    app_state: &Arc<RwLock<AppState>>,
+    ready_clients: &Arc<Mutex<HashSet<SocketAddr>>>,
+    addrs: Vec<SocketAddr>,
+) -> usize {
+    let mut closed = 0;
+    for addr in addrs {
+        let client_connection = app_state.write().await.sockets.remove(&addr);
+        ready_clients.lock().await.remove(&addr);
+        if let Some(client_connection) = client_connection {
+            let _ = client_connection.socket.lock().await.send(Message::Close(None)).await;
+            closed += 1;
+        }
+    }
+
+    closed
+}
+
+async fn set_miner_status(
+    app_state: &Arc<RwLock<AppState>>,
+    ready_clients: &Arc<Mutex<HashSet<SocketAddr>>>,
+    database: &Arc<Database>,
+    pubkey: &str,
+    enabled: bool,
+    status: &str,
+) -> AdminResult<AdminAck> {
+    let powered_by_dbms = POWERED_BY_DBMS.get().unwrap_or(&PoweredByDbms::Unavailable);
+    if powered_by_dbms == &PoweredByDbms::Unavailable {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Miner status requires POWERED_BY_DBMS enabled.".to_string(),
+        ));
+    }
+
+    let Ok(miner_pubkey) = Pubkey::from_str(pubkey) else {
+        return Err((StatusCode::BAD_REQUEST, "Invalid public key".to_string()));
+    };
+
+    if let Err(e) =
+        database.update_miner_status(miner_pubkey.to_string(), enabled, status.to_string()).await
+    {
+        error!(target: "server_log", "Admin: failed to update status of miner {}: {:?}", pubkey, e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to update miner".to_string()));
+    }
+
+    // miners are only checked when connecting, kick the ones not allowed to mine any more
+    let mut closed = 0;
+    if !enabled {
+        let addrs: Vec<SocketAddr> = app_state
+            .read()
+            .await
+            .sockets
+            .iter()
+            .filter(|(_, client_connection)| client_connection.pubkey.eq(&miner_pubkey))
+            .map(|(addr, _)| *addr)
+            .collect();
+        closed = close_sockets(app_state, ready_clients, addrs).await;
+    }
+
+    ack(format!("Miner {} set to {}, {} socket(s) disconnected.", pubkey, status, closed))
+}
+
+pub async fn post_pause() -> AdminResult<AdminAck> {
+    OPERATOR_PAUSED.store(true, Relaxed);
+    ack("Mission dispatch paused.".to_string())
+}
+
+pub async fn post_resume() -> AdminResult<AdminAck> {
+    OPERATOR_PAUSED.store(false, Relaxed);
+    ack("Mission dispatch resumed.".to_string())
+}
+
+pub async fn post_enable_miner(
+    Query(miner): Query<PubkeyParam>,
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(ready_clients): Extension<Arc<Mutex<HashSet<SocketAddr>>>>,
+    Extension(database): Extension<Arc<Database>>,
+) -> AdminResult<AdminAck> {
+    set_miner_status(&app_state, &ready_clients, &database, &miner.pubkey, true, "Enrolled").await
+}
+
+pub async fn post_disable_miner(
+    Query(miner): Query<PubkeyParam>,
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(ready_clients): Extension<Arc<Mutex<HashSet<SocketAddr>>>>,
+    Extension(database): Extension<Arc<Database>>,
+) -> AdminResult<AdminAck> {
+    set_miner_status(&app_state, &ready_clients, &database, &miner.pubkey, false, "Disabled").await
+}
+
+pub async fn post_ban_miner(
+    Query(miner): Query<PubkeyParam>,
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(ready_clients): Extension<Arc<Mutex<HashSet<SocketAddr>>>>,
+    Extension(database): Extension<Arc<Database>>,
+) -> AdminResult<AdminAck> {
+    set_miner_status(&app_state, &ready_clients, &database, &miner.pubkey, false, "Banned").await
+}
+
+pub async fn post_disconnect(
+    Query(socket): Query<SocketAddrParam>,
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(ready_clients): Extension<Arc<Mutex<HashSet<SocketAddr>>>>,
+) -> AdminResult<AdminAck> {
+    let Ok(addr) = SocketAddr::from_str(&socket.addr) else {
+        return Err((StatusCode::BAD_REQUEST, "Invalid socket address".to_string()));
+    };
+
+    if close_sockets(&app_state, &ready_clients, vec![addr]).await > 0 {
+        ack(format!("Socket {} disconnected.", addr))
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("Socket {} not connected.", addr)))
+    }
+}
+
+pub async fn post_broadcast(
+    Extension(all_clients_sender): Extension<UnboundedSender<MessageInternalAllClients>>,
+    text: String,
+) -> AdminResult<AdminAck> {
+    if text.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Empty message".to_string()));
+    }
+
+    if all_clients_sender
+        .send(MessageInternalAllClients { text: text.clone(), pool: None })
+        .is_err()
+    {
+        error!(target: "server_log", "Admin: all clients message receiver dropped.");
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to broadcast".to_string()));
+    }
+    ack(format!("Broadcasted: {}", text))
+}
+
+pub async fn post_flush_claims(
+    Extension(claims_queue): Extension<Arc<ClaimsQueue>>,
+) -> AdminResult<AdminAck> {
+    let mut writer = claims_queue.queue.write().await;
+    let flushed = writer.len();
+    writer.clear();
+    drop(writer);
+
+    ack(format!("Flushed {} queued claim(s).", flushed))
+}
+
+pub async fn get_state(
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(claims_queue): Extension<Arc<ClaimsQueue>>,
+    Extension(pools): Extension<Arc<Pools>>,
+) -> AdminResult<AdminState> {
+    let sockets = app_state
+        .read()
+        .await
+        .sockets
+        .iter()
+        .map(|(addr, client_connection)| AdminSocket {
+            addr: addr.to_string(),
+            pubkey: client_connection.pubkey.to_string(),
+            miner_id: client_connection.miner_id,
+            pool: client_connection.pool.to_string(),
+        })
+        .collect();
+
+    let mut admin_pools = Vec::new();
+    for pool in pools.iter() {
+        admin_pools.push(pool_state(pool).await);
+    }
+
+    let claims_queue = claims_queue
+        .queue
+        .read()
+        .await
+        .iter()
+        .map(|(pubkey, item)| AdminClaim {
+            pubkey: pubkey.to_string(),
+            receiver_pubkey: item.receiver_pubkey.to_string(),
+            amount: item.amount,
+        })
+        .collect();
+
+    Ok(Json(AdminState {
+        operator_paused: OPERATOR_PAUSED.load(Relaxed),
+        sockets,
+        pools: admin_pools,
+        claims_queue,
+    }))
+}
+
+async fn pool_state(pool: &PoolInstance) -> AdminPool {
+    let ready_clients =
+        pool.ready_clients.lock().await.iter().map(|addr| addr.to_string()).collect();
+
+    let reader = pool.epoch_hashes.read().await;
+    let challenge = BASE64_STANDARD.encode(reader.challenge);
+    let best_difficulty = reader.best_hash.difficulty;
+    let contributions = reader
+        .contributions
+        .iter()
+        .map(|(pubkey, contribution)| AdminContribution {
+            pubkey: pubkey.to_string(),
+            miner_id: contribution.miner_id,
+            difficulty: contribution.supplied_diff,
+            hashpower: contribution.hashpower,
+        })
+        .collect();
+    drop(reader);
+
+    let nonce_ranges = pool
+        .client_nonce_ranges
+        .read()
+        .await
+        .iter()
+        .map(|(pubkey, range)| AdminNonceRange {
+            pubkey: pubkey.to_string(),
+            start: range.start,
+            end: range.end,
+        })
+        .collect();
+
+    AdminPool {
+        authority: pool.authority.to_string(),
+        paused: pool.paused.load(Relaxed),
+        challenge,
+        best_difficulty,
+        ready_clients,
+        contributions,
+        nonce_ranges,
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::metrics::METRICS,
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig},
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        compute_budget::{self, ComputeBudgetInstruction},
+        instruction::Instruction,
+        pubkey::Pubkey,
+        transaction::Transaction,
+    },
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::{debug, warn},
+};
+
+// MI: max compute units of a transaction, the simulation runs with it
+const MAX_CU_LIMIT: u32 = 1_400_000;
+
+// MI: drillx verification costs the same each time, yet program upgrades may change usage
+const SIMULATION_TTL: Duration = Duration::from_secs(600);
+
+/// Instructions other than the compute budget ones, by program and number of accounts.
+type InstructionShape = Vec<(Pubkey, usize)>;
+
+/// Sets the compute unit limit of a transaction to what its simulation consumed plus a safety
+/// margin. Results are cached per instruction shape, so only the first transaction of a shape,
+/// e.g. the first one including a reset, pays the extra simulation round trip.
+pub struct ComputeUnitEstimator {
+    margin_percent: u64,
+    cache: Mutex<HashMap<InstructionShape, (u32, Instant)>>,
+}
+
+impl ComputeUnitEstimator {
+    pub fn new(margin_percent: u64) -> Self {
+        ComputeUnitEstimator { margin_percent, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// Compute unit limit of a transaction made of `ixs` paid by `payer`, `fallback` when the
+    /// simulation fails.
+    pub async fn cu_limit(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        fallback: u32,
+    ) -> u32 {
+        let shape: InstructionShape = ixs
+            .iter()
+            .filter(|ix| ix.program_id != compute_budget::id())
+            .map(|ix| (ix.program_id, ix.accounts.len()))
+            .collect();
+
+        if let Some((limit, simulated_at)) = self.cache.lock().unwrap().get(&shape) {
+            if simulated_at.elapsed() < SIMULATION_TTL {
+                return *limit;
+            }
+        }
+
+        match self.simulate(rpc_client, ixs, payer).await {
+            Ok(units_consumed) => {
+                let limit = units_consumed
+                    .saturating_mul(100u64.saturating_add(self.margin_percent))
+                    .saturating_div(100)
+                    .min(MAX_CU_LIMIT as u64) as u32;
+                debug!(target: "server_log", "Simulation consumed {} compute units, limit set to {}", units_consumed, limit);
+                self.cache.lock().unwrap().insert(shape, (limit, Instant::now()));
+                limit
+            },
+            Err(err) => {
+                warn!(target: "server_log", "Failed to simulate mine transaction: {}. Falling back to {} compute units.", err, fallback);
+                fallback
+            },
+        }
+    }
+
+    /// Compute units consumed by the instructions. Runs without signatures on the latest
+    /// blockhash, compute budget instructions in `ixs` are replaced by the max limit.
+    async fn simulate(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+    ) -> Result<u64, String> {
+        let mut sim_ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_CU_LIMIT)];
+        sim_ixs.extend(ixs.iter().filter(|ix| ix.program_id != compute_budget::id()).cloned());
+        let tx = Transaction::new_with_payer(&sim_ixs, Some(payer));
+
+        let started = Instant::now();
+        let res = rpc_client
+            .simulate_transaction_with_config(
+                &tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..Default::default()
+                },
+            )
+            .await;
+        METRICS.observe_rpc_call("simulate_transaction", started.elapsed());
+
+        let result = res.map_err(|err| err.to_string())?.value;
+        if let Some(err) = result.err {
+            return Err(format!("{err}, logs: {:?}", result.logs.unwrap_or_default()));
+        }
+        result.units_consumed.ok_or("Simulation did not report units consumed".to_string())
+    }
+}
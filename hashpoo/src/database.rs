@@ -164,6 +164,18 @@ pub async fn validate_dbms(dbms_settings: &mut PoweredByParams<'_>, _database: D
         return false;
     }
 
+    // tables added after the initial schema, idempotent so it runs on every start
+    let submission_attempts_sql = include_str!("../migrations/sqlite/submission_attempts.sql");
+    if let Err(e) = conn.execute_batch(submission_attempts_sql) {
+        error!(target: "server_log", "Error occurred creating submission_attempts table: {}", e);
+        return false;
+    }
+    let pool_rewards_sql = include_str!("../migrations/sqlite/pool_rewards.sql");
+    if let Err(e) = conn.execute_batch(pool_rewards_sql) {
+        error!(target: "server_log", "Error occurred creating pool rewards index: {}", e);
+        return false;
+    }
+
     true
 }
 
@@ -233,6 +245,23 @@ pub async fn validate_dbms(dbms_settings: &mut PoweredByParams<'_>, database: Da
     }
     dbms_settings.corrupted = false;
 
+    // tables added after the initial schema, idempotent so it runs on every start
+    let miner_notifications_sql = include_str!("../migrations/postgres/miner_notifications.sql");
+    if let Err(e) = conn.batch_execute(miner_notifications_sql).await {
+        error!(target: "server_log", "Error occurred creating miner_notifications table: {}", e);
+        return false;
+    }
+    let submission_attempts_sql = include_str!("../migrations/postgres/submission_attempts.sql");
+    if let Err(e) = conn.batch_execute(submission_attempts_sql).await {
+        error!(target: "server_log", "Error occurred creating submission_attempts table: {}", e);
+        return false;
+    }
+    let pool_rewards_sql = include_str!("../migrations/postgres/pool_rewards.sql");
+    if let Err(e) = conn.batch_execute(pool_rewards_sql).await {
+        error!(target: "server_log", "Error occurred creating pool rewards index: {}", e);
+        return false;
+    }
+
     // match conn.query_opt(check_comp_flag_sql, &[]).await {
     //     // more than 1 rows returned
     //     Err(e) => {
@@ -336,6 +365,21 @@ impl Database {
         self.connection_pool.get().await.map_err(|err| err.to_string())
     }
 
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn ping(&self) -> Result<(), String> {
+        let db_conn = self.get_connection().await?;
+        db_conn.simple_query("SELECT 1").await.map(|_| ()).map_err(|err| err.to_string())
+    }
+
+    #[cfg(feature = "powered-by-dbms-sqlite")]
+    pub async fn ping(&self) -> Result<(), String> {
+        let db_conn = self._get_connection().await?;
+        match db_conn.interact(|conn| conn.execute_batch("SELECT 1")).await {
+            Ok(interaction) => interaction.map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
     #[cfg(feature = "powered-by-dbms-sqlite")]
     pub async fn get_pool_by_authority_pubkey(
         &self,
@@ -659,6 +703,75 @@ impl Database {
         }
     }
 
+    #[cfg(feature = "powered-by-dbms-sqlite")]
+    pub async fn update_miner_status(
+        &self,
+        miner_pubkey: String,
+        is_enabled: bool,
+        status: String,
+    ) -> Result<(), DatabaseError> {
+        let sql = "UPDATE miners SET enabled = ?, status = ? WHERE pubkey = ?";
+
+        if let Ok(db_conn) = self.connection_pool.get().await {
+            let res = db_conn
+                .interact(move |conn| {
+                    conn.execute(sql, params![&is_enabled, &status, &miner_pubkey])
+                })
+                .await;
+
+            match res {
+                Ok(interaction) => match interaction {
+                    Ok(query) => {
+                        if query != 1 {
+                            return Err(DatabaseError::FailedToUpdateRow);
+                        }
+                        return Ok(());
+                    },
+                    Err(e) => {
+                        error!(target: "server_log", "{:?}", e);
+                        return Err(DatabaseError::QueryFailed);
+                    },
+                },
+                Err(e) => {
+                    error!(target: "server_log", "{:?}", e);
+                    return Err(DatabaseError::InteractionFailed);
+                },
+            }
+        } else {
+            return Err(DatabaseError::FailedToGetConnectionFromPool);
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn update_miner_status(
+        &self,
+        miner_pubkey: String,
+        is_enabled: bool,
+        status: String,
+    ) -> Result<(), DatabaseError> {
+        let sql = "UPDATE miners SET enabled = $1, status = $2 WHERE pubkey = $3";
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn.execute(&stmt, &[&is_enabled, &status, &miner_pubkey]).await;
+
+            match res {
+                Ok(num_rows) => {
+                    if num_rows != 1 {
+                        return Err(DatabaseError::FailedToUpdateRow);
+                    }
+                    Ok(())
+                },
+                Err(e) => {
+                    error!(target: "server_log", "{}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+            }
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
     #[cfg(feature = "powered-by-dbms-sqlite")]
     pub async fn get_miner_by_pubkey_str(
         &self,
@@ -1118,6 +1231,164 @@ impl Database {
         }
     }
 
+    #[cfg(feature = "powered-by-dbms-sqlite")]
+    pub async fn add_submission_attempt(
+        &self,
+        attempt: InsertSubmissionAttempt,
+    ) -> Result<(), DatabaseError> {
+        let sql = r#"INSERT INTO submission_attempts (pool_id, challenge_id, attempt, method, difficulty, priority_fee, cu_limit, tip, blockhash, signature, sent_at_ms, confirmed_at_ms, slot, outcome, error_code, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#;
+
+        if let Ok(db_conn) = self.connection_pool.get().await {
+            let res = db_conn
+                .interact(move |conn| {
+                    conn.execute(
+                        sql,
+                        params![
+                            attempt.pool_id,
+                            attempt.challenge_id,
+                            attempt.attempt,
+                            attempt.method,
+                            attempt.difficulty,
+                            attempt.priority_fee,
+                            attempt.cu_limit,
+                            attempt.tip,
+                            attempt.blockhash,
+                            attempt.signature,
+                            attempt.sent_at_ms,
+                            attempt.confirmed_at_ms,
+                            attempt.slot,
+                            attempt.outcome,
+                            attempt.error_code,
+                            attempt.error
+                        ],
+                    )
+                })
+                .await;
+
+            match res {
+                Ok(Ok(1)) => Ok(()),
+                Ok(Ok(_)) => Err(DatabaseError::FailedToInsertRow),
+                Ok(Err(e)) => {
+                    error!(target: "server_log", "{:?}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+                Err(e) => {
+                    error!(target: "server_log", "{:?}", e);
+                    Err(DatabaseError::InteractionFailed)
+                },
+            }
+        } else {
+            return Err(DatabaseError::FailedToGetConnectionFromPool);
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn add_submission_attempt(
+        &self,
+        attempt: InsertSubmissionAttempt,
+    ) -> Result<(), DatabaseError> {
+        let sql = r#"INSERT INTO submission_attempts (pool_id, challenge_id, attempt, method, difficulty, priority_fee, cu_limit, tip, blockhash, signature, sent_at_ms, confirmed_at_ms, slot, outcome, error_code, error) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn
+                .execute(
+                    &stmt,
+                    &[
+                        &attempt.pool_id,
+                        &attempt.challenge_id,
+                        &attempt.attempt,
+                        &attempt.method,
+                        &attempt.difficulty,
+                        &attempt.priority_fee,
+                        &attempt.cu_limit,
+                        &attempt.tip,
+                        &attempt.blockhash,
+                        &attempt.signature,
+                        &attempt.sent_at_ms,
+                        &attempt.confirmed_at_ms,
+                        &attempt.slot,
+                        &attempt.outcome,
+                        &attempt.error_code,
+                        &attempt.error,
+                    ],
+                )
+                .await;
+
+            match res {
+                Ok(1) => Ok(()),
+                Ok(_) => Err(DatabaseError::FailedToInsertRow),
+                Err(e) => {
+                    error!(target: "server_log", "{}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+            }
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-sqlite")]
+    pub async fn get_recent_mine_fees(
+        &self,
+        pool_id: i32,
+        limit: i64,
+    ) -> Result<Vec<MineFee>, DatabaseError> {
+        let sql = r#"SELECT priority_fee, outcome = 'landed' FROM submission_attempts WHERE pool_id = ? AND (outcome = 'landed' OR error_code IN ('rpc_error', 'jito_error', 'tpu_error')) ORDER BY id DESC LIMIT ?"#;
+
+        if let Ok(db_conn) = self.connection_pool.get().await {
+            let res = db_conn
+                .interact(move |conn| {
+                    let mut stmt = conn.prepare(sql)?;
+                    let fees = stmt
+                        .query_map(params![pool_id, limit], |row| {
+                            Ok(MineFee { priority_fee: row.get(0)?, landed: row.get(1)? })
+                        })?
+                        .collect::<Result<Vec<MineFee>, rusqlite::Error>>()?;
+                    Ok::<Vec<MineFee>, rusqlite::Error>(fees)
+                })
+                .await;
+
+            match res {
+                Ok(Ok(fees)) => Ok(fees),
+                Ok(Err(e)) => {
+                    error!(target: "server_log", "Query error: {}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+                Err(e) => {
+                    error!(target: "server_log", "{:?}", e);
+                    Err(DatabaseError::InteractionFailed)
+                },
+            }
+        } else {
+            return Err(DatabaseError::FailedToGetConnectionFromPool);
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn get_recent_mine_fees(
+        &self,
+        pool_id: i32,
+        limit: i64,
+    ) -> Result<Vec<MineFee>, DatabaseError> {
+        let sql = r#"SELECT priority_fee, outcome = 'landed' FROM submission_attempts WHERE pool_id = $1 AND (outcome = 'landed' OR error_code IN ('rpc_error', 'jito_error', 'tpu_error')) ORDER BY id DESC LIMIT $2"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            db_conn
+                .query(&stmt, &[&pool_id, &limit])
+                .await
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| MineFee { priority_fee: row.get(0), landed: row.get(1) })
+                        .collect()
+                })
+                .map_err(From::from)
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
     #[cfg(feature = "powered-by-dbms-postgres")]
     pub async fn get_transaction_id_by_sig(
         &self,
@@ -1337,7 +1608,7 @@ impl Database {
 
     #[cfg(feature = "powered-by-dbms-sqlite")]
     pub async fn _get_miner_rewards(&self, miner_pubkey: String) -> Result<Reward, DatabaseError> {
-        let sql = r#"SELECT r.balance, r.miner_id FROM miners m JOIN rewards r ON m.id = r.miner_id WHERE m.pubkey = ?"#;
+        let sql = r#"SELECT SUM(r.balance), r.miner_id FROM miners m JOIN rewards r ON m.id = r.miner_id WHERE m.pubkey = ? GROUP BY r.miner_id"#;
 
         if let Ok(db_conn) = self.connection_pool.get().await {
             let res = db_conn
@@ -1376,7 +1647,7 @@ impl Database {
 
     #[cfg(feature = "powered-by-dbms-postgres")]
     pub async fn get_miner_rewards(&self, miner_pubkey: String) -> Result<Reward, DatabaseError> {
-        let sql = r#"SELECT r.balance, r.miner_id FROM miners m JOIN rewards r ON m.id = r.miner_id WHERE m.pubkey = $1"#;
+        let sql = r#"SELECT SUM(r.balance)::BIGINT, r.miner_id FROM miners m JOIN rewards r ON m.id = r.miner_id WHERE m.pubkey = $1 GROUP BY r.miner_id"#;
 
         if let Ok(db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
@@ -1392,6 +1663,32 @@ impl Database {
         }
     }
 
+    /// The rewards balance the miner holds in each pool, largest first.
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn get_miner_pool_rewards(
+        &self,
+        miner_id: i64,
+    ) -> Result<Vec<PoolReward>, DatabaseError> {
+        let sql = r#"SELECT pool_id, balance FROM rewards WHERE miner_id = $1 AND balance > 0 ORDER BY balance DESC"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn
+                .query(&stmt, &[&miner_id])
+                .await
+                .map(|rows| {
+                    rows.into_iter()
+                        .map(|row| PoolReward { pool_id: row.get(0), balance: row.get(1) })
+                        .collect()
+                })
+                .map_err(From::from);
+
+            res
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
     #[cfg(feature = "powered-by-dbms-sqlite")]
     pub async fn add_new_reward(&self, reward: InsertReward) -> Result<(), DatabaseError> {
         let sql = r#"INSERT INTO rewards (miner_id, pool_id) VALUES (?, ?)"#;
@@ -1457,7 +1754,7 @@ impl Database {
 
     #[cfg(feature = "powered-by-dbms-sqlite")]
     pub async fn update_rewards(&self, rewards: Vec<UpdateReward>) -> Result<(), DatabaseError> {
-        let sql = r#"UPDATE rewards SET balance = balance + ? WHERE miner_id = ?"#;
+        let sql = r#"INSERT INTO rewards (miner_id, pool_id, balance) VALUES (?, ?, ?) ON CONFLICT (miner_id, pool_id) DO UPDATE SET balance = balance + excluded.balance"#;
 
         if let Ok(db_conn) = self.connection_pool.get().await {
             let res = db_conn
@@ -1468,7 +1765,9 @@ impl Database {
                     let mut stmt = tx.prepare(sql).unwrap();
 
                     for reward in rewards {
-                        rows_affected += stmt.execute((&reward.balance, &reward.miner_id)).unwrap();
+                        rows_affected += stmt
+                            .execute((&reward.miner_id, &reward.pool_id, &reward.balance))
+                            .unwrap();
                     }
                     drop(stmt);
                     tx.commit().unwrap();
@@ -1504,7 +1803,7 @@ impl Database {
 
     #[cfg(feature = "powered-by-dbms-postgres")]
     pub async fn update_rewards(&self, rewards: Vec<UpdateReward>) -> Result<(), DatabaseError> {
-        let sql = r#"UPDATE rewards SET balance = balance + $1 WHERE miner_id = $2"#;
+        let sql = r#"INSERT INTO rewards (miner_id, pool_id, balance) VALUES ($1, $2, $3) ON CONFLICT (miner_id, pool_id) DO UPDATE SET balance = rewards.balance + EXCLUDED.balance"#;
 
         if let Ok(mut db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
@@ -1512,8 +1811,10 @@ impl Database {
             let mut rows_affected = 0;
             let tx = db_conn.transaction().await.unwrap();
             for reward in rewards {
-                rows_affected +=
-                    tx.execute(&stmt, &[&reward.balance, &reward.miner_id]).await.unwrap();
+                rows_affected += tx
+                    .execute(&stmt, &[&reward.miner_id, &reward.pool_id, &reward.balance])
+                    .await
+                    .unwrap();
             }
 
             tx.commit().await.unwrap();
@@ -1532,13 +1833,14 @@ impl Database {
     pub async fn decrease_miner_reward(
         &self,
         miner_id: i64,
+        pool_id: i32,
         rewards_to_decrease: i64,
     ) -> Result<(), DatabaseError> {
-        let sql = "UPDATE rewards SET balance = balance - $1 WHERE miner_id = $2";
+        let sql = "UPDATE rewards SET balance = balance - $1 WHERE miner_id = $2 AND pool_id = $3";
 
         if let Ok(db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
-            let res = db_conn.execute(&stmt, &[&rewards_to_decrease, &miner_id]).await;
+            let res = db_conn.execute(&stmt, &[&rewards_to_decrease, &miner_id, &pool_id]).await;
 
             match res {
                 Ok(num_rows) => {
@@ -1559,9 +1861,10 @@ impl Database {
     }
 
     #[cfg(feature = "powered-by-dbms-sqlite")]
-    pub async fn get_summaries_for_last_24_hours(
+    pub async fn get_miner_summaries(
         &self,
         pool_id: i32,
+        window_hours: i32,
     ) -> Result<Vec<Summary>, DatabaseError> {
         let sql = r#"
 SELECT
@@ -1575,16 +1878,17 @@ SELECT
     FROM
         contributions c
             INNER JOIN miners m ON c.miner_id = m.id
-            INNER JOIN earnings e ON c.challenge_id = e.challenge_id AND c.miner_id = e.miner_id AND e.pool_id = ?
+            INNER JOIN earnings e ON c.challenge_id = e.challenge_id AND c.miner_id = e.miner_id AND e.pool_id = ?1
             INNER JOIN pools p ON e.pool_id = p.id
     WHERE
-        c.created >= datetime('now', '-24 hour') and
+        c.created >= datetime('now', ?2) and
         c.created < 'now' and
         m.enabled = true
     GROUP BY m.pubkey
     ORDER BY percent DESC
         "#;
 
+        let window = format!("-{} hour", window_hours);
         if let Ok(db_conn) = self.connection_pool.get().await {
             let res = db_conn
                 .interact(move |conn| {
@@ -1604,7 +1908,7 @@ SELECT
                     let mut summaries = vec![];
                     let mut stmt = conn.prepare(sql).unwrap();
                     let summary_iter = stmt
-                        .query_map(&[&pool_id], |row| {
+                        .query_map(params![pool_id, window], |row| {
                             Ok::<Summary, rusqlite::Error>(Summary {
                                 miner_pubkey: row.get(0)?,
                                 num_of_contributions: row.get(1)?,
@@ -1637,7 +1941,7 @@ SELECT
                             return Err(DatabaseError::QueryFailed);
                         },
                     },
-                    [] => todo!(),
+                    [] => Ok(vec![]),
                 },
                 Err(e) => {
                     error!(target: "server_log", "{:?}", e);
@@ -1650,9 +1954,10 @@ SELECT
     }
 
     #[cfg(feature = "powered-by-dbms-postgres")]
-    pub async fn get_summaries_for_last_24_hours(
+    pub async fn get_miner_summaries(
         &self,
         pool_id: i32,
+        window_hours: i32,
     ) -> Result<Vec<Summary>, DatabaseError> {
         let sql = r#"
 SELECT
@@ -1669,7 +1974,7 @@ SELECT
             INNER JOIN earnings e ON c.challenge_id = e.challenge_id AND c.miner_id = e.miner_id AND e.pool_id = $1
             INNER JOIN pools p ON e.pool_id = p.id
     WHERE
-        c.created >= NOW() - INTERVAL '24 hour' AND
+        c.created >= NOW() - make_interval(hours => $2) AND
         c.created < NOW() AND
         m.enabled = true
     GROUP BY m.pubkey
@@ -1680,15 +1985,17 @@ SELECT
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
             let mut summaries = vec![];
             let summary_iter =
-                db_conn.query(&stmt, &[&pool_id]).await.unwrap().into_iter().map(|row| Summary {
-                    miner_pubkey: row.get(0),
-                    num_of_contributions: row.get(1),
-                    min_diff: row.get(2),
-                    avg_diff: row.get(3),
-                    max_diff: row.get(4),
-                    earning_sub_total: row.get(5),
-                    percent: row.get(6),
-                });
+                db_conn.query(&stmt, &[&pool_id, &window_hours]).await.unwrap().into_iter().map(
+                    |row| Summary {
+                        miner_pubkey: row.get(0),
+                        num_of_contributions: row.get(1),
+                        min_diff: row.get(2),
+                        avg_diff: row.get(3),
+                        max_diff: row.get(4),
+                        earning_sub_total: row.get(5),
+                        percent: row.get(6),
+                    },
+                );
 
             for summary in summary_iter {
                 summaries.push(summary);
@@ -1699,6 +2006,158 @@ SELECT
         }
     }
 
+    #[cfg(feature = "powered-by-dbms-sqlite")]
+    pub async fn get_pool_report_totals(
+        &self,
+        pool_id: i32,
+        commissions_miner_id: i64,
+        window_hours: i32,
+    ) -> Result<PoolReportTotals, DatabaseError> {
+        let sql = r#"
+SELECT
+        (SELECT COUNT(*) FROM challenges WHERE pool_id = ?1 AND created >= datetime('now', ?3)),
+        (SELECT COUNT(*) FROM challenges WHERE pool_id = ?1 AND rewards_earned > 0 AND created >= datetime('now', ?3)),
+        (SELECT IFNULL(SUM(rewards_earned), 0) FROM challenges WHERE pool_id = ?1 AND created >= datetime('now', ?3)),
+        (SELECT IFNULL(SUM(amount), 0) FROM earnings WHERE pool_id = ?1 AND miner_id = ?2 AND created >= datetime('now', ?3)),
+        (SELECT COUNT(DISTINCT miner_id) FROM earnings WHERE pool_id = ?1 AND miner_id != ?2 AND created >= datetime('now', ?3)),
+        (SELECT COUNT(*) FROM transactions WHERE pool_id = ?1 AND transaction_type = 'mine' AND created >= datetime('now', ?3)),
+        (SELECT COUNT(*) FROM submission_attempts WHERE pool_id = ?1 AND (outcome = 'landed' OR error_code IN ('needs_reset', 'hash_invalid', 'ore_error', 'instruction_error', 'landed_error')) AND created >= datetime('now', ?3)),
+        (SELECT IFNULL(SUM(priority_fee * cu_limit / 1000000 + tip), 0) FROM submission_attempts WHERE pool_id = ?1 AND (outcome = 'landed' OR error_code IN ('needs_reset', 'hash_invalid', 'ore_error', 'instruction_error', 'landed_error')) AND created >= datetime('now', ?3))
+        "#;
+
+        let window = format!("-{} hour", window_hours);
+        if let Ok(db_conn) = self.connection_pool.get().await {
+            let res = db_conn
+                .interact(move |conn| {
+                    conn.query_row_and_then(
+                        sql,
+                        params![pool_id, commissions_miner_id, window],
+                        |row| {
+                            Ok::<PoolReportTotals, rusqlite::Error>(PoolReportTotals {
+                                num_challenges: row.get(0)?,
+                                num_solved: row.get(1)?,
+                                rewards: row.get(2)?,
+                                commissions: row.get(3)?,
+                                num_miners: row.get(4)?,
+                                num_mine_transactions: row.get(5)?,
+                                num_paid_attempts: row.get(6)?,
+                                attempt_fees: row.get(7)?,
+                            })
+                        },
+                    )
+                })
+                .await;
+
+            match res {
+                Ok(Ok(totals)) => Ok(totals),
+                Ok(Err(e)) => {
+                    error!(target: "server_log", "Query error: {}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+                Err(e) => {
+                    error!(target: "server_log", "{:?}", e);
+                    Err(DatabaseError::InteractionFailed)
+                },
+            }
+        } else {
+            return Err(DatabaseError::FailedToGetConnectionFromPool);
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn get_pool_report_totals(
+        &self,
+        pool_id: i32,
+        commissions_miner_id: i64,
+        window_hours: i32,
+    ) -> Result<PoolReportTotals, DatabaseError> {
+        let sql = r#"
+SELECT
+        (SELECT COUNT(*) FROM challenges WHERE pool_id = $1 AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COUNT(*) FROM challenges WHERE pool_id = $1 AND rewards_earned > 0 AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COALESCE(SUM(rewards_earned), 0) FROM challenges WHERE pool_id = $1 AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COALESCE(SUM(amount), 0) FROM earnings WHERE pool_id = $1 AND miner_id = $2 AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COUNT(DISTINCT miner_id) FROM earnings WHERE pool_id = $1 AND miner_id != $2 AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COUNT(*) FROM transactions WHERE pool_id = $1 AND transaction_type = 'mine' AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COUNT(*) FROM submission_attempts WHERE pool_id = $1 AND (outcome = 'landed' OR error_code IN ('needs_reset', 'hash_invalid', 'ore_error', 'instruction_error', 'landed_error')) AND created >= NOW() - make_interval(hours => $3))::bigint,
+        (SELECT COALESCE(SUM(priority_fee * cu_limit / 1000000 + tip), 0) FROM submission_attempts WHERE pool_id = $1 AND (outcome = 'landed' OR error_code IN ('needs_reset', 'hash_invalid', 'ore_error', 'instruction_error', 'landed_error')) AND created >= NOW() - make_interval(hours => $3))::bigint
+        "#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            db_conn
+                .query_one(&stmt, &[&pool_id, &commissions_miner_id, &window_hours])
+                .await
+                .map(|row| PoolReportTotals {
+                    num_challenges: row.get(0),
+                    num_solved: row.get(1),
+                    rewards: row.get(2),
+                    commissions: row.get(3),
+                    num_miners: row.get(4),
+                    num_mine_transactions: row.get(5),
+                    num_paid_attempts: row.get(6),
+                    attempt_fees: row.get(7),
+                })
+                .map_err(From::from)
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-sqlite")]
+    pub async fn get_fee_spends(&self, window_hours: i32) -> Result<Vec<FeeSpend>, DatabaseError> {
+        let sql = r#"SELECT CAST(strftime('%s', 'now') - strftime('%s', created) AS INTEGER), priority_fee * cu_limit / 1000000 + tip FROM submission_attempts WHERE (outcome = 'landed' OR error_code IN ('needs_reset', 'hash_invalid', 'ore_error', 'instruction_error', 'landed_error')) AND created >= datetime('now', ?)"#;
+
+        let window = format!("-{} hour", window_hours);
+        if let Ok(db_conn) = self.connection_pool.get().await {
+            let res = db_conn
+                .interact(move |conn| {
+                    let mut stmt = conn.prepare(sql)?;
+                    let spends = stmt
+                        .query_map(params![window], |row| {
+                            Ok(FeeSpend { age_secs: row.get(0)?, lamports: row.get(1)? })
+                        })?
+                        .collect::<Result<Vec<FeeSpend>, rusqlite::Error>>()?;
+                    Ok::<Vec<FeeSpend>, rusqlite::Error>(spends)
+                })
+                .await;
+
+            match res {
+                Ok(Ok(spends)) => Ok(spends),
+                Ok(Err(e)) => {
+                    error!(target: "server_log", "Query error: {}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+                Err(e) => {
+                    error!(target: "server_log", "{:?}", e);
+                    Err(DatabaseError::InteractionFailed)
+                },
+            }
+        } else {
+            return Err(DatabaseError::FailedToGetConnectionFromPool);
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn get_fee_spends(&self, window_hours: i32) -> Result<Vec<FeeSpend>, DatabaseError> {
+        let sql = r#"SELECT EXTRACT(EPOCH FROM NOW() - created)::bigint, priority_fee * cu_limit / 1000000 + tip FROM submission_attempts WHERE (outcome = 'landed' OR error_code IN ('needs_reset', 'hash_invalid', 'ore_error', 'instruction_error', 'landed_error')) AND created >= NOW() - make_interval(hours => $1)"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            db_conn
+                .query(&stmt, &[&window_hours])
+                .await
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| FeeSpend { age_secs: row.get(0), lamports: row.get(1) })
+                        .collect()
+                })
+                .map_err(From::from)
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
     #[cfg(feature = "powered-by-dbms-postgres")]
     pub async fn signup_enrollment(
         &self,
@@ -1765,4 +2224,128 @@ SELECT
             return Err(DatabaseError::FailedToGetConnectionFromPool);
         }
     }
+
+    /// Returns whether the subscription is verified, a verified target stays verified when only
+    /// the events change.
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn upsert_miner_notification(
+        &self,
+        subscription: InsertMinerNotification,
+    ) -> Result<bool, DatabaseError> {
+        let sql = r#"INSERT INTO miner_notifications (miner_id, channel, target, events, offline_mins, verified, verify_code) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (miner_id) DO UPDATE SET channel = EXCLUDED.channel, target = EXCLUDED.target, events = EXCLUDED.events, offline_mins = EXCLUDED.offline_mins,
+                verified = EXCLUDED.verified OR (miner_notifications.verified AND miner_notifications.channel = EXCLUDED.channel AND miner_notifications.target = EXCLUDED.target),
+                verify_code = CASE WHEN miner_notifications.verified AND miner_notifications.channel = EXCLUDED.channel AND miner_notifications.target = EXCLUDED.target THEN NULL ELSE EXCLUDED.verify_code END
+            RETURNING verified"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn
+                .query_one(
+                    &stmt,
+                    &[
+                        &subscription.miner_id,
+                        &subscription.channel,
+                        &subscription.target,
+                        &subscription.events,
+                        &subscription.offline_mins,
+                        &subscription.verified,
+                        &subscription.verify_code,
+                    ],
+                )
+                .await;
+
+            match res {
+                Ok(row) => Ok(row.get(0)),
+                Err(e) => {
+                    error!(target: "server_log", "{}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+            }
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    /// Marks the subscription verified when `code` is the one sent to its target.
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn verify_miner_notification(
+        &self,
+        miner_id: i64,
+        code: String,
+    ) -> Result<u64, DatabaseError> {
+        let sql = r#"UPDATE miner_notifications SET verified = true, verify_code = NULL
+            WHERE miner_id = $1 AND verify_code = $2 AND NOT verified"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            db_conn.execute(&stmt, &[&miner_id, &code]).await.map_err(From::from)
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn delete_miner_notification(&self, miner_id: i64) -> Result<u64, DatabaseError> {
+        let sql = "DELETE FROM miner_notifications WHERE miner_id = $1";
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            db_conn.execute(&stmt, &[&miner_id]).await.map_err(From::from)
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn get_miner_notifications(&self) -> Result<Vec<MinerNotification>, DatabaseError> {
+        let sql = r#"SELECT n.miner_id, m.pubkey, n.channel, n.target, n.events, n.offline_mins
+            FROM miner_notifications n JOIN miners m ON m.id = n.miner_id WHERE n.verified"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn.query(&stmt, &[]).await;
+
+            match res {
+                Ok(rows) => Ok(rows
+                    .iter()
+                    .map(|row| MinerNotification {
+                        miner_id: row.get(0),
+                        pubkey: row.get(1),
+                        channel: row.get(2),
+                        target: row.get(3),
+                        events: row.get(4),
+                        offline_mins: row.get(5),
+                    })
+                    .collect()),
+                Err(e) => {
+                    error!(target: "server_log", "{}", e);
+                    Err(DatabaseError::QueryFailed)
+                },
+            }
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    pub async fn get_miner_earnings_for_last_hours(
+        &self,
+        miner_id: i64,
+        hours: i32,
+    ) -> Result<i64, DatabaseError> {
+        let sql = r#"SELECT COALESCE(SUM(amount), 0)::BIGINT FROM earnings
+            WHERE miner_id = $1 AND created > CURRENT_TIMESTAMP - make_interval(hours => $2)"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            db_conn
+                .query_one(&stmt, &[&miner_id, &hours])
+                .await
+                .map(|row| row.get(0))
+                .map_err(From::from)
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
 }
@@ -0,0 +1,150 @@
+use {
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        hash::Hash,
+        instruction::Instruction,
+        nonce::state::{State, Versions},
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_instruction, system_program,
+        transaction::Transaction,
+    },
+    std::sync::Mutex,
+    tracing::info,
+};
+
+// MI: one nonce account per wallet and purpose, derived from the wallet so no extra keypair
+// file is needed and a restart finds the same accounts again
+pub const MINE_NONCE_SEED: &str = "hashpoo-mine-nonce";
+pub const CLAIM_NONCE_SEED: &str = "hashpoo-claim-nonce";
+
+/// A durable nonce account owned by a pool wallet. Transactions built on it start with its
+/// advance instruction and use the stored nonce instead of a recent blockhash, so they never
+/// expire, and of all the transactions signed on one nonce value at most one can land.
+pub struct DurableNonce {
+    pubkey: Pubkey,
+    authority: Pubkey,
+    /// Nonce value the next transaction is signed with, None once it may have been used.
+    nonce: Mutex<Option<Hash>>,
+}
+
+impl DurableNonce {
+    /// Loads the nonce account of `authority` for `seed`, creating it paid by `payer` when it
+    /// does not exist yet.
+    pub async fn load_or_create(
+        rpc_client: &RpcClient,
+        authority: &Keypair,
+        payer: &Keypair,
+        seed: &str,
+    ) -> Result<Self, String> {
+        let pubkey = Pubkey::create_with_seed(&authority.pubkey(), seed, &system_program::id())
+            .map_err(|err| format!("Invalid nonce account seed {seed}: {err}"))?;
+        let durable_nonce =
+            DurableNonce { pubkey, authority: authority.pubkey(), nonce: Mutex::new(None) };
+
+        if rpc_client
+            .get_account_with_commitment(&pubkey, rpc_client.commitment())
+            .await
+            .map_err(|err| format!("Failed to load nonce account {pubkey}: {err}"))?
+            .value
+            .is_none()
+        {
+            info!(target: "server_log", "Creating durable nonce account {} of {}", pubkey, authority.pubkey());
+            let rent = rpc_client
+                .get_minimum_balance_for_rent_exemption(State::size())
+                .await
+                .map_err(|err| format!("Failed to get nonce account rent: {err}"))?;
+            let ixs = system_instruction::create_nonce_account_with_seed(
+                &payer.pubkey(),
+                &pubkey,
+                &authority.pubkey(),
+                seed,
+                &authority.pubkey(),
+                rent,
+            );
+            let hash = rpc_client
+                .get_latest_blockhash()
+                .await
+                .map_err(|err| format!("Failed to get latest blockhash: {err}"))?;
+            let tx = Transaction::new_signed_with_payer(
+                &ixs,
+                Some(&payer.pubkey()),
+                &[payer, authority],
+                hash,
+            );
+            rpc_client
+                .send_and_confirm_transaction(&tx)
+                .await
+                .map_err(|err| format!("Failed to create nonce account {pubkey}: {err}"))?;
+        }
+
+        durable_nonce.fetch(rpc_client).await?;
+        info!(target: "server_log", "Using durable nonce account {}", pubkey);
+        Ok(durable_nonce)
+    }
+
+    /// Has to be the first instruction of the transaction.
+    pub fn advance_ix(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.pubkey, &self.authority)
+    }
+
+    /// The nonce value to sign with, read from the account only when the last one may have
+    /// been used.
+    pub async fn blockhash(&self, rpc_client: &RpcClient) -> Result<Hash, String> {
+        if let Some(nonce) = *self.nonce.lock().unwrap() {
+            return Ok(nonce);
+        }
+        self.fetch(rpc_client).await
+    }
+
+    /// Reads the current nonce value from the account.
+    pub async fn fetch(&self, rpc_client: &RpcClient) -> Result<Hash, String> {
+        let account = rpc_client
+            .get_account_with_commitment(&self.pubkey, rpc_client.commitment())
+            .await
+            .map_err(|err| format!("Failed to load nonce account {}: {err}", self.pubkey))?
+            .value
+            .ok_or(format!("Nonce account {} does not exist", self.pubkey))?;
+        let versions: Versions = bincode::deserialize(&account.data)
+            .map_err(|err| format!("Failed to parse nonce account {}: {err}", self.pubkey))?;
+        let State::Initialized(ref data) = versions.state() else {
+            return Err(format!("Nonce account {} is not initialized", self.pubkey));
+        };
+
+        let nonce = data.blockhash();
+        *self.nonce.lock().unwrap() = Some(nonce);
+        Ok(nonce)
+    }
+
+    /// Advances the nonce in a transaction of its own on a recent blockhash, which voids every
+    /// transaction signed on the current value that has not landed yet.
+    pub async fn advance(
+        &self,
+        rpc_client: &RpcClient,
+        payer: &Keypair,
+        authority: &Keypair,
+    ) -> Result<Signature, String> {
+        let hash = rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|err| format!("Failed to get latest blockhash: {err}"))?;
+        let tx = Transaction::new_signed_with_payer(
+            &[self.advance_ix()],
+            Some(&payer.pubkey()),
+            &[payer, authority],
+            hash,
+        );
+        let signature = rpc_client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .map_err(|err| format!("Failed to advance nonce account {}: {err}", self.pubkey))?;
+        self.invalidate();
+        Ok(signature)
+    }
+
+    /// To call once a transaction signed on the current value landed or may still land, the
+    /// next one then reads the value again.
+    pub fn invalidate(&self) {
+        *self.nonce.lock().unwrap() = None;
+    }
+}
@@ -0,0 +1,91 @@
+use {
+    axum::response::sse::Event,
+    chrono::Utc,
+    serde::Serialize,
+    tokio::sync::broadcast::{self, Sender},
+};
+
+// MI: events not consumed within this many newer events are dropped for the lagging subscriber.
+pub const EVENTS_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PoolEvent {
+    NewChallenge {
+        challenge: String,
+    },
+    MissionDispatched {
+        challenge: String,
+        cutoff: i64,
+        num_clients: usize,
+    },
+    BestDifficultyUpdated {
+        difficulty: u32,
+        pubkey: String,
+    },
+    SubmissionAttempt {
+        attempt: u32,
+        difficulty: u32,
+        num_contributions: usize,
+        priority_fee: u64,
+        fee_type: String,
+    },
+    SubmissionResult {
+        attempt: u32,
+        success: bool,
+        signature: Option<String>,
+        error: Option<String>,
+    },
+    RewardsDistributed {
+        challenge: String,
+        difficulty: u32,
+        rewards: f64,
+        balance: f64,
+        num_contributors: usize,
+    },
+    ClaimProcessed {
+        pubkey: String,
+        amount: f64,
+        signature: String,
+    },
+}
+
+#[derive(Serialize)]
+struct TimestampedEvent<'a> {
+    timestamp: i64,
+    #[serde(flatten)]
+    event: &'a PoolEvent,
+}
+
+impl PoolEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            PoolEvent::NewChallenge { .. } => "new_challenge",
+            PoolEvent::MissionDispatched { .. } => "mission_dispatched",
+            PoolEvent::BestDifficultyUpdated { .. } => "best_difficulty_updated",
+            PoolEvent::SubmissionAttempt { .. } => "submission_attempt",
+            PoolEvent::SubmissionResult { .. } => "submission_result",
+            PoolEvent::RewardsDistributed { .. } => "rewards_distributed",
+            PoolEvent::ClaimProcessed { .. } => "claim_processed",
+        }
+    }
+
+    pub fn to_sse(&self) -> Event {
+        let data = TimestampedEvent { timestamp: Utc::now().timestamp(), event: self };
+        Event::default()
+            .event(self.name())
+            .json_data(data)
+            .unwrap_or_else(|_| Event::default().event(self.name()))
+    }
+}
+
+pub fn events_channel() -> Sender<PoolEvent> {
+    let (sender, _) = broadcast::channel(EVENTS_CHANNEL_CAPACITY);
+    sender
+}
+
+/// Publishing never blocks nor fails the caller, events are simply dropped while nobody is
+/// subscribed to the stream.
+pub fn publish(sender: &Sender<PoolEvent>, event: PoolEvent) {
+    let _ = sender.send(event);
+}
@@ -0,0 +1,279 @@
+use {
+    ore_api::consts::ONE_MINUTE,
+    serde::Serialize,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashSet, VecDeque},
+        net::SocketAddr,
+        time::Duration,
+    },
+    tokio::time::Instant,
+};
+
+// MI: estimation windows, the longest window also bounds how long samples are retained.
+const WINDOW_10_MINS: Duration = Duration::from_secs(600);
+const WINDOW_1_HOUR: Duration = Duration::from_secs(3_600);
+const WINDOW_24_HOURS: Duration = Duration::from_secs(86_400);
+
+/// Best submission of a worker for one challenge. A best solution of difficulty `d` takes 2^d
+/// hashes on average to find, so that is the amount of work the worker did in the epoch.
+/// Lower submissions of the same epoch are part of that work, they are not counted again.
+struct ShareSample {
+    at: Instant,
+    pubkey: Pubkey,
+    worker: SocketAddr,
+    challenge: [u8; 32],
+    difficulty: u32,
+    hashes: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct HashrateEstimate {
+    // estimated hashes per second
+    pub hashrate_10m: f64,
+    pub hashrate_1h: f64,
+    pub hashrate_24h: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerHashrate {
+    pub worker: String,
+    pub hashrate: HashrateEstimate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MinerHashrate {
+    pub pubkey: String,
+    pub hashrate: HashrateEstimate,
+    pub workers: Vec<WorkerHashrate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolHashrate {
+    pub hashrate: HashrateEstimate,
+    pub num_miners: usize,
+    pub num_workers: usize,
+}
+
+#[derive(Default)]
+pub struct HashrateEstimator {
+    samples: VecDeque<ShareSample>,
+    /// When the first sample was recorded, windows longer than the time since are not full yet.
+    first_sample_at: Option<Instant>,
+}
+
+impl HashrateEstimator {
+    pub fn new() -> Self {
+        HashrateEstimator { samples: VecDeque::new(), first_sample_at: None }
+    }
+
+    pub fn record(
+        &mut self,
+        pubkey: Pubkey,
+        worker: SocketAddr,
+        challenge: [u8; 32],
+        difficulty: u32,
+    ) {
+        let now = Instant::now();
+        self.first_sample_at.get_or_insert(now);
+        // MI: each worker searches its own nonce range, so the per worker bests add up to the
+        // miner's work. Samples are in arrival order and an epoch is shorter than the shortest
+        // window, so the search stops at samples older than that window
+        let current = self
+            .samples
+            .iter_mut()
+            .rev()
+            .take_while(|sample| now.duration_since(sample.at) <= WINDOW_10_MINS)
+            .find(|sample| sample.worker.eq(&worker) && sample.challenge.eq(&challenge));
+        match current {
+            Some(sample) => {
+                if difficulty > sample.difficulty {
+                    sample.difficulty = difficulty;
+                    sample.hashes = 2f64.powi(difficulty as i32);
+                }
+            },
+            None => self.samples.push_back(ShareSample {
+                at: now,
+                pubkey,
+                worker,
+                challenge,
+                difficulty,
+                hashes: 2f64.powi(difficulty as i32),
+            }),
+        }
+
+        while let Some(sample) = self.samples.front() {
+            if now.duration_since(sample.at) > WINDOW_24_HOURS {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn pool_hashrate(&self) -> PoolHashrate {
+        let mut miners = HashSet::new();
+        let mut workers = HashSet::new();
+        for sample in self.samples.iter() {
+            miners.insert(sample.pubkey);
+            workers.insert(sample.worker);
+        }
+
+        PoolHashrate {
+            hashrate: self.estimate(|_| true),
+            num_miners: miners.len(),
+            num_workers: workers.len(),
+        }
+    }
+
+    pub fn miner_hashrate(&self, pubkey: &Pubkey) -> MinerHashrate {
+        let mut workers: Vec<SocketAddr> = vec![];
+        for sample in self.samples.iter() {
+            if sample.pubkey.eq(pubkey) && !workers.contains(&sample.worker) {
+                workers.push(sample.worker);
+            }
+        }
+
+        MinerHashrate {
+            pubkey: pubkey.to_string(),
+            hashrate: self.estimate(|s| s.pubkey.eq(pubkey)),
+            workers: workers
+                .into_iter()
+                .map(|worker| WorkerHashrate {
+                    worker: worker.to_string(),
+                    hashrate: self.estimate(|s| s.pubkey.eq(pubkey) && s.worker.eq(&worker)),
+                })
+                .collect(),
+        }
+    }
+
+    /// All miners seen within the last 24 hours, highest 1 hour hashrate first.
+    pub fn miners_hashrate(&self) -> Vec<MinerHashrate> {
+        let mut pubkeys: Vec<Pubkey> = vec![];
+        for sample in self.samples.iter() {
+            if !pubkeys.contains(&sample.pubkey) {
+                pubkeys.push(sample.pubkey);
+            }
+        }
+
+        let mut miners: Vec<MinerHashrate> =
+            pubkeys.iter().map(|pubkey| self.miner_hashrate(pubkey)).collect();
+        miners.sort_by(|a, b| b.hashrate.hashrate_1h.total_cmp(&a.hashrate.hashrate_1h));
+        miners
+    }
+
+    pub fn worker_hashrate(&self, worker: &SocketAddr) -> HashrateEstimate {
+        self.estimate(|s| s.worker.eq(worker))
+    }
+
+    fn estimate<F: Fn(&ShareSample) -> bool>(&self, filter: F) -> HashrateEstimate {
+        let now = Instant::now();
+        let (mut hashes_10m, mut hashes_1h, mut hashes_24h) = (0f64, 0f64, 0f64);
+
+        // samples are kept in arrival order, walk backwards from the newest one
+        for sample in self.samples.iter().rev() {
+            let age = now.duration_since(sample.at);
+            if age > WINDOW_24_HOURS {
+                break;
+            }
+            if !filter(sample) {
+                continue;
+            }
+            hashes_24h += sample.hashes;
+            if age <= WINDOW_1_HOUR {
+                hashes_1h += sample.hashes;
+            }
+            if age <= WINDOW_10_MINS {
+                hashes_10m += sample.hashes;
+            }
+        }
+
+        // MI: the first sample is the work of the challenge minute before it, the windows cover no
+        // more time than that since, e.g. after a restart
+        let covered = self.first_sample_at.map_or(Duration::ZERO, |at| now.duration_since(at))
+            + Duration::from_secs(ONE_MINUTE as u64);
+        HashrateEstimate {
+            hashrate_10m: hashes_10m / WINDOW_10_MINS.min(covered).as_secs_f64(),
+            hashrate_1h: hashes_1h / WINDOW_1_HOUR.min(covered).as_secs_f64(),
+            hashrate_24h: hashes_24h / WINDOW_24_HOURS.min(covered).as_secs_f64(),
+        }
+    }
+}
+
+pub fn format_hashrate(hashrate: f64) -> String {
+    let units = ["H/s", "KH/s", "MH/s", "GH/s", "TH/s"];
+    let mut value = hashrate;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < units.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{:.2} {}", value, units[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    // MI: time is paused in the tests, a sample recorded now covers one minute
+    #[tokio::test(start_paused = true)]
+    async fn counts_only_the_best_submission_of_an_epoch() {
+        let mut estimator = HashrateEstimator::new();
+        let pubkey = Pubkey::new_unique();
+        estimator.record(pubkey, worker(1), [1; 32], 10);
+        estimator.record(pubkey, worker(1), [1; 32], 12);
+        estimator.record(pubkey, worker(1), [1; 32], 11);
+
+        let hashrate = estimator.pool_hashrate().hashrate;
+        assert_eq!(hashrate.hashrate_10m, 4096.0 / 60.0);
+        assert_eq!(hashrate.hashrate_24h, 4096.0 / 60.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn adds_up_epochs_and_workers() {
+        let mut estimator = HashrateEstimator::new();
+        let pubkey = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        estimator.record(pubkey, worker(1), [1; 32], 10);
+        estimator.record(pubkey, worker(1), [2; 32], 10);
+        estimator.record(pubkey, worker(2), [2; 32], 11);
+        estimator.record(other, worker(3), [2; 32], 13);
+
+        assert_eq!(estimator.pool_hashrate().hashrate.hashrate_10m, 12288.0 / 60.0);
+        assert_eq!(estimator.pool_hashrate().num_miners, 2);
+        assert_eq!(estimator.pool_hashrate().num_workers, 3);
+
+        let miner = estimator.miner_hashrate(&pubkey);
+        assert_eq!(miner.hashrate.hashrate_10m, 4096.0 / 60.0);
+        assert_eq!(miner.workers.len(), 2);
+        assert_eq!(estimator.worker_hashrate(&worker(2)).hashrate_10m, 2048.0 / 60.0);
+        assert_eq!(estimator.miners_hashrate()[0].pubkey, other.to_string());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn divides_by_the_time_covered_until_the_window_is_full() {
+        let mut estimator = HashrateEstimator::new();
+        let pubkey = Pubkey::new_unique();
+        estimator.record(pubkey, worker(1), [1; 32], 10);
+        tokio::time::advance(Duration::from_secs(1_140)).await;
+        estimator.record(pubkey, worker(1), [2; 32], 10);
+
+        // MI: 20 minutes covered, the 10 minute window is full, the longer ones are not
+        let hashrate = estimator.pool_hashrate().hashrate;
+        assert_eq!(hashrate.hashrate_10m, 1024.0 / 600.0);
+        assert_eq!(hashrate.hashrate_1h, 2048.0 / 1_200.0);
+        assert_eq!(hashrate.hashrate_24h, 2048.0 / 1_200.0);
+    }
+
+    #[test]
+    fn formats_hashrate_units() {
+        assert_eq!(format_hashrate(999.0), "999.00 H/s");
+        assert_eq!(format_hashrate(1_500.0), "1.50 KH/s");
+        assert_eq!(format_hashrate(2_000_000_000_000_000.0), "2000.00 TH/s");
+    }
+}
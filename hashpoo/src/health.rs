@@ -0,0 +1,227 @@
+use {
+    crate::{
+        database::{Database, PoweredByDbms},
+        pools::Pools,
+        rr_database::RrDatabase,
+        AppState, WalletExtension, POWERED_BY_DBMS,
+    },
+    axum::{extract::State, http::StatusCode, Extension, Json},
+    serde::Serialize,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signer::Signer},
+    std::{
+        collections::BTreeMap,
+        future::Future,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tokio::sync::RwLock,
+};
+
+// MI: each dependency probe gives up after this long so a hung rpc or db can't hang the probe.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// MI: process wide heartbeats, stamped by the processors and read by the /health routes.
+// The proof update clock of each pool lives in its ProofSource.
+pub static HEALTH: Health = Health::new();
+
+pub struct Health {
+    /// Last submission loop iteration of each pool, by pool authority.
+    last_submission_loop: Mutex<BTreeMap<Pubkey, u64>>,
+}
+
+impl Health {
+    pub const fn new() -> Self {
+        Health { last_submission_loop: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn submission_loop_tick(&self, pool: Pubkey) {
+        self.last_submission_loop.lock().unwrap().insert(pool, now_secs());
+    }
+
+    /// Seconds since the last iteration of each pool's submission loop.
+    fn submission_loop_ages(&self) -> Vec<(Pubkey, u64)> {
+        let now = now_secs();
+        self.last_submission_loop
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(pool, at)| (*pool, now.saturating_sub(*at)))
+            .collect()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+pub struct HealthConfig {
+    pub min_sol_balance: u64,
+    pub max_proof_age: u64,
+    pub max_submission_stall: u64,
+}
+
+#[derive(Serialize)]
+pub struct HealthCheck {
+    name: &'static str,
+    /// Authority of the pool a per pool check is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<String>,
+    healthy: bool,
+    detail: String,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => HealthCheck { name, pool: None, healthy: true, detail },
+            Err(detail) => HealthCheck { name, pool: None, healthy: false, detail },
+        }
+    }
+
+    fn for_pool(name: &'static str, pool: &Pubkey, result: Result<String, String>) -> Self {
+        HealthCheck { pool: Some(pool.to_string()), ..HealthCheck::new(name, result) }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HealthReport {
+    healthy: bool,
+    checks: Vec<HealthCheck>,
+}
+
+fn report(checks: Vec<HealthCheck>) -> (StatusCode, Json<HealthReport>) {
+    let healthy = checks.iter().all(|check| check.healthy);
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(HealthReport { healthy, checks }))
+}
+
+async fn with_timeout<T>(f: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    tokio::time::timeout(CHECK_TIMEOUT, f)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())))
+}
+
+fn submission_loop_checks(config: &HealthConfig) -> Vec<HealthCheck> {
+    HEALTH
+        .submission_loop_ages()
+        .into_iter()
+        .map(|(pool, age)| {
+            let result = if age <= config.max_submission_stall {
+                Ok(format!("last iteration {}s ago", age))
+            } else {
+                Err(format!("no iteration for {}s", age))
+            };
+            HealthCheck::for_pool("submission_loop", &pool, result)
+        })
+        .collect()
+}
+
+/// Liveness only checks the submission loops, a stall there is the one failure a restart
+/// fixes. Dependency outages are left to readiness.
+pub async fn get_live(
+    Extension(config): Extension<Arc<HealthConfig>>,
+) -> (StatusCode, Json<HealthReport>) {
+    report(submission_loop_checks(&config))
+}
+
+pub async fn get_ready(
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(config): Extension<Arc<HealthConfig>>,
+    Extension(rpc_client): Extension<Arc<RpcClient>>,
+    Extension(wallet): Extension<Arc<WalletExtension>>,
+    Extension(database): Extension<Arc<Database>>,
+    Extension(rr_database): Extension<Arc<RrDatabase>>,
+    Extension(pools): Extension<Arc<Pools>>,
+) -> (StatusCode, Json<HealthReport>) {
+    // MI: without POWERED_BY_DBMS the databases are never connected to, nothing to check
+    let dbms_enabled =
+        POWERED_BY_DBMS.get().unwrap_or(&PoweredByDbms::Unavailable) != &PoweredByDbms::Unavailable;
+    let fee_payer = wallet.fee_wallet.pubkey();
+    let (slot, balance, db, rr_db) = tokio::join!(
+        with_timeout(async { rpc_client.get_slot().await.map_err(|e| e.to_string()) }),
+        with_timeout(async { rpc_client.get_balance(&fee_payer).await.map_err(|e| e.to_string()) }),
+        with_timeout(async {
+            if dbms_enabled {
+                database.ping().await
+            } else {
+                Ok(())
+            }
+        }),
+        with_timeout(async {
+            if dbms_enabled {
+                rr_database.ping().await
+            } else {
+                Ok(())
+            }
+        }),
+    );
+
+    let rpc = slot.map(|slot| format!("slot {}", slot));
+
+    let sol_balance = balance.and_then(|lamports| {
+        if lamports >= config.min_sol_balance {
+            Ok(format!("{} SOL", lamports_to_sol(lamports)))
+        } else {
+            Err(format!(
+                "{} SOL is below the floor of {} SOL",
+                lamports_to_sol(lamports),
+                lamports_to_sol(config.min_sol_balance)
+            ))
+        }
+    });
+
+    let mut checks = vec![HealthCheck::new("rpc", rpc)];
+    let app_state = app_state.read().await;
+    for pool in pools.iter() {
+        // MI: the proof only changes once a challenge is solved, so an idle pool is not stale.
+        let proof_source = &pool.proof_source;
+        let proof_age = proof_source.update_age();
+        let num_sockets = app_state
+            .sockets
+            .values()
+            .filter(|client_connection| client_connection.pool.eq(&pool.authority))
+            .count();
+        let proof_update = if proof_age <= config.max_proof_age {
+            Ok(format!("last update {}s ago", proof_age))
+        } else if num_sockets == 0 {
+            Ok(format!("last update {}s ago, no miners connected", proof_age))
+        } else {
+            Err(format!("no update for {}s with {} miners connected", proof_age, num_sockets))
+        };
+
+        // MI: polling keeps the proof fresh without any subscription, only both down is not ready
+        let num_connected = proof_source.connected_endpoints();
+        let num_endpoints = proof_source.endpoints.len();
+        let latency = proof_source.last_latency().as_secs_f64();
+        let proof_source_status = if num_connected > 0 {
+            Ok(format!(
+                "{}/{} websocket endpoints connected, last update latency {:.1}s",
+                num_connected, num_endpoints, latency
+            ))
+        } else {
+            match proof_source.last_poll_age() {
+                Some(age) if age <= config.max_proof_age => Ok(format!(
+                    "0/{} websocket endpoints connected, polling rpc, last poll {}s ago",
+                    num_endpoints, age
+                )),
+                _ => Err(format!(
+                    "0/{} websocket endpoints connected, rpc polls failing",
+                    num_endpoints
+                )),
+            }
+        };
+
+        checks.push(HealthCheck::for_pool("proof_update", &pool.authority, proof_update));
+        checks.push(HealthCheck::for_pool("proof_source", &pool.authority, proof_source_status));
+    }
+    drop(app_state);
+    if dbms_enabled {
+        checks.push(HealthCheck::new("database", db.map(|_| "reachable".to_string())));
+        checks.push(HealthCheck::new("read_replica", rr_db.map(|_| "reachable".to_string())));
+    }
+    checks.push(HealthCheck::new("fee_payer_sol_balance", sol_balance));
+    checks.extend(submission_loop_checks(&config));
+
+    report(checks)
+}
@@ -0,0 +1,363 @@
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    rand::seq::SliceRandom,
+    reqwest::Client,
+    serde_json::{json, Value},
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_instruction,
+        transaction::Transaction,
+    },
+    std::{str::FromStr, sync::Arc, time::Duration},
+    tokio::{sync::Mutex, time::Instant},
+    tracing::{debug, info, warn},
+};
+
+pub const DEFAULT_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
+
+// MI: a bundle not landed by then has an expired blockhash anyway
+const BUNDLE_STATUS_TIMEOUT: Duration = Duration::from_secs(60);
+const BUNDLE_STATUS_INTERVAL: Duration = Duration::from_secs(2);
+// MI: a single block engine call, a hung one must not hold the submission past its deadline
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct JitoConfig {
+    /// Block engine base url, the json-rpc calls go to `<url>/api/v1/bundles`.
+    pub block_engine_url: String,
+    /// Tip in lamports of an ordinary solution.
+    pub tip: u64,
+    pub tip_cap: u64,
+}
+
+impl JitoConfig {
+    /// Solutions of at least `extra_fee_difficulty` tip `extra_fee_percent` more, the same way
+    /// they pay more priority fee, never above the tip cap.
+    pub fn tip_for(
+        &self,
+        difficulty: u32,
+        extra_fee_difficulty: u32,
+        extra_fee_percent: u64,
+    ) -> u64 {
+        let tip = if difficulty >= extra_fee_difficulty {
+            self.tip.saturating_mul(100u64.saturating_add(extra_fee_percent)).saturating_div(100)
+        } else {
+            self.tip
+        };
+        tip.min(self.tip_cap)
+    }
+}
+
+pub struct JitoClient {
+    config: JitoConfig,
+    http: Client,
+    tip_accounts: Mutex<Vec<Pubkey>>,
+}
+
+impl JitoClient {
+    pub fn new(config: JitoConfig) -> Self {
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build jito http client");
+        JitoClient { config, http, tip_accounts: Mutex::new(vec![]) }
+    }
+
+    pub fn config(&self) -> &JitoConfig {
+        &self.config
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let url = format!("{}/api/v1/bundles", self.config.block_engine_url.trim_end_matches('/'));
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self
+            .http
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| format!("{method} request failed: {err}"))?
+            .json()
+            .await
+            .map_err(|err| format!("{method} response is not json: {err}"))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!("{method} error: {error}"));
+        }
+        response.get("result").cloned().ok_or(format!("{method} response has no result"))
+    }
+
+    /// One of the block engine tip accounts picked at random, they are fetched once and cached.
+    async fn tip_account(&self) -> Result<Pubkey, String> {
+        let mut tip_accounts = self.tip_accounts.lock().await;
+        if tip_accounts.is_empty() {
+            let result = self.call("getTipAccounts", json!([])).await?;
+            *tip_accounts = result
+                .as_array()
+                .ok_or("getTipAccounts result is not an array".to_string())?
+                .iter()
+                .filter_map(|account| account.as_str())
+                .filter_map(|account| Pubkey::from_str(account).ok())
+                .collect();
+        }
+        tip_accounts
+            .choose(&mut rand::thread_rng())
+            .copied()
+            .ok_or("No jito tip account".to_string())
+    }
+
+    /// Returns the bundle id.
+    pub async fn send_bundle(&self, transactions: &[Transaction]) -> Result<String, String> {
+        let mut encoded = vec![];
+        for tx in transactions {
+            let bytes = bincode::serialize(tx)
+                .map_err(|err| format!("Failed to serialize transaction: {err}"))?;
+            encoded.push(BASE64_STANDARD.encode(bytes));
+        }
+        let result = self.call("sendBundle", json!([encoded, { "encoding": "base64" }])).await?;
+        result
+            .as_str()
+            .map(|id| id.to_string())
+            .ok_or("sendBundle result is not a bundle id".to_string())
+    }
+
+    /// None while the block engine has not seen the bundle land yet.
+    pub async fn bundle_status(&self, bundle_id: &str) -> Result<Option<BundleStatus>, String> {
+        let result = self.call("getBundleStatuses", json!([[bundle_id]])).await?;
+        let Some(status) =
+            result.get("value").and_then(|value| value.as_array()).and_then(|value| value.first())
+        else {
+            return Ok(None);
+        };
+        if status.is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(BundleStatus {
+            slot: status.get("slot").and_then(|slot| slot.as_u64()).unwrap_or(0),
+            confirmation_status: status
+                .get("confirmation_status")
+                .and_then(|confirmation_status| confirmation_status.as_str())
+                .unwrap_or("processed")
+                .to_string(),
+            err: status
+                .get("err")
+                .filter(|err| !err.is_null() && err.get("Ok").is_none())
+                .map(|err| err.to_string()),
+        }))
+    }
+
+    /// The instructions plus a transfer of `tip` lamports from the payer to a tip account, signed
+    /// as the single transaction of a bundle. The latest blockhash is fetched unless one is given.
+    pub async fn bundle_transaction(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signer: &Keypair,
+        tip: u64,
+        blockhash: Option<Hash>,
+    ) -> Result<Transaction, String> {
+        let tip_account = self.tip_account().await?;
+        let mut ixs = instructions.to_vec();
+        ixs.push(system_instruction::transfer(&payer.pubkey(), &tip_account, tip));
+
+        let hash = match blockhash {
+            Some(hash) => hash,
+            None => {
+                rpc_client
+                    .get_latest_blockhash_with_commitment(rpc_client.commitment())
+                    .await
+                    .map_err(|err| format!("Failed to get latest blockhash: {err}"))?
+                    .0
+            },
+        };
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+        tx.sign(&[payer, signer], hash);
+        Ok(tx)
+    }
+
+    /// Sends the transaction as a bundle, then polls the bundle status until it is confirmed,
+    /// giving up at `deadline`.
+    pub async fn send_and_confirm(
+        &self,
+        tx: Transaction,
+        tip: u64,
+        deadline: Instant,
+    ) -> Result<Signature, String> {
+        let deadline = deadline.min(Instant::now() + BUNDLE_STATUS_TIMEOUT);
+        if Instant::now() >= deadline {
+            return Err("Bundle deadline passed before sending".to_string());
+        }
+        let signature = tx.signatures[0];
+
+        let bundle_id = self.send_bundle(&[tx]).await?;
+        info!(target: "server_log", "Sent jito bundle {} with tip {} lamports, sig: {}", bundle_id, tip, signature);
+
+        let started = Instant::now();
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            tokio::time::sleep(BUNDLE_STATUS_INTERVAL.min(deadline - now)).await;
+            match self.bundle_status(&bundle_id).await {
+                Ok(Some(status)) => {
+                    if let Some(err) = status.err {
+                        return Err(format!("Bundle {bundle_id} failed: {err}"));
+                    }
+                    debug!(target: "server_log", "Bundle {} is {} at slot {}", bundle_id, status.confirmation_status, status.slot);
+                    if status.confirmation_status == "confirmed"
+                        || status.confirmation_status == "finalized"
+                    {
+                        return Ok(signature);
+                    }
+                },
+                Ok(None) => {},
+                Err(err) => {
+                    warn!(target: "server_log", "Failed to get status of bundle {}: {}", bundle_id, err);
+                },
+            }
+        }
+
+        Err(format!("Bundle {bundle_id} did not land within {}s", started.elapsed().as_secs()))
+    }
+}
+
+pub struct BundleStatus {
+    pub slot: u64,
+    pub confirmation_status: String,
+    pub err: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        axum::{routing::post, Extension, Json, Router},
+        solana_sdk::system_instruction::SystemInstruction,
+        std::sync::Mutex as StdMutex,
+    };
+
+    /// A block engine answering with `status` for every bundle, `Value::Null` while not landed.
+    struct MockBlockEngine {
+        tip_account: Pubkey,
+        status: Value,
+        bundles: StdMutex<Vec<Vec<String>>>,
+    }
+
+    async fn handle(
+        Extension(engine): Extension<Arc<MockBlockEngine>>,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        let result = match request["method"].as_str() {
+            Some("getTipAccounts") => json!([engine.tip_account.to_string()]),
+            Some("sendBundle") => {
+                let encoded = request["params"][0]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|tx| tx.as_str().unwrap().to_string())
+                    .collect();
+                engine.bundles.lock().unwrap().push(encoded);
+                json!("bundle-1")
+            },
+            Some("getBundleStatuses") => json!({ "value": [engine.status] }),
+            _ => return Json(json!({ "error": "unknown method" })),
+        };
+        Json(json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+    }
+
+    async fn mock_block_engine(status: Value) -> (JitoClient, Arc<MockBlockEngine>) {
+        let engine = Arc::new(MockBlockEngine {
+            tip_account: Pubkey::new_unique(),
+            status,
+            bundles: StdMutex::new(vec![]),
+        });
+        let app =
+            Router::new().route("/api/v1/bundles", post(handle)).layer(Extension(engine.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = JitoClient::new(JitoConfig {
+            block_engine_url: format!("http://{addr}/"),
+            tip: 1_000,
+            tip_cap: 10_000,
+        });
+        (client, engine)
+    }
+
+    async fn send(
+        client: &JitoClient,
+        payer: &Keypair,
+        deadline: Instant,
+    ) -> Result<Signature, String> {
+        // MI: never called with the blockhash given
+        let rpc_client = Arc::new(RpcClient::new("http://127.0.0.1:1".to_string()));
+        let tx = client
+            .bundle_transaction(&rpc_client, &[], payer, payer, 5_000, Some(Hash::new_unique()))
+            .await?;
+        client.send_and_confirm(tx, 5_000, deadline).await
+    }
+
+    #[tokio::test]
+    async fn lands_a_bundle_with_the_tip_transfer() {
+        let (client, engine) = mock_block_engine(
+            json!({ "slot": 42, "confirmation_status": "confirmed", "err": { "Ok": null } }),
+        )
+        .await;
+        let payer = Keypair::new();
+
+        let signature =
+            send(&client, &payer, Instant::now() + Duration::from_secs(10)).await.unwrap();
+
+        let bundles = engine.bundles.lock().unwrap();
+        assert_eq!(bundles.len(), 1);
+        let tx: Transaction =
+            bincode::deserialize(&BASE64_STANDARD.decode(&bundles[0][0]).unwrap()).unwrap();
+        assert_eq!(tx.signatures[0], signature);
+        let tip_ix = tx.message.instructions.last().unwrap();
+        assert_eq!(tx.message.account_keys[tip_ix.accounts[1] as usize], engine.tip_account);
+        assert_eq!(
+            bincode::deserialize::<SystemInstruction>(&tip_ix.data).unwrap(),
+            SystemInstruction::Transfer { lamports: 5_000 }
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_a_bundle_landed_with_an_error() {
+        let (client, _) = mock_block_engine(
+            json!({ "slot": 42, "confirmation_status": "confirmed", "err": { "Err": "custom" } }),
+        )
+        .await;
+
+        let err = send(&client, &Keypair::new(), Instant::now() + Duration::from_secs(10))
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("Bundle bundle-1 failed"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn gives_up_at_the_deadline() {
+        let (client, engine) = mock_block_engine(Value::Null).await;
+        let payer = Keypair::new();
+
+        let started = Instant::now();
+        let err = send(&client, &payer, started + Duration::from_millis(300)).await.unwrap_err();
+        assert!(err.contains("did not land"), "{err}");
+        assert!(started.elapsed() < BUNDLE_STATUS_INTERVAL);
+
+        let err = send(&client, &payer, Instant::now()).await.unwrap_err();
+        assert_eq!(err, "Bundle deadline passed before sending");
+        assert_eq!(engine.bundles.lock().unwrap().len(), 1);
+    }
+}
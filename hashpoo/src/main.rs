@@ -1,5 +1,7 @@
 #[cfg(feature = "powered-by-dbms-postgres")]
 use processors::claim_processor::claim_processor;
+#[cfg(feature = "powered-by-dbms-postgres")]
+use processors::miner_notification_processor::miner_notification_processor;
 use {
     self::models::*,
     // ::ore_utils::AccountDeserialize,
@@ -10,27 +12,37 @@ use {
             ConnectInfo, Query, State, WebSocketUpgrade,
         },
         http::{Method, Response, StatusCode},
+        middleware,
         response::IntoResponse,
         routing::{get, post},
         Extension, Json, Router,
     },
     axum_extra::{headers::authorization::Basic, TypedHeader},
     base64::{prelude::BASE64_STANDARD, Engine},
-    bitflags::bitflags,
     clap::{
         builder::{
             styling::{AnsiColor, Effects},
             Styles,
         },
-        command, Parser,
+        Parser, Subcommand,
     },
+    compute_units::ComputeUnitEstimator,
     database::{Database, DatabaseError, PoweredByDbms, PoweredByParams},
     drillx::Solution,
-    dynamic_fee as pfee,
+    dry_run::DryRun,
+    durable_nonce::{DurableNonce, MINE_NONCE_SEED},
+    dynamic_fee::{self as pfee, FeeContext, FeeEstimator},
     futures::{stream::SplitSink, StreamExt},
-    notification::RewardsMessage,
+    hashrate::HashrateEstimator,
+    health::HealthConfig,
+    jito::{JitoClient, JitoConfig},
+    miner_notifications::{ConfirmationCodes, MinerNotificationsConfig},
+    notification::Notification,
     ore_api::consts::EPOCH_DURATION,
+    pools::{PoolInstance, PoolParam, Pools},
+    prefetch::SubmissionPrefetch,
     processors::{
+        alert_processor::{alert_processor, AlertConfig},
         client_message_processor::client_message_processor,
         messaging_all_clients_processor::messaging_all_clients_processor,
         ping_check_processor::ping_check_processor,
@@ -38,15 +50,30 @@ use {
         pool_mine_success_processor::pool_mine_success_processor,
         pool_submission_processor::pool_submission_processor,
         proof_tracking_processor::proof_tracking_processor,
-        ready_clients_processor::ready_clients_processor, reporting_processor::reporting_processor,
+        ready_clients_processor::ready_clients_processor,
+        reporting_processor::reporting_processor,
+        rpc_health_processor::rpc_health_processor,
+        submission_prefetch_processor::submission_prefetch_processor,
+    },
+    profitability::{
+        get_profitability, ProfitabilityConfig, ProfitabilityGuard, BUDGET_WINDOW_HOURS,
     },
-    routes::{get_challenges, get_latest_mine_transaction, get_pool_balance},
+    proof_source::{ProofSource, ProofSourceConfig},
+    rate_limit::{rate_limit_middleware, reserve_connection, RateLimitConfig, RateLimits},
+    reports::{ReportArgs, ReportsConfig},
+    routes::{
+        get_challenges, get_dashboard, get_events, get_latest_mine_transaction, get_miner_hashrate,
+        get_pool_balance, get_pool_hashrate, get_pool_stats,
+    },
+    rpc_pool::{FailoverSender, RpcPool},
     rr_database::RrDatabase,
     serde::Deserialize,
+    signed_request::SignedRequests,
     solana_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client::rpc_client::RpcClientConfig,
     solana_sdk::{
         commitment_config::{CommitmentConfig, CommitmentLevel},
-        native_token::{lamports_to_sol, LAMPORTS_PER_SOL},
+        native_token::{lamports_to_sol, sol_to_lamports, LAMPORTS_PER_SOL},
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signature},
         signer::Signer,
@@ -59,8 +86,8 @@ use {
         ops::ControlFlow,
         path::Path,
         str::FromStr,
-        sync::{atomic::AtomicBool, Arc, Once, OnceLock},
-        time::{SystemTime, UNIX_EPOCH},
+        sync::{atomic::AtomicBool, Arc, OnceLock},
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
     tokio::{
         sync::{mpsc::UnboundedSender, Mutex, RwLock},
@@ -78,14 +105,32 @@ use {
     },
 };
 
+mod admin;
+mod compute_units;
 mod database;
+mod dry_run;
+mod durable_nonce;
 mod dynamic_fee;
+mod events;
+mod hashrate;
+mod health;
+mod jito;
 mod message;
+mod metrics;
+mod miner_notifications;
 mod models;
 mod notification;
+mod pools;
+mod prefetch;
 mod processors;
+mod profitability;
+mod proof_source;
+mod rate_limit;
+mod reports;
 mod routes;
+mod rpc_pool;
 mod rr_database;
+mod signed_request;
 mod tpu;
 mod utils;
 
@@ -131,60 +176,15 @@ const CREATE_ATA_DEDUCTION: u64 = 400_000_000; // grains
 const RPC_RETRIES: usize = 3; // 5
 
 const SUBMIT_LIMIT: u32 = 5;
-const CHECK_LIMIT: usize = 30; // 30
+// MI: how long a landed tx waits for the new challenge before moving on
+const PROOF_UPDATE_TIMEOUT: Duration = Duration::from_secs(30);
 const NO_BEST_SOLUTION_INTERVAL: usize = 5;
 
 static POWERED_BY_DBMS: OnceLock<PoweredByDbms> = OnceLock::new();
 static WALLET_PUBKEY: OnceLock<Pubkey> = OnceLock::new();
-static PAUSED: AtomicBool = AtomicBool::new(false);
-
-static mut MESSAGING_FLAGS: MessagingFlags = MessagingFlags::empty();
-static INIT_MESSAGING_FLAGS: Once = Once::new();
-
-static mut SLACK_WEBHOOK: String = String::new();
-static mut DISCORD_WEBHOOK: String = String::new();
-
-fn get_messaging_flags() -> MessagingFlags {
-    unsafe {
-        INIT_MESSAGING_FLAGS.call_once(|| {
-            let mut exists_slack_webhook = false;
-
-            let key = "SLACK_WEBHOOK";
-            match std::env::var(key) {
-                Ok(val) => {
-                    exists_slack_webhook = true;
-                    SLACK_WEBHOOK = val;
-                },
-                Err(e) => {
-                    warn!(target: "server_log", "couldn't interpret {key}: {e}. slack messaging service unvailable.")
-                },
-            }
-
-            let mut exists_discord_webhook = false;
-            let key = "DISCORD_WEBHOOK";
-            match std::env::var(key) {
-                Ok(val) => {
-                    exists_discord_webhook = true;
-                    DISCORD_WEBHOOK = val;
-                },
-                Err(e) => {
-                    warn!(target: "server_log", "couldn't interpret {key}: {e}. discord messaging service unvailable.")
-                },
-            }
-
-            let mut messaging_flags = MessagingFlags::empty();
-            if exists_slack_webhook {
-                messaging_flags |= MessagingFlags::SLACK;
-            }
-            if exists_discord_webhook {
-                messaging_flags |= MessagingFlags::DISCORD;
-            }
-
-            MESSAGING_FLAGS = messaging_flags;
-        });
-        MESSAGING_FLAGS
-    }
-}
+// MI: set by the operator through the admin api, independent of the per pool submission pauses.
+static OPERATOR_PAUSED: AtomicBool = AtomicBool::new(false);
+static ADMIN_PUBKEY: OnceLock<Pubkey> = OnceLock::new();
 
 #[derive(Clone)]
 enum ClientVersion {
@@ -197,18 +197,32 @@ enum ClientVersion {
 struct ClientConnection {
     pubkey: Pubkey,
     miner_id: i64,
+    /// Authority of the mini pool the worker mines for.
+    pool: Pubkey,
     client_version: ClientVersion,
     socket: Arc<Mutex<SplitSink<WebSocket, Message>>>,
 }
 
 struct WalletExtension {
     miner_wallet: Arc<Keypair>,
-    #[allow(dead_code)]
     fee_wallet: Arc<Keypair>,
 }
 
 struct AppState {
     sockets: HashMap<SocketAddr, ClientConnection>,
+    /// Connection slots reserved by websocket upgrades still in progress.
+    pending_connections: HashMap<SocketAddr, (Pubkey, std::time::Instant)>,
+}
+
+impl AppState {
+    /// Connections of the workers mining for the given pool authority.
+    fn pool_sockets(&self, pool: &Pubkey) -> HashMap<SocketAddr, ClientConnection> {
+        self.sockets
+            .iter()
+            .filter(|(_, client_connection)| client_connection.pool.eq(pool))
+            .map(|(addr, client_connection)| (*addr, client_connection.clone()))
+            .collect()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -223,6 +237,8 @@ struct ClaimsQueue {
 
 pub struct MessageInternalAllClients {
     text: String,
+    /// Only the workers of this pool, None for all connected workers.
+    pool: Option<Pubkey>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -275,21 +291,14 @@ pub struct MineConfig {
     // mining pool db table rowid/identity if powered by dbms
     pool_id: i32,
     stats_enabled: bool,
+    dashboard_enabled: bool,
+    // difficulty a challenge has to reach to count as a solve in pool luck
+    luck_difficulty: u32,
     #[allow(dead_code)]
     commissions_pubkey: String,
     commissions_miner_id: i64,
 }
 
-bitflags! {
-    #[repr(transparent)]
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct MessagingFlags: u8 {
-        const SLACK   = 1 << 0;
-        const DISCORD = 1 << 1;
-        // const EMAIL   = 1 << 2;
-    }
-}
-
 pub struct DifficultyPayload {
     pub solution_difficulty: u32,
     pub expected_min_difficulty: u32,
@@ -341,6 +350,19 @@ struct Args {
 
     #[arg(long, short, action, help = "Enable stats endpoints")]
     stats: bool,
+    #[arg(
+        long,
+        action,
+        help = "Serve the bundled web dashboard at /dashboard, requires stats enabled"
+    )]
+    dashboard: bool,
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Network difficulty pool luck is measured at, a solved challenge whose best difficulty reaches it counts as a solve.",
+        default_value = "24"
+    )]
+    luck_difficulty: u32,
 
     #[arg(
         long,
@@ -350,6 +372,15 @@ struct Args {
     )]
     dynamic_fee_url: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "FEE_STRATEGY",
+        help = "Dynamic fee strategy, e.g. cap(max(helius,local:90),200000). Strategies: auto, helius, triton, alchemy, quiknode, local[:PERCENTILE] or a static fee, combined with max(..), min(..) and cap(.., FEE).",
+        default_value = "auto",
+        global = true
+    )]
+    fee_strategy: String,
+
     #[arg(
         long,
         short,
@@ -390,7 +421,7 @@ struct Args {
         long,
         short,
         value_name = "MESSAGING_DIFF",
-        help = "The min difficulty that will notify messaging channels(if configured) upon transaction success.",
+        help = "The min difficulty that will notify messaging channels(if configured) upon transaction success. Channels may override it with their own min_difficulty.",
         default_value = "25"
     )]
     pub messaging_diff: u32,
@@ -398,6 +429,103 @@ struct Args {
     #[arg(long, help = "Send and confirm transactions using tpu client.", global = true)]
     send_tpu_mine_tx: bool,
 
+    #[arg(
+        long,
+        help = "Send mine transactions as jito bundles with a tip to the block engine.",
+        conflicts_with = "send_tpu_mine_tx",
+        global = true
+    )]
+    send_jito_mine_tx: bool,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Jito block engine url bundles are sent to.",
+        default_value = jito::DEFAULT_BLOCK_ENGINE_URL,
+        global = true
+    )]
+    jito_block_engine_url: String,
+
+    #[arg(
+        long,
+        value_name = "TIP_LAMPORTS",
+        help = "Jito tip of a mine bundle. Solutions of at least the extra fee difficulty tip the extra fee percent more.",
+        default_value = "10000",
+        global = true
+    )]
+    jito_tip: u64,
+
+    #[arg(
+        long,
+        value_name = "TIP_CAP_LAMPORTS",
+        help = "Max jito tip of a mine bundle.",
+        default_value = "200000",
+        global = true
+    )]
+    jito_tip_cap: u64,
+
+    #[arg(
+        long,
+        help = "Race each signed mine transaction to every RPC_URL endpoint at once, and to the tpu too with --send-tpu-mine-tx, first confirmation wins.",
+        global = true
+    )]
+    race_mine_tx: bool,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Safety margin added to the compute units a simulated mine transaction consumes, which becomes its compute unit limit.",
+        default_value = "10",
+        global = true
+    )]
+    cu_limit_margin: u64,
+
+    #[arg(
+        long,
+        help = "Skip simulating mine transactions, always use the fixed 480k compute unit limit, 500k with a reset.",
+        global = true
+    )]
+    fixed_cu_limit: bool,
+
+    #[arg(
+        long,
+        help = "Sign mine and claim transactions on durable nonce accounts of the pool wallets instead of recent blockhashes, the accounts are created on first use.",
+        global = true
+    )]
+    durable_nonce: bool,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "ORE price in SOL, caps the fees of a mine transaction by the value of its expected reward. Unset leaves fees uncapped by reward.",
+        global = true
+    )]
+    ore_price_sol: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        help = "Max share of the expected reward value a mine transaction may spend on fees, priority fee, tip and signatures together.",
+        default_value = "0.5",
+        global = true
+    )]
+    max_fee_fraction: f64,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Max SOL spent on mine transaction fees over the last 24 hours, submissions stop once it is spent.",
+        global = true
+    )]
+    daily_fee_budget: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Shadow mode for staging a build, mine and claim transactions are built and signed as usual but only simulated. Database writes go to DRY_RUN_DATABASE_URL, or are skipped when it is unset.",
+        global = true
+    )]
+    dry_run: bool,
+
     /// Mine with sound notification on/off
     #[arg(
         long,
@@ -407,6 +535,141 @@ struct Args {
         global = true
     )]
     pub no_sound_notification: bool,
+    #[arg(
+        long,
+        value_name = "REQUESTS_PER_MINUTE",
+        help = "Max http requests per minute per IP, and per pubkey for the requests it signed. 0 means unlimited.",
+        default_value = "0"
+    )]
+    pub http_rate_limit: u32,
+    #[arg(
+        long,
+        value_name = "UPGRADES_PER_MINUTE",
+        help = "Max websocket connection attempts per minute per IP, and per pubkey once its signature is verified. 0 means unlimited.",
+        default_value = "0"
+    )]
+    pub ws_rate_limit: u32,
+    #[arg(
+        long,
+        value_name = "REQUESTS_PER_MINUTE",
+        help = "Max http requests per minute per api key(set via API_KEYS env var). 0 means unlimited.",
+        default_value = "0"
+    )]
+    pub api_key_rate_limit: u32,
+    #[arg(
+        long,
+        value_name = "MAX_CONNECTIONS",
+        help = "Max concurrent websocket connections per IP. 0 means unlimited.",
+        default_value = "0"
+    )]
+    pub max_connections_per_ip: usize,
+    #[arg(
+        long,
+        value_name = "MAX_CONNECTIONS",
+        help = "Max concurrent websocket connections per pubkey. 0 means unlimited.",
+        default_value = "0"
+    )]
+    pub max_connections_per_pubkey: usize,
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Fee wallet SOL balance below which /health/ready reports not ready.",
+        default_value = "0.01"
+    )]
+    pub min_sol_balance: f64,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Max age of the last proof update while miners are connected before /health/ready reports not ready.",
+        default_value = "300"
+    )]
+    pub max_proof_age: u64,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Max time without a submission loop iteration before /health/live reports not live.",
+        default_value = "180"
+    )]
+    pub max_submission_stall: u64,
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Alert when the fee payer SOL balance drops below this. 0 disables the alert.",
+        default_value = "0.05"
+    )]
+    pub alert_sol_balance: f64,
+    #[arg(
+        long,
+        value_name = "NUM_FAILURES",
+        help = "Alert after this many consecutive failed mine transaction submissions. 0 disables the alert.",
+        default_value = "5"
+    )]
+    pub alert_submission_failures: u64,
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        help = "Alert when no pool proof update is received for this many minutes. 0 disables the alert.",
+        default_value = "10"
+    )]
+    pub alert_proof_stale: u64,
+    #[arg(
+        long,
+        value_name = "NUM_FAILURES",
+        help = "Alert after this many claim failures within 10 minutes. 0 disables the alert.",
+        default_value = "3"
+    )]
+    pub alert_claim_failures: u64,
+    #[arg(
+        long,
+        value_name = "NUM_RETRIES",
+        help = "Alert after this many db write retries within 10 minutes. 0 disables the alert.",
+        default_value = "20"
+    )]
+    pub alert_db_write_retries: u64,
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        help = "Alert when no miner is connected for this many minutes. 0 disables the alert.",
+        default_value = "30"
+    )]
+    pub alert_no_miners: u64,
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        help = "Min interval between two alerts of the same rule.",
+        default_value = "30"
+    )]
+    pub alert_cooldown: u64,
+    #[arg(
+        long,
+        value_name = "HOURS",
+        help = "Period of the earnings digest sent to subscribed miners. 0 disables the digest.",
+        default_value = "24"
+    )]
+    pub miner_digest_hours: u64,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Poll the pool proof over rpc once no websocket subscription has delivered an update for this long.",
+        default_value = "120"
+    )]
+    pub proof_stale_secs: u64,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Interval of the rpc proof polls while the websocket subscriptions are stale.",
+        default_value = "10"
+    )]
+    pub proof_poll_interval: u64,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Commands {
+    #[command(about = "Build a pool report from the database and exit.")]
+    Report(ReportArgs),
 }
 
 // #[tokio::main(flavor = "multi_thread", worker_threads = 12)]
@@ -471,12 +734,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             metadata.target() == "contribution_log"
         }));
 
+    let env_filter_dryrunlog = tracing_subscriber::EnvFilter::try_from_default_env()
+        .or_else(|_| tracing_subscriber::EnvFilter::try_new("trace"))
+        .unwrap();
+    // MI: simulated transactions of --dry-run, one json record per line
+    let dry_run_logs = tracing_appender::rolling::daily(&log_file_dir, "dry-run.log");
+    let (dry_run_logs, _guard) = tracing_appender::non_blocking(dry_run_logs);
+    let dry_run_log_layer = tracing_subscriber::fmt::layer()
+        .with_writer(dry_run_logs)
+        .with_filter(env_filter_dryrunlog)
+        .with_filter(tracing_subscriber::filter::filter_fn(|metadata| {
+            metadata.target() == "dry_run_log"
+        }));
+
     tracing_subscriber::registry()
         // .with(fmt::layer()) // MI: default layer() which is only required when followed by pure
         // EnvFilter w/o layer definition
         .with(stdout_log_layer)
         .with(server_log_layer)
         .with(contribution_log_layer)
+        .with(dry_run_log_layer)
         .init();
 
     // let file_appender = tracing_appender::rolling::daily("./logs", "ore-ppl-srv.log");
@@ -485,16 +762,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // load envs
     let wallet_path_str = std::env::var("WALLET_PATH").expect("WALLET_PATH must be set.");
+    // MI: several comma separated keypairs run several mini pools in this process
+    let wallet_path_strs: Vec<String> = wallet_path_str
+        .split(',')
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    if wallet_path_strs.is_empty() {
+        panic!("WALLET_PATH must be set.");
+    }
     let key = "FEE_WALLET_PATH";
     let fee_wallet_path_str = match std::env::var(key) {
         Ok(val) => val,
         Err(_) => {
-            info!(target: "server_log", "FEE_WALLET_PATH not set, using WALLET_PATH instead.");
-            wallet_path_str.clone()
+            info!(target: "server_log", "FEE_WALLET_PATH not set, using the first WALLET_PATH instead.");
+            wallet_path_strs[0].clone()
         },
     };
-    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set.");
-    let rpc_ws_url = std::env::var("RPC_WS_URL").expect("RPC_WS_URL must be set.");
+    let rpc_urls = rpc_pool::rpc_urls_from_env();
+    if rpc_urls.is_empty() {
+        panic!("RPC_URL must be set.");
+    }
+    let rpc_ws_urls = ProofSourceConfig::ws_urls_from_env();
+    if rpc_ws_urls.is_empty() {
+        panic!("RPC_WS_URL must be set.");
+    }
+
+    // MI: a dry run never touches the pool database, it uses a shadow one or none at all
+    let dry_run_database_uri =
+        if args.dry_run { std::env::var("DRY_RUN_DATABASE_URL").ok() } else { None };
+    if args.dry_run {
+        warn!(target: "server_log", "Dry run: mine and claim transactions are only simulated.");
+        if dry_run_database_uri.is_none() {
+            warn!(target: "server_log", "Dry run: DRY_RUN_DATABASE_URL not set, the database is not used.");
+            let _ = POWERED_BY_DBMS.set(PoweredByDbms::Unavailable);
+        }
+    }
 
     let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
         let key = "POWERED_BY_DBMS";
@@ -506,16 +809,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    let database_uri: String;
-    let key = "DATABASE_URL";
-    match std::env::var(key) {
-        Ok(val) => {
-            database_uri = val;
-        },
-        Err(_) => database_uri = String::from("ore_priv_pool.db.sqlite3"),
-    }
-
-    let database_rr_uri = std::env::var("DATABASE_RR_URL").expect("DATABASE_RR_URL must be set.");
+    let (database_uri, database_rr_uri) = if args.dry_run {
+        dry_run::database_uris(dry_run_database_uri)
+    } else {
+        let database_uri = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| String::from("ore_priv_pool.db.sqlite3"));
+        let database_rr_uri =
+            std::env::var("DATABASE_RR_URL").expect("DATABASE_RR_URL must be set.");
+        (database_uri, database_rr_uri)
+    };
     let commission_env =
         std::env::var("COMMISSION_PUBKEY").expect("COMMISSION_PUBKEY must be set.");
     let commission_pubkey = match Pubkey::from_str(&commission_env) {
@@ -526,9 +828,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
     };
 
-    let reports_interval_in_hrs: u64 = match std::env::var("REPORTS_INTERVAL_IN_HOURS") {
-        Ok(val) => val.parse().expect("REPORTS_INTERVAL_IN_HOURS must be a positive number"),
-        Err(_) => 6,
+    let reports_config = match ReportsConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        },
     };
 
     let mut dbms_settings = PoweredByParams {
@@ -555,8 +860,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let database = Arc::new(database);
 
-    let messaging_flags = get_messaging_flags();
-
     let priority_fee = Arc::new(args.priority_fee);
     let priority_fee_cap = Arc::new(args.priority_fee_cap);
 
@@ -568,27 +871,98 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let extra_fee_percent = Arc::new(args.extra_fee_percent);
 
     let dynamic_fee = Arc::new(args.dynamic_fee);
-    let dynamic_fee_url = Arc::new(args.dynamic_fee_url);
-
-    let slack_difficulty = Arc::new(args.slack_difficulty);
-    let messaging_diff = Arc::new(args.messaging_diff);
 
     let send_tpu_mine_tx = Arc::new(args.send_tpu_mine_tx);
+    let race_mine_tx = Arc::new(args.race_mine_tx);
+    let jito_client = if args.send_jito_mine_tx {
+        info!(target: "server_log", "Sending mine transactions as jito bundles to {}", args.jito_block_engine_url);
+        Some(Arc::new(JitoClient::new(JitoConfig {
+            block_engine_url: args.jito_block_engine_url.clone(),
+            tip: args.jito_tip,
+            tip_cap: args.jito_tip_cap,
+        })))
+    } else {
+        None
+    };
 
-    let no_sound_notification = Arc::new(args.no_sound_notification);
+    if args.durable_nonce && args.send_tpu_mine_tx && !args.race_mine_tx {
+        return Err("--durable-nonce needs --race-mine-tx along with --send-tpu-mine-tx, the tpu client re-signs with fresh blockhashes.".into());
+    }
 
-    // load wallet
-    let wallet_path = Path::new(&wallet_path_str);
+    if args.dry_run && args.durable_nonce {
+        return Err("--dry-run can't be used with --durable-nonce, creating the nonce accounts would send transactions.".into());
+    }
+    let dry_run = if args.dry_run { Some(Arc::new(DryRun::default())) } else { None };
 
-    if !wallet_path.exists() {
-        tracing::error!(target: "server_log", "❌ Failed to load wallet at: {}", wallet_path_str);
-        return Err("Failed to find wallet path.".into());
+    let cu_estimator = if args.fixed_cu_limit {
+        None
+    } else {
+        Some(Arc::new(ComputeUnitEstimator::new(args.cu_limit_margin)))
+    };
+
+    if args.max_fee_fraction <= 0.0 {
+        return Err("--max-fee-fraction must be above 0.".into());
+    }
+    if args.ore_price_sol.is_some_and(|price| price <= 0.0) {
+        return Err("--ore-price-sol must be above 0.".into());
+    }
+    if args.daily_fee_budget.is_some_and(|budget| budget <= 0.0) {
+        return Err("--daily-fee-budget must be above 0.".into());
+    }
+    let profitability = Arc::new(ProfitabilityGuard::new(ProfitabilityConfig {
+        ore_price_sol: args.ore_price_sol,
+        max_fee_fraction: args.max_fee_fraction,
+        daily_budget_lamports: args.daily_fee_budget.map(sol_to_lamports),
+    }));
+    info!(target: "server_log", "Fee profitability guard: {}", profitability.describe());
+    // MI: the budget window outlives a restart, its spends are in the submission attempts
+    if powered_by_dbms != &PoweredByDbms::Unavailable {
+        match database.get_fee_spends(BUDGET_WINDOW_HOURS as i32).await {
+            Ok(spends) => profitability.restore(&spends),
+            Err(e) => {
+                warn!(target: "server_log", "Failed to restore fees spent from the database: {:?}", e)
+            },
+        }
     }
 
-    let wallet = read_keypair_file(wallet_path)
-        .expect("Failed to load keypair from file: {wallet_path_str}");
-    let wallet_pubkey = wallet.pubkey();
-    info!(target: "server_log", "loaded wallet {}", wallet_pubkey.to_string());
+    let no_sound_notification = Arc::new(args.no_sound_notification);
+
+    // api keys for trusted dashboards, comma separated
+    let api_keys = match std::env::var("API_KEYS") {
+        Ok(val) => {
+            val.split(',').map(|key| key.trim().to_string()).filter(|key| !key.is_empty()).collect()
+        },
+        Err(_) => HashSet::new(),
+    };
+    let rate_limits = Arc::new(RateLimits::new(RateLimitConfig {
+        http_per_minute: args.http_rate_limit,
+        ws_per_minute: args.ws_rate_limit,
+        api_key_per_minute: args.api_key_rate_limit,
+        max_connections_per_ip: args.max_connections_per_ip,
+        max_connections_per_pubkey: args.max_connections_per_pubkey,
+        api_keys,
+    }));
+
+    // load wallets, one mini pool proof per authority keypair
+    let mut wallets = Vec::new();
+    for wallet_path_str in wallet_path_strs.iter() {
+        let wallet_path = Path::new(wallet_path_str);
+
+        if !wallet_path.exists() {
+            tracing::error!(target: "server_log", "❌ Failed to load wallet at: {}", wallet_path_str);
+            return Err("Failed to find wallet path.".into());
+        }
+
+        let wallet = read_keypair_file(wallet_path)
+            .expect("Failed to load keypair from file: {wallet_path_str}");
+        info!(target: "server_log", "loaded wallet {}", wallet.pubkey().to_string());
+        if wallets.iter().any(|loaded: &Keypair| loaded.pubkey() == wallet.pubkey()) {
+            return Err("WALLET_PATH lists the same wallet twice.".into());
+        }
+        wallets.push(wallet);
+    }
+    // MI: the first wallet is the primary pool, the single pool routes keep serving it
+    let wallet_pubkey = wallets[0].pubkey();
 
     // load fee wallet
     let wallet_path = Path::new(&fee_wallet_path_str);
@@ -600,15 +974,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let fee_wallet = read_keypair_file(wallet_path)
         .expect("Failed to load keypair from file: {wallet_path_str}");
-    info!(target: "server_log", "loaded fee wallet {}", wallet.pubkey().to_string());
+    info!(target: "server_log", "loaded fee wallet {}", fee_wallet.pubkey().to_string());
+    let fee_wallet = Arc::new(fee_wallet);
 
     WALLET_PUBKEY.get_or_init(|| wallet_pubkey);
 
+    // admin api operator, defaults to the pool authority wallet
+    let admin_pubkey = match std::env::var("ADMIN_PUBKEY") {
+        Ok(val) => Pubkey::from_str(&val).expect("ADMIN_PUBKEY must be a valid pubkey."),
+        Err(_) => {
+            warn!(target: "server_log", "ADMIN_PUBKEY not set, the admin api falls back to the pool authority wallet {}.", wallet_pubkey);
+            wallet_pubkey
+        },
+    };
+    ADMIN_PUBKEY.get_or_init(|| admin_pubkey);
+
+    if let Some(Commands::Report(report_args)) = args.command.as_ref() {
+        let pool_authorities: Vec<Pubkey> = wallets.iter().map(|wallet| wallet.pubkey()).collect();
+        return reports::report_command(
+            report_args,
+            &database,
+            &pool_authorities,
+            commission_pubkey,
+            args.slack_difficulty.min(args.messaging_diff),
+        )
+        .await;
+    }
+
     info!(target: "server_log", "establishing rpc connection...");
-    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let rpc_pool = Arc::new(RpcPool::new(&rpc_urls));
+    let rpc_client = RpcClient::new_sender(
+        FailoverSender::new(rpc_pool.clone()),
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    );
+    if rpc_urls.len() > 1 {
+        info!(target: "server_log", "Reads fail over across {} rpc endpoints.", rpc_urls.len());
+    }
+    // MI: even a single endpoint gets probed, its health and slot lag are exported
+    let app_rpc_pool = rpc_pool.clone();
+    tokio::spawn(async move {
+        rpc_health_processor(app_rpc_pool).await;
+    });
 
-    info!(target: "server_log", "loading sol balance...");
-    let balance = if let Ok(balance) = rpc_client.get_balance(&wallet_pubkey).await {
+    // MI: the fee wallet pays every transaction, the pool wallets need no sol
+    info!(target: "server_log", "loading sol balance of fee wallet {}...", fee_wallet.pubkey());
+    let balance = if let Ok(balance) = rpc_client.get_balance(&fee_wallet.pubkey()).await {
         balance
     } else {
         return Err("Failed to load balance".into());
@@ -617,81 +1027,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!(target: "server_log", "Balance: {:.9}", balance as f64 / LAMPORTS_PER_SOL as f64);
 
     if balance < 1_000_000 {
-        return Err("Sol balance is too low!".into());
+        return Err("Sol balance of the fee wallet is too low!".into());
     }
+    metrics::METRICS.set_fee_wallet_balance(balance);
 
-    let proof_pubkey = mini_pool_proof_pubkey(wallet_pubkey);
-    debug!(target: "server_log", "MINI POOL PROOF ADDRESS: {:?}", proof_pubkey);
-    let proof = if let Ok(loaded_proof) = get_proof(&rpc_client, proof_pubkey).await {
-        debug!(target: "server_log", "LOADED MINI POOL PROOF: \n{:?}", loaded_proof);
-        loaded_proof
-    } else {
-        error!(target: "server_log", "Failed to load mini pool proof.");
-        info!(target: "server_log", "Creating mini pool proof account...");
-
-        let ix = get_register_ix(wallet_pubkey);
-
-        if let Ok((hash, _slot)) =
-            rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment()).await
-        {
-            let mut tx = Transaction::new_with_payer(&[ix], Some(&wallet_pubkey));
-
-            tx.sign(&[&wallet], hash);
-
-            let result = rpc_client
-                .send_and_confirm_transaction_with_spinner_and_commitment(
-                    &tx,
-                    rpc_client.commitment(),
-                )
-                .await;
-
-            if let Ok(sig) = result {
-                info!(target: "server_log", "Sig: {}", sig.to_string());
-            } else {
-                return Err("Failed to create mini pool proof account".into());
-            }
-        }
-        let proof = if let Ok(loaded_proof) = get_mini_pool_proof(&rpc_client, wallet_pubkey).await
-        {
-            loaded_proof
-        } else {
-            return Err("Failed to get newly created mini pool proof".into());
-        };
-        proof
-    };
-
-    let mine_config: Arc<MineConfig>;
+    let mut commission_miner_id = i64::MAX;
     if powered_by_dbms == &PoweredByDbms::Postgres || powered_by_dbms == &PoweredByDbms::Sqlite {
-        info!(target: "server_log", "Check if the mining pool record exists in the database");
-        let mining_pool = database.get_pool_by_authority_pubkey(wallet_pubkey.to_string()).await;
-
-        match mining_pool {
-            Ok(_) => {},
-            Err(DatabaseError::FailedToGetConnectionFromPool) => {
-                panic!("Failed to get a connection from database pool");
-            },
-            Err(_) => {
-                info!(target: "server_log", "Mining pool record missing from database. Inserting...");
-                let proof_pubkey = utils::mini_pool_proof_pubkey(wallet_pubkey);
-                let pool_pubkey = utils::mini_pool_pubkey(wallet_pubkey);
-                let result = database
-                    .add_new_pool(
-                        wallet_pubkey.to_string(),
-                        proof_pubkey.to_string(),
-                        pool_pubkey.to_string(),
-                    )
-                    .await;
-
-                if result.is_err() {
-                    panic!("Failed to add mining pool record in database");
-                } else {
-                    info!(target: "server_log", "Mining pool record added to database");
-                }
-            },
-        }
-
         info!(target: "server_log", "Check if the commissions receiver record exists in the database");
-        let commission_miner_id;
         match database.get_miner_by_pubkey_str(commission_pubkey.to_string()).await {
             Ok(miner) => {
                 info!(target: "server_log", "Found commissions receiver in db.");
@@ -701,6 +1043,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 info!(target: "server_log", "Failed to get commissions receiver account from database.");
                 info!(target: "server_log", "Inserting Commissions receiver account...");
 
+                // MI: register the primary pool first, the enrollment rewards tracker refers to it
+                ensure_pool_record(&database, wallet_pubkey).await;
                 match database
                     .signup_enrollment(commission_pubkey.to_string(), wallet_pubkey.to_string())
                     .await
@@ -721,85 +1065,440 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             },
         }
+    }
 
-        let mining_pool =
-            database.get_pool_by_authority_pubkey(wallet_pubkey.to_string()).await.unwrap();
+    let shared_state = Arc::new(RwLock::new(AppState {
+        sockets: HashMap::new(),
+        pending_connections: HashMap::new(),
+    }));
 
-        mine_config = Arc::new(MineConfig {
-            pool_id: mining_pool.id,
-            stats_enabled: args.stats,
-            commissions_pubkey: commission_pubkey.to_string(),
-            commissions_miner_id: commission_miner_id,
+    let pongs = Arc::new(RwLock::new(LastPong { pongs: HashMap::new() }));
+
+    let claims_queue = Arc::new(ClaimsQueue { queue: RwLock::new(HashMap::new()) });
+
+    let rpc_client = Arc::new(rpc_client);
+
+    let hashrate_estimator = Arc::new(RwLock::new(HashrateEstimator::new()));
+
+    let event_sender = events::events_channel();
+
+    let health_config = Arc::new(HealthConfig {
+        min_sol_balance: sol_to_lamports(args.min_sol_balance),
+        max_proof_age: args.max_proof_age,
+        max_submission_stall: args.max_submission_stall,
+    });
+
+    let (notification_sender, notification_receiver) =
+        tokio::sync::mpsc::unbounded_channel::<Notification>();
+
+    // channels from NOTIFICATIONS_CONFIG and the legacy slack/discord webhooks
+    let notification_channels =
+        notification::load_notification_channels(args.slack_difficulty.min(args.messaging_diff));
+    if !notification_channels.is_empty() {
+        tokio::spawn(async move {
+            notification::notification_processor(notification_channels, notification_receiver)
+                .await;
         });
+    }
 
-        info!(target: "server_log", "Check if current challenge for pool exists in the database");
-        let challenge = database.get_challenge_by_challenge(proof.challenge.to_vec()).await;
+    let (all_clients_sender, all_clients_receiver) =
+        tokio::sync::mpsc::unbounded_channel::<MessageInternalAllClients>();
 
-        match challenge {
-            Ok(_) => {},
-            Err(DatabaseError::FailedToGetConnectionFromPool) => {
-                panic!("Failed to get a connection from database pool");
-            },
-            Err(_) => {
-                info!(target: "server_log", "Challenge record missing from database. Inserting...");
-                let new_challenge = models::InsertChallenge {
-                    pool_id: mining_pool.id,
-                    challenge: proof.challenge.to_vec(),
-                    rewards_earned: None,
-                };
-                let result = database.add_new_challenge(new_challenge).await;
+    let mut pool_instances = Vec::new();
+    for wallet in wallets {
+        let wallet_pubkey = wallet.pubkey();
+        let proof_pubkey = mini_pool_proof_pubkey(wallet_pubkey);
+        debug!(target: "server_log", "MINI POOL PROOF ADDRESS: {:?}", proof_pubkey);
+        let proof = if let Ok(loaded_proof) = get_proof(&rpc_client, proof_pubkey).await {
+            debug!(target: "server_log", "LOADED MINI POOL PROOF: \n{:?}", loaded_proof);
+            loaded_proof
+        } else {
+            error!(target: "server_log", "Failed to load mini pool proof.");
+            if args.dry_run {
+                return Err("Dry run does not create the mini pool proof account.".into());
+            }
+            info!(target: "server_log", "Creating mini pool proof account...");
+
+            let ix = get_register_ix(wallet_pubkey);
 
-                if result.is_err() {
-                    panic!("Failed to add challenge record in database");
+            if let Ok((hash, _slot)) =
+                rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment()).await
+            {
+                let mut tx = Transaction::new_with_payer(&[ix], Some(&wallet_pubkey));
+
+                tx.sign(&[&wallet], hash);
+
+                let result = rpc_client
+                    .send_and_confirm_transaction_with_spinner_and_commitment(
+                        &tx,
+                        rpc_client.commitment(),
+                    )
+                    .await;
+
+                if let Ok(sig) = result {
+                    info!(target: "server_log", "Sig: {}", sig.to_string());
                 } else {
-                    info!(target: "server_log", "Challenge record added to database");
+                    return Err("Failed to create mini pool proof account".into());
                 }
+            }
+            let proof =
+                if let Ok(loaded_proof) = get_mini_pool_proof(&rpc_client, wallet_pubkey).await {
+                    loaded_proof
+                } else {
+                    return Err("Failed to get newly created mini pool proof".into());
+                };
+            proof
+        };
+
+        // MI: the pool mine success processor refreshes it every few rewards
+        match rpc_client.get_balance(&wallet_pubkey).await {
+            Ok(balance) => metrics::METRICS.set_miner_wallet_balance(wallet_pubkey, balance),
+            Err(e) => {
+                warn!(target: "server_log", "Failed to load sol balance of miner wallet {}: {:?}", wallet_pubkey, e)
             },
         }
-    } else {
-        // NOT POWERED BY DBMS
-        mine_config = Arc::new(MineConfig {
-            pool_id: i32::MAX,
-            stats_enabled: args.stats,
-            commissions_pubkey: commission_pubkey.to_string(),
-            commissions_miner_id: i64::MAX,
+
+        let mine_config: Arc<MineConfig>;
+        if powered_by_dbms == &PoweredByDbms::Postgres || powered_by_dbms == &PoweredByDbms::Sqlite
+        {
+            ensure_pool_record(&database, wallet_pubkey).await;
+            let mining_pool =
+                database.get_pool_by_authority_pubkey(wallet_pubkey.to_string()).await.unwrap();
+
+            mine_config = Arc::new(MineConfig {
+                pool_id: mining_pool.id,
+                stats_enabled: args.stats,
+                dashboard_enabled: args.dashboard,
+                luck_difficulty: args.luck_difficulty,
+                commissions_pubkey: commission_pubkey.to_string(),
+                commissions_miner_id: commission_miner_id,
+            });
+
+            info!(target: "server_log", "Check if current challenge for pool exists in the database");
+            let challenge = database.get_challenge_by_challenge(proof.challenge.to_vec()).await;
+
+            match challenge {
+                Ok(_) => {},
+                Err(DatabaseError::FailedToGetConnectionFromPool) => {
+                    panic!("Failed to get a connection from database pool");
+                },
+                Err(_) => {
+                    info!(target: "server_log", "Challenge record missing from database. Inserting...");
+                    let new_challenge = models::InsertChallenge {
+                        pool_id: mining_pool.id,
+                        challenge: proof.challenge.to_vec(),
+                        rewards_earned: None,
+                    };
+                    let result = database.add_new_challenge(new_challenge).await;
+
+                    if result.is_err() {
+                        panic!("Failed to add challenge record in database");
+                    } else {
+                        info!(target: "server_log", "Challenge record added to database");
+                    }
+                },
+            }
+        } else {
+            // NOT POWERED BY DBMS
+            mine_config = Arc::new(MineConfig {
+                pool_id: i32::MAX,
+                stats_enabled: args.stats,
+                dashboard_enabled: args.dashboard,
+                luck_difficulty: args.luck_difficulty,
+                commissions_pubkey: commission_pubkey.to_string(),
+                commissions_miner_id: i64::MAX,
+            });
+        }
+
+        let epoch_hashes = Arc::new(RwLock::new(EpochHashes {
+            challenge: proof.challenge,
+            best_hash: BestHash { solution: None, difficulty: 0 },
+            contributions: HashMap::new(),
+        }));
+
+        let wallet_extension = Arc::new(WalletExtension {
+            miner_wallet: Arc::new(wallet),
+            fee_wallet: fee_wallet.clone(),
         });
-    }
+        let mine_nonce = if args.durable_nonce {
+            Some(Arc::new(
+                DurableNonce::load_or_create(
+                    &rpc_client,
+                    &wallet_extension.miner_wallet,
+                    &wallet_extension.fee_wallet,
+                    MINE_NONCE_SEED,
+                )
+                .await?,
+            ))
+        } else {
+            None
+        };
+        let proof_ext = Arc::new(Mutex::new(proof));
+        let proof_source = Arc::new(ProofSource::new(proof_ext.clone(), &proof, &rpc_ws_urls));
+        let nonce_ext = Arc::new(Mutex::new(0u64));
 
-    let epoch_hashes = Arc::new(RwLock::new(EpochHashes {
-        challenge: proof.challenge,
-        best_hash: BestHash { solution: None, difficulty: 0 },
-        contributions: HashMap::new(),
-    }));
+        let client_nonce_ranges = Arc::new(RwLock::new(HashMap::new()));
 
-    let wallet_extension = Arc::new(WalletExtension {
-        miner_wallet: Arc::new(wallet),
-        fee_wallet: Arc::new(fee_wallet),
-    });
-    let proof_ext = Arc::new(Mutex::new(proof));
-    let nonce_ext = Arc::new(Mutex::new(0u64));
+        let ready_clients = Arc::new(Mutex::new(HashSet::new()));
+        let paused = Arc::new(AtomicBool::new(false));
 
-    let client_nonce_ranges = Arc::new(RwLock::new(HashMap::new()));
+        // Track pool proof changes over every websocket endpoint, with rpc polls as fallback
+        let proof_source_config = ProofSourceConfig {
+            ws_urls: rpc_ws_urls.clone(),
+            stale_secs: args.proof_stale_secs,
+            poll_interval: Duration::from_secs(args.proof_poll_interval.max(1)),
+        };
+        tokio::spawn({
+            let proof_source = proof_source.clone();
+            let rpc_client = rpc_client.clone();
+            async move {
+                proof_tracking_processor(
+                    proof_source_config,
+                    proof_source,
+                    rpc_client,
+                    wallet_pubkey,
+                )
+                .await;
+            }
+        });
 
-    let shared_state = Arc::new(RwLock::new(AppState { sockets: HashMap::new() }));
-    let ready_clients = Arc::new(Mutex::new(HashSet::new()));
+        let (client_message_sender, client_message_receiver) =
+            tokio::sync::mpsc::unbounded_channel::<ClientMessage>();
+
+        // Handle client messages
+        let app_ready_clients = ready_clients.clone();
+        let app_proof = proof_ext.clone();
+        let app_epoch_hashes = epoch_hashes.clone();
+        let app_client_nonce_ranges = client_nonce_ranges.clone();
+        let app_state = shared_state.clone();
+        let app_pongs = pongs.clone();
+        let app_min_difficulty = min_difficulty.clone();
+        let app_hashrate_estimator = hashrate_estimator.clone();
+        let app_event_sender = event_sender.clone();
+        tokio::spawn(async move {
+            client_message_processor(
+                app_state,
+                client_message_receiver,
+                app_epoch_hashes,
+                app_ready_clients,
+                app_proof,
+                app_client_nonce_ranges,
+                app_pongs,
+                app_hashrate_estimator,
+                app_event_sender,
+                *app_min_difficulty,
+            )
+            .await;
+        });
 
-    let pongs = Arc::new(RwLock::new(LastPong { pongs: HashMap::new() }));
+        // Handle ready clients
+        let app_rpc_client = rpc_client.clone();
+        let app_shared_state = shared_state.clone();
+        let app_proof = proof_ext.clone();
+        let app_epoch_hashes = epoch_hashes.clone();
+        let app_paused = paused.clone();
+        let app_ready_clients = ready_clients.clone();
+        let app_nonce = nonce_ext.clone();
+        let app_client_nonce_ranges = client_nonce_ranges.clone();
+        let app_buffer_time = buffer_time.clone();
+        let app_risk_time = risk_time.clone();
+        let app_profitability = profitability.clone();
+        let app_event_sender = event_sender.clone();
+        tokio::spawn(async move {
+            ready_clients_processor(
+                app_rpc_client,
+                app_shared_state,
+                app_proof,
+                app_epoch_hashes,
+                app_paused,
+                app_ready_clients,
+                app_nonce,
+                app_client_nonce_ranges,
+                app_buffer_time,
+                app_risk_time,
+                app_profitability,
+                app_event_sender,
+            )
+            .await;
+        });
 
-    let claims_queue = Arc::new(ClaimsQueue { queue: RwLock::new(HashMap::new()) });
+        let (mine_success_sender, mine_success_receiver) =
+            tokio::sync::mpsc::unbounded_channel::<MessageInternalMineSuccess>();
+
+        let app_rpc_client = rpc_client.clone();
+        let app_mine_config = mine_config.clone();
+        let app_shared_state = shared_state.clone();
+        let app_proof = proof_ext.clone();
+        let app_proof_source = proof_source.clone();
+        let app_epoch_hashes = epoch_hashes.clone();
+        let app_paused = paused.clone();
+        let app_wallet = wallet_extension.clone();
+        let app_nonce = nonce_ext.clone();
+        let app_dynamic_fee = dynamic_fee.clone();
+        let app_fee_estimator: Arc<dyn FeeEstimator> = Arc::from(pfee::parse_fee_strategy(
+            &args.fee_strategy,
+            &FeeContext {
+                rpc_client: rpc_client.clone(),
+                dynamic_fee_url: args.dynamic_fee_url.clone(),
+                database: database.clone(),
+                pool_id: mine_config.pool_id,
+            },
+        )?);
+        if *dynamic_fee {
+            info!(target: "server_log", "Dynamic fee strategy: {}", app_fee_estimator.describe());
+        }
+        let app_priority_fee = priority_fee.clone();
+        let app_priority_fee_cap = priority_fee_cap.clone();
+        let app_extra_fee_difficulty = extra_fee_difficulty.clone();
+        let app_extra_fee_percent = extra_fee_percent.clone();
+        let app_send_tpu_mine_tx = send_tpu_mine_tx.clone();
+        let app_jito_client = jito_client.clone();
+        let app_mine_nonce = mine_nonce.clone();
+        let app_race_mine_tx = race_mine_tx.clone();
+        let app_rpc_pool = rpc_pool.clone();
+        let app_cu_estimator = cu_estimator.clone();
+        let prefetch = Arc::new(SubmissionPrefetch::new(
+            rpc_client.clone(),
+            if *dynamic_fee { Some(app_fee_estimator.clone()) } else { None },
+            *priority_fee_cap,
+        ));
+        let app_prefetch = prefetch.clone();
+        tokio::spawn(async move {
+            submission_prefetch_processor(app_prefetch).await;
+        });
+        let app_prefetch = prefetch.clone();
+        let app_profitability = profitability.clone();
+        let app_dry_run = dry_run.clone();
+        let app_no_sound_notification = no_sound_notification.clone();
+        let app_database = database.clone();
+        let app_all_clients_sender = all_clients_sender.clone();
+        let app_notification_sender = notification_sender.clone();
+        let app_buffer_time = buffer_time.clone();
+        let app_risk_time = risk_time.clone();
+        let app_event_sender = event_sender.clone();
+        tokio::spawn(async move {
+            pool_submission_processor(
+                app_rpc_client,
+                app_mine_config,
+                app_shared_state,
+                app_proof,
+                app_proof_source,
+                app_epoch_hashes,
+                app_paused,
+                app_wallet,
+                app_nonce,
+                app_dynamic_fee,
+                app_fee_estimator,
+                app_priority_fee,
+                app_priority_fee_cap,
+                app_extra_fee_difficulty,
+                app_extra_fee_percent,
+                app_send_tpu_mine_tx,
+                app_jito_client,
+                app_mine_nonce,
+                app_race_mine_tx,
+                app_rpc_pool,
+                app_cu_estimator,
+                app_prefetch,
+                app_profitability,
+                app_dry_run,
+                app_no_sound_notification,
+                app_database,
+                app_all_clients_sender,
+                mine_success_sender,
+                app_notification_sender,
+                app_buffer_time,
+                app_risk_time,
+                app_event_sender,
+            )
+            .await;
+        });
 
-    let rpc_client = Arc::new(rpc_client);
+        let app_rpc_client = rpc_client.clone();
+        let app_mine_config = mine_config.clone();
+        let app_shared_state = shared_state.clone();
+        let app_database = database.clone();
+        let app_wallet = wallet_extension.clone();
+        let app_event_sender = event_sender.clone();
+        tokio::spawn(async move {
+            pool_mine_success_processor(
+                app_rpc_client,
+                app_mine_config,
+                app_shared_state,
+                app_database,
+                app_wallet,
+                mine_success_receiver,
+                app_event_sender,
+            )
+            .await;
+        });
 
-    let last_challenge = Arc::new(Mutex::new([0u8; 32]));
+        info!(target: "server_log", "Started mini pool {} with proof {}", wallet_pubkey, proof_pubkey);
+        pool_instances.push(Arc::new(PoolInstance {
+            authority: wallet_pubkey,
+            wallet: wallet_extension,
+            mine_config,
+            proof_source,
+            epoch_hashes,
+            paused,
+            ready_clients,
+            client_nonce_ranges,
+            client_channel: client_message_sender,
+        }));
+    }
+    let pools = Arc::new(Pools::new(pool_instances));
+
+    // MI: routes without a `pool` param, admin and the fee payer checks use the primary pool
+    let primary_pool = pools.primary().clone();
+    let wallet_extension = primary_pool.wallet.clone();
+    let mine_config = primary_pool.mine_config.clone();
+    let epoch_hashes = primary_pool.epoch_hashes.clone();
+    let ready_clients = primary_pool.ready_clients.clone();
+    let client_nonce_ranges = primary_pool.client_nonce_ranges.clone();
 
+    // Claims are paid by the pools the miner earned the rewards in
     #[cfg(feature = "powered-by-dbms-postgres")]
     tokio::spawn({
         let rpc_client = rpc_client.clone();
-        let wallet = wallet_extension.clone();
+        let pools = pools.clone();
         let claims_queue = claims_queue.clone();
         let database = database.clone();
+        let event_sender = event_sender.clone();
+        let fee_wallet = fee_wallet.clone();
+        let use_durable_nonce = args.durable_nonce;
+        let dry_run = dry_run.clone();
         async move {
-            claim_processor(claims_queue, rpc_client, wallet.miner_wallet.clone(), database).await;
+            let mut claim_nonces = HashMap::new();
+            if use_durable_nonce {
+                for wallet in pools.iter().map(|pool| &pool.wallet.miner_wallet) {
+                    match DurableNonce::load_or_create(
+                        &rpc_client,
+                        wallet,
+                        &fee_wallet,
+                        durable_nonce::CLAIM_NONCE_SEED,
+                    )
+                    .await
+                    {
+                        Ok(claim_nonce) => {
+                            claim_nonces.insert(wallet.pubkey(), Arc::new(claim_nonce));
+                        },
+                        Err(err) => {
+                            error!(target: "server_log", "Claims of pool {} go without durable nonce: {}", wallet.pubkey(), err);
+                        },
+                    }
+                }
+            }
+            claim_processor(
+                claims_queue,
+                rpc_client,
+                pools,
+                fee_wallet,
+                claim_nonces,
+                dry_run,
+                database,
+                event_sender,
+            )
+            .await;
         }
     });
 
@@ -810,185 +1509,63 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         pong_tracking_processor(app_pongs, app_state).await;
     });
 
-    // Establish webocket connection for tracking pool proof changes.
-    tokio::spawn({
-        let wallet = wallet_extension.clone();
-        let proof = proof_ext.clone();
-        let last_challenge = last_challenge.clone();
-        async move {
-            proof_tracking_processor(
-                rpc_ws_url,
-                wallet.miner_wallet.clone(),
-                proof,
-                last_challenge,
-            )
-            .await;
-        }
-    });
-
-    let (client_message_sender, client_message_receiver) =
-        tokio::sync::mpsc::unbounded_channel::<ClientMessage>();
-
-    // Handle client messages
-    let app_ready_clients = ready_clients.clone();
-    let app_proof = proof_ext.clone();
-    let app_epoch_hashes = epoch_hashes.clone();
-    let app_client_nonce_ranges = client_nonce_ranges.clone();
-    let app_state = shared_state.clone();
-    let app_pongs = pongs.clone();
-    let app_min_difficulty = min_difficulty.clone();
-    tokio::spawn(async move {
-        client_message_processor(
-            app_state,
-            client_message_receiver,
-            app_epoch_hashes,
-            app_ready_clients,
-            app_proof,
-            app_client_nonce_ranges,
-            app_pongs,
-            *app_min_difficulty,
-        )
-        .await;
-    });
-
-    // Handle ready clients
-    // rpc has been Arc-ed above
-    // let rpc_client = Arc::new(rpc_client);
+    // Raise operational alerts through the notification channels
     let app_rpc_client = rpc_client.clone();
+    let app_wallet = wallet_extension.clone();
+    let app_pools = pools.clone();
+    let app_profitability = profitability.clone();
     let app_shared_state = shared_state.clone();
-    let app_proof = proof_ext.clone();
-    let app_epoch_hashes = epoch_hashes.clone();
-    let app_ready_clients = ready_clients.clone();
-    let app_nonce = nonce_ext.clone();
-    let app_client_nonce_ranges = client_nonce_ranges.clone();
-    let app_buffer_time = buffer_time.clone();
-    let app_risk_time = risk_time.clone();
+    let app_notification_sender = notification_sender.clone();
+    let alert_config = AlertConfig {
+        min_sol_balance: sol_to_lamports(args.alert_sol_balance),
+        submission_failures: args.alert_submission_failures,
+        proof_stale_mins: args.alert_proof_stale,
+        claim_failures: args.alert_claim_failures,
+        db_write_retries: args.alert_db_write_retries,
+        no_miners_mins: args.alert_no_miners,
+        cooldown_mins: args.alert_cooldown,
+    };
     tokio::spawn(async move {
-        ready_clients_processor(
+        alert_processor(
+            alert_config,
             app_rpc_client,
+            app_wallet.fee_wallet.pubkey(),
+            app_pools,
+            app_profitability,
             app_shared_state,
-            app_proof,
-            app_epoch_hashes,
-            app_ready_clients,
-            app_nonce,
-            app_client_nonce_ranges,
-            app_buffer_time,
-            app_risk_time,
+            app_notification_sender,
         )
         .await;
     });
 
-    let (slack_message_sender, slack_message_receiver) =
-        tokio::sync::mpsc::unbounded_channel::<RewardsMessage>();
-
-    // if exists slack webhook, handle slack messages to send
-    if messaging_flags.contains(MessagingFlags::SLACK) {
-        tokio::spawn(async move {
-            unsafe {
-                notification::slack_messaging_processor(
-                    SLACK_WEBHOOK.clone(),
-                    slack_message_receiver,
-                )
-                .await;
-            }
-        });
-    }
-
-    let (discord_message_sender, discord_message_receiver) =
-        tokio::sync::mpsc::unbounded_channel::<RewardsMessage>();
-
-    // if exists discord webhook, handle discord messages to send
-    if messaging_flags.contains(MessagingFlags::DISCORD) {
+    // Per-miner notifications, delivered to the webhook or email each miner subscribed with
+    let miner_notifications_config = Arc::new(MinerNotificationsConfig {
+        email: notification::load_miner_email_config(),
+        digest_hours: args.miner_digest_hours,
+    });
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    if powered_by_dbms != &PoweredByDbms::Unavailable {
+        let config = miner_notifications_config.clone();
+        let database = database.clone();
+        let app_state = shared_state.clone();
+        let events = event_sender.subscribe();
         tokio::spawn(async move {
-            unsafe {
-                notification::discord_messaging_processor(
-                    DISCORD_WEBHOOK.clone(),
-                    discord_message_receiver,
-                )
-                .await;
-            }
+            miner_notification_processor(config, database, app_state, events).await;
         });
     }
 
     // Start report routine
-    let app_mine_config = mine_config.clone();
-    let app_database = database.clone();
-    tokio::spawn(async move {
-        reporting_processor(reports_interval_in_hrs, app_mine_config, app_database).await;
-    });
-
-    let (mine_success_sender, mine_success_receiver) =
-        tokio::sync::mpsc::unbounded_channel::<MessageInternalMineSuccess>();
-
-    let (all_clients_sender, all_clients_receiver) =
-        tokio::sync::mpsc::unbounded_channel::<MessageInternalAllClients>();
-
-    let app_rpc_client = rpc_client.clone();
-    let app_mine_config = mine_config.clone();
-    let app_shared_state = shared_state.clone();
-    let app_proof = proof_ext.clone();
-    let app_epoch_hashes = epoch_hashes.clone();
-    let app_wallet = wallet_extension.clone();
-    let app_nonce = nonce_ext.clone();
-    let app_dynamic_fee = dynamic_fee.clone();
-    let app_dynamic_fee_url = dynamic_fee_url.clone();
-    let app_priority_fee = priority_fee.clone();
-    let app_priority_fee_cap = priority_fee_cap.clone();
-    let app_extra_fee_difficulty = extra_fee_difficulty.clone();
-    let app_extra_fee_percent = extra_fee_percent.clone();
-    let app_send_tpu_mine_tx = send_tpu_mine_tx.clone();
-    let app_no_sound_notification = no_sound_notification.clone();
+    let app_pools = pools.clone();
     let app_database = database.clone();
-    let app_all_clients_sender = all_clients_sender.clone();
-    let app_slack_message_sender = slack_message_sender.clone();
-    let app_discord_message_sender = discord_message_sender.clone();
-    let app_slack_difficulty = slack_difficulty.clone();
-    let app_messaging_diff = messaging_diff.clone();
-    let app_buffer_time = buffer_time.clone();
-    let app_risk_time = risk_time.clone();
+    let app_hashrate_estimator = hashrate_estimator.clone();
+    let app_notification_sender = notification_sender.clone();
     tokio::spawn(async move {
-        pool_submission_processor(
-            app_rpc_client,
-            app_mine_config,
-            app_shared_state,
-            app_proof,
-            app_epoch_hashes,
-            app_wallet,
-            app_nonce,
-            app_dynamic_fee,
-            app_dynamic_fee_url,
-            app_priority_fee,
-            app_priority_fee_cap,
-            app_extra_fee_difficulty,
-            app_extra_fee_percent,
-            app_send_tpu_mine_tx,
-            app_no_sound_notification,
+        reporting_processor(
+            reports_config,
+            app_pools,
             app_database,
-            app_all_clients_sender,
-            mine_success_sender,
-            app_slack_message_sender,
-            app_discord_message_sender,
-            app_slack_difficulty,
-            app_messaging_diff,
-            app_buffer_time,
-            app_risk_time,
-        )
-        .await;
-    });
-
-    let app_rpc_client = rpc_client.clone();
-    let app_mine_config = mine_config.clone();
-    let app_shared_state = shared_state.clone();
-    let app_database = database.clone();
-    let app_wallet = wallet_extension.clone();
-    tokio::spawn(async move {
-        pool_mine_success_processor(
-            app_rpc_client,
-            app_mine_config,
-            app_shared_state,
-            app_database,
-            app_wallet,
-            mine_success_receiver,
+            app_hashrate_estimator,
+            app_notification_sender,
         )
         .await;
     });
@@ -1000,7 +1577,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let cors = CorsLayer::new().allow_methods([Method::GET]).allow_origin(tower_http::cors::Any);
 
-    let client_channel = client_message_sender.clone();
+    // Operator admin routes, every request signed by the operator
+    let signed_requests = Arc::new(SignedRequests::default());
+    let admin_routes = Router::new()
+        .route("/v1/admin/pause", post(admin::post_pause))
+        .route("/v1/admin/resume", post(admin::post_resume))
+        .route("/v1/admin/miner/enable", post(admin::post_enable_miner))
+        .route("/v1/admin/miner/disable", post(admin::post_disable_miner))
+        .route("/v1/admin/miner/ban", post(admin::post_ban_miner))
+        .route("/v1/admin/disconnect", post(admin::post_disconnect))
+        .route("/v1/admin/broadcast", post(admin::post_broadcast))
+        .route("/v1/admin/claims/flush", post(admin::post_flush_claims))
+        .route("/v1/admin/state", get(admin::get_state))
+        .route_layer(middleware::from_fn_with_state(
+            signed_requests.clone(),
+            admin::require_operator,
+        ));
+
+    // MI: miner notification subscriptions are only stored in postgres
+    #[cfg(feature = "powered-by-dbms-postgres")]
+    let miner_notification_routes = Router::new()
+        .route(
+            "/v1/miner/notifications",
+            post(miner_notifications::post_miner_notifications)
+                .delete(miner_notifications::delete_miner_notifications),
+        )
+        .route(
+            "/v1/miner/notifications/verify",
+            post(miner_notifications::post_verify_miner_notifications),
+        );
+    #[cfg(not(feature = "powered-by-dbms-postgres"))]
+    let miner_notification_routes = Router::new();
+
     let app_shared_state = shared_state.clone();
     let app = Router::new()
         .route("/v1/ws", get(ws_handler))
@@ -1011,7 +1619,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/v1/sol-balance", get(get_sol_balance))
         .route("/v1/claim", post(post_claim))
         .route("/v1/active-miners", get(get_connected_miners))
+        .route("/v1/hashrate", get(get_pool_hashrate))
+        .route("/v1/miner/hashrate", get(get_miner_hashrate))
         .route("/timestamp", get(get_timestamp))
+        .route("/dashboard", get(get_dashboard))
+        // MI: public, see the /metrics note in .env.example
+        .route("/metrics", get(get_metrics))
+        .route("/health/live", get(health::get_live))
+        .route("/health/ready", get(health::get_ready))
+        .route("/v1/events", get(get_events))
         .route("/v1/miner/balance", get(get_miner_balance))
         .route("/v1/stake-multiplier", get(get_stake_multiplier))
         // App RR Database routes
@@ -1021,17 +1637,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/v1/miner/last-claim", get(get_miner_last_claim))
         .route("/v1/challenges", get(get_challenges))
         .route("/v1/pool", get(routes::get_pool))
+        .route("/v1/pools", get(pools::get_pools))
         .route("/v1/pool/staked", get(routes::get_pool_staked))
         .route("/v1/pool/balance", get(get_pool_balance))
+        .route("/v1/pool/stats", get(get_pool_stats))
         .route("/v1/txns/latest-mine", get(get_latest_mine_transaction))
+        .route("/v1/pool/profitability", get(get_profitability))
+        .route("/v1/dry-run/simulations", get(dry_run::get_simulations))
+        .merge(miner_notification_routes)
+        .merge(admin_routes)
         .with_state(app_shared_state)
         .layer(Extension(database))
         .layer(Extension(rr_database))
         .layer(Extension(wallet_extension))
-        .layer(Extension(client_channel))
         .layer(Extension(rpc_client))
         .layer(Extension(client_nonce_ranges))
         .layer(Extension(claims_queue))
+        .layer(Extension(ready_clients))
+        .layer(Extension(mine_config))
+        .layer(Extension(hashrate_estimator))
+        .layer(Extension(event_sender))
+        .layer(Extension(epoch_hashes))
+        .layer(Extension(all_clients_sender))
+        .layer(Extension(health_config))
+        .layer(Extension(miner_notifications_config))
+        .layer(Extension(Arc::new(ConfirmationCodes::default())))
+        .layer(Extension(signed_requests))
+        .layer(Extension(pools))
+        .layer(Extension(rpc_pool))
+        .layer(Extension(profitability))
+        .layer(Extension(dry_run))
+        .layer(Extension(rate_limits.clone()))
+        .layer(middleware::from_fn_with_state(rate_limits, rate_limit_middleware))
         // Logging
         .layer(
             TraceLayer::new_for_http()
@@ -1053,6 +1690,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Adds the record of a mini pool to the database on its first start.
+async fn ensure_pool_record(database: &Database, authority: Pubkey) {
+    info!(target: "server_log", "Check if the mining pool record of {} exists in the database", authority);
+    match database.get_pool_by_authority_pubkey(authority.to_string()).await {
+        Ok(_) => {},
+        Err(DatabaseError::FailedToGetConnectionFromPool) => {
+            panic!("Failed to get a connection from database pool");
+        },
+        Err(_) => {
+            info!(target: "server_log", "Mining pool record missing from database. Inserting...");
+            let proof_pubkey = utils::mini_pool_proof_pubkey(authority);
+            let pool_pubkey = utils::mini_pool_pubkey(authority);
+            let result = database
+                .add_new_pool(
+                    authority.to_string(),
+                    proof_pubkey.to_string(),
+                    pool_pubkey.to_string(),
+                )
+                .await;
+
+            if result.is_err() {
+                panic!("Failed to add mining pool record in database");
+            } else {
+                info!(target: "server_log", "Mining pool record added to database");
+            }
+        },
+    }
+}
+
 async fn get_pool_authority_pubkey(
     Extension(wallet): Extension<Arc<WalletExtension>>,
 ) -> impl IntoResponse {
@@ -1223,11 +1889,14 @@ async fn get_miner_rewards(
 }
 
 async fn get_last_challenge_contributions(
+    query_params: Query<PoolParam>,
     Extension(rr_database): Extension<Arc<RrDatabase>>,
     Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(pools): Extension<Arc<Pools>>,
 ) -> Result<Json<Vec<ContributionWithPubkey>>, String> {
     if mine_config.stats_enabled {
-        let res = rr_database.get_last_challenge_contributions().await;
+        let pool = pools.select(query_params.pool.as_deref())?;
+        let res = rr_database.get_last_challenge_contributions(pool.mine_config.pool_id).await;
 
         match res {
             Ok(contributions) => Ok(Json(contributions)),
@@ -1350,6 +2019,42 @@ struct ConnectedMinersParams {
     pubkey: Option<String>,
 }
 
+async fn get_metrics(
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(claims_queue): Extension<Arc<ClaimsQueue>>,
+    Extension(pools): Extension<Arc<Pools>>,
+    Extension(rpc_pool): Extension<Arc<RpcPool>>,
+    Extension(profitability): Extension<Arc<ProfitabilityGuard>>,
+    Extension(dry_run): Extension<Option<Arc<DryRun>>>,
+) -> impl IntoResponse {
+    let mut num_ready_clients = 0;
+    for pool in pools.iter() {
+        num_ready_clients += pool.ready_clients.lock().await.len();
+    }
+    let gauges = metrics::StateGauges {
+        connected_sockets: app_state.read().await.sockets.len(),
+        ready_clients: num_ready_clients,
+        claims_queue_depth: claims_queue.queue.read().await.len(),
+    };
+    let proof_sources: Vec<(Pubkey, &ProofSource)> =
+        pools.iter().map(|pool| (pool.authority, &*pool.proof_source)).collect();
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body({
+            let mut body = metrics::METRICS.render(gauges);
+            ProofSource::write_metrics(&proof_sources, &mut body);
+            rpc_pool.write_metrics(&mut body);
+            profitability.write_metrics(&mut body);
+            if let Some(ref dry_run) = dry_run {
+                dry_run.write_metrics(&mut body);
+            }
+            body
+        })
+        .unwrap()
+}
+
 async fn get_connected_miners(
     query_params: Query<ConnectedMinersParams>,
     State(app_state): State<Arc<RwLock<AppState>>>,
@@ -1401,6 +2106,7 @@ async fn post_claim(
     Extension(rpc_client): Extension<Arc<RpcClient>>,
     Extension(database): Extension<Arc<Database>>,
     Extension(claims_queue): Extension<Arc<ClaimsQueue>>,
+    Extension(rate_limits): Extension<Arc<RateLimits>>,
     query_params: Query<ClaimParams>,
 ) -> impl IntoResponse {
     let msg_timestamp = query_params.timestamp;
@@ -1430,6 +2136,10 @@ async fn post_claim(
             signed_msg.extend(amount.to_le_bytes());
 
             if signature.verify(&miner_pubkey.to_bytes(), &signed_msg) {
+                rate_limits
+                    .check_http_pubkey(&miner_pubkey)
+                    .map_err(|(status, msg)| (status, msg.to_string()))?;
+
                 let reader = claims_queue.queue.read().await;
                 let queue = reader.clone();
                 drop(reader);
@@ -1524,6 +2234,8 @@ async fn post_claim(
 #[derive(Deserialize)]
 struct WsQueryParams {
     timestamp: u64,
+    /// Authority of the mini pool to mine for, assigned by the server when missing.
+    pool: Option<String>,
 }
 
 #[debug_handler]
@@ -1532,9 +2244,11 @@ async fn ws_handler(
     TypedHeader(auth_header): TypedHeader<axum_extra::headers::Authorization<Basic>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(app_state): State<Arc<RwLock<AppState>>>,
-    Extension(client_channel): Extension<UnboundedSender<ClientMessage>>,
+    Extension(pools): Extension<Arc<Pools>>,
+    Extension(hashrate_estimator): Extension<Arc<RwLock<HashrateEstimator>>>,
     Extension(database): Extension<Arc<Database>>,
     query_params: Query<WsQueryParams>,
+    Extension(rate_limits): Extension<Arc<RateLimits>>,
 ) -> impl IntoResponse {
     let msg_timestamp = query_params.timestamp;
 
@@ -1558,11 +2272,31 @@ async fn ws_handler(
         }
     });
 
-    let pool_operator_wallet_pubkey = WALLET_PUBKEY.get().unwrap();
-
     // verify client
     if let Ok(user_pubkey) = Pubkey::from_str(pubkey) {
-        if powered_by_dbms == &PoweredByDbms::Postgres || powered_by_dbms == &PoweredByDbms::Sqlite
+        let Ok(signature) = Signature::from_str(signed_msg) else {
+            return Err((StatusCode::UNAUTHORIZED, "Invalid signature"));
+        };
+        if !signature.verify(&user_pubkey.to_bytes(), &msg_timestamp.to_le_bytes()) {
+            return Err((StatusCode::UNAUTHORIZED, "Sig verification failed"));
+        }
+        rate_limits.check_ws_pubkey(&user_pubkey)?;
+
+        let requested_pool = match query_params.pool.as_deref().map(Pubkey::from_str) {
+            Some(Ok(pool)) => Some(pool),
+            Some(Err(_)) => return Err((StatusCode::BAD_REQUEST, "Invalid pool pubkey")),
+            None => None,
+        };
+        let Some(pool) =
+            pools.assign(requested_pool, &user_pubkey, &app_state, &hashrate_estimator).await
+        else {
+            return Err((StatusCode::NOT_FOUND, "Pool is not served by this server"));
+        };
+        let pool_operator_wallet_pubkey = pool.authority;
+        let client_channel = pool.client_channel.clone();
+
+        let miner_id = if powered_by_dbms == &PoweredByDbms::Postgres
+            || powered_by_dbms == &PoweredByDbms::Sqlite
         {
             info!(target: "server_log", "Check if the miner record exists in the database");
             let db_miner = database.get_miner_by_pubkey_str(pubkey.to_string()).await;
@@ -1580,7 +2314,7 @@ async fn ws_handler(
                     miner =
                         database.get_miner_by_pubkey_str(user_pubkey.to_string()).await.unwrap();
 
-                    let wallet_pubkey = *pool_operator_wallet_pubkey; // MI, all clients share operator mini pool(== solo pool)
+                    let wallet_pubkey = pool_operator_wallet_pubkey; // MI, rewards tracker of the pool the miner joined first
 
                     let db_pool =
                         database.get_pool_by_authority_pubkey(wallet_pubkey.to_string()).await;
@@ -1659,28 +2393,7 @@ async fn ws_handler(
                 return Err((StatusCode::UNAUTHORIZED, "pubkey is not authorized to mine"));
             }
 
-            if let Ok(signature) = Signature::from_str(signed_msg) {
-                let ts_msg = msg_timestamp.to_le_bytes();
-
-                if signature.verify(&user_pubkey.to_bytes(), &ts_msg) {
-                    info!(target: "server_log", "Client: {addr} connected with pubkey {pubkey}.");
-                    return Ok(ws.on_upgrade(move |socket| {
-                        handle_socket(
-                            socket,
-                            addr,
-                            user_pubkey,
-                            miner.id,
-                            ClientVersion::V1,
-                            app_state,
-                            client_channel,
-                        )
-                    }));
-                } else {
-                    return Err((StatusCode::UNAUTHORIZED, "Sig verification failed"));
-                }
-            } else {
-                return Err((StatusCode::UNAUTHORIZED, "Invalid signature"));
-            }
+            miner.id
         } else {
             // NO DBMS FOUND
             warn!(target: "server_log", "WARNING: NO DBMS FOUND. NO CLAIM FOR REMOTE MINERS!");
@@ -1703,30 +2416,24 @@ async fn ws_handler(
             //     }
             // };
 
-            if let Ok(signature) = Signature::from_str(signed_msg) {
-                let ts_msg = msg_timestamp.to_le_bytes();
-
-                if signature.verify(&user_pubkey.to_bytes(), &ts_msg) {
-                    info!(target: "server_log", "Client: {addr} connected with pubkey {pubkey}.");
-                    return Ok(ws.on_upgrade(move |socket| {
-                        handle_socket(
-                            socket,
-                            addr,
-                            user_pubkey,
-                            // MI: default miner_id for non-dbms
-                            i64::MAX,
-                            ClientVersion::V1,
-                            app_state,
-                            client_channel,
-                        )
-                    }));
-                } else {
-                    return Err((StatusCode::UNAUTHORIZED, "Sig verification failed"));
-                }
-            } else {
-                return Err((StatusCode::UNAUTHORIZED, "Invalid signature"));
-            }
-        }
+            // MI: default miner_id for non-dbms
+            i64::MAX
+        };
+
+        reserve_connection(&rate_limits, &app_state, addr, user_pubkey).await?;
+        info!(target: "server_log", "Client: {addr} connected with pubkey {pubkey} to pool {pool_operator_wallet_pubkey}.");
+        Ok(ws.on_upgrade(move |socket| {
+            handle_socket(
+                socket,
+                addr,
+                user_pubkey,
+                miner_id,
+                pool_operator_wallet_pubkey,
+                ClientVersion::V1,
+                app_state,
+                client_channel,
+            )
+        }))
     } else {
         return Err((StatusCode::UNAUTHORIZED, "Invalid pubkey"));
     }
@@ -1737,6 +2444,7 @@ async fn handle_socket(
     who: SocketAddr,
     who_pubkey: Pubkey,
     who_miner_id: i64,
+    who_pool: Pubkey,
     client_version: ClientVersion,
     rw_app_state: Arc<RwLock<AppState>>,
     client_channel: UnboundedSender<ClientMessage>,
@@ -1745,6 +2453,7 @@ async fn handle_socket(
         debug!(target: "server_log", "Pinged {who}... pubkey: {who_pubkey}");
     } else {
         error!(target: "server_log", "could not ping {who} pubkey: {who_pubkey}");
+        rw_app_state.write().await.pending_connections.remove(&who);
 
         // if we can't ping we can't do anything, return to close the connection
         return;
@@ -1752,6 +2461,7 @@ async fn handle_socket(
 
     let (sender, mut receiver) = socket.split();
     let mut app_state = rw_app_state.write().await;
+    app_state.pending_connections.remove(&who);
     if app_state.sockets.contains_key(&who) {
         info!(target: "server_log", "Socket addr: {who} already has an active connection");
         return;
@@ -1759,6 +2469,7 @@ async fn handle_socket(
         let new_client_connection = ClientConnection {
             pubkey: who_pubkey,
             miner_id: who_miner_id,
+            pool: who_pool,
             client_version,
             socket: Arc::new(Mutex::new(sender)),
         };
@@ -0,0 +1,350 @@
+use {
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::BTreeMap,
+        fmt::Write,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    },
+};
+
+// MI: process wide registry, fed by the processors and rendered by the /metrics route in the
+// prometheus text exposition format.
+pub static METRICS: Metrics = Metrics::new();
+
+#[derive(Default, Clone, Copy)]
+struct Latency {
+    sum_secs: f64,
+    count: u64,
+}
+
+pub struct Metrics {
+    contributions_accepted: AtomicU64,
+    contributions_rejected: Mutex<BTreeMap<&'static str, u64>>,
+    submission_attempts: AtomicU64,
+    submission_successes: AtomicU64,
+    submission_failures: Mutex<BTreeMap<&'static str, u64>>,
+    /// By pool authority, a pool landing its transactions doesn't hide another one failing.
+    consecutive_submission_failures: Mutex<BTreeMap<Pubkey, u64>>,
+    claim_failures: AtomicU64,
+    priority_fee_paid_lamports: AtomicU64,
+    last_priority_fee_microlamports: AtomicU64,
+    db_write_latency: Mutex<BTreeMap<&'static str, Latency>>,
+    db_write_retries: Mutex<BTreeMap<&'static str, u64>>,
+    rpc_call_latency: Mutex<BTreeMap<&'static str, Latency>>,
+    submission_stage_latency: Mutex<BTreeMap<&'static str, Latency>>,
+    /// By pool authority, the miner wallet of each pool.
+    miner_wallet_sol_balance_lamports: Mutex<BTreeMap<Pubkey, u64>>,
+    fee_wallet_sol_balance_lamports: AtomicU64,
+}
+
+/// Point-in-time gauges owned by the server state rather than the registry.
+pub struct StateGauges {
+    pub connected_sockets: usize,
+    pub ready_clients: usize,
+    pub claims_queue_depth: usize,
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Metrics {
+            contributions_accepted: AtomicU64::new(0),
+            contributions_rejected: Mutex::new(BTreeMap::new()),
+            submission_attempts: AtomicU64::new(0),
+            submission_successes: AtomicU64::new(0),
+            submission_failures: Mutex::new(BTreeMap::new()),
+            consecutive_submission_failures: Mutex::new(BTreeMap::new()),
+            claim_failures: AtomicU64::new(0),
+            priority_fee_paid_lamports: AtomicU64::new(0),
+            last_priority_fee_microlamports: AtomicU64::new(0),
+            db_write_latency: Mutex::new(BTreeMap::new()),
+            db_write_retries: Mutex::new(BTreeMap::new()),
+            rpc_call_latency: Mutex::new(BTreeMap::new()),
+            submission_stage_latency: Mutex::new(BTreeMap::new()),
+            miner_wallet_sol_balance_lamports: Mutex::new(BTreeMap::new()),
+            fee_wallet_sol_balance_lamports: AtomicU64::new(0),
+        }
+    }
+
+    pub fn contribution_accepted(&self) {
+        self.contributions_accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn contribution_rejected(&self, reason: &'static str) {
+        *self.contributions_rejected.lock().unwrap().entry(reason).or_insert(0) += 1;
+    }
+
+    pub fn submission_attempt(&self) {
+        self.submission_attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `fee` is the compute unit price in micro-lamports, `cu_limit` the compute unit limit
+    /// requested for the transaction.
+    pub fn submission_success(&self, pool: Pubkey, fee: u64, cu_limit: u32) {
+        self.submission_successes.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_submission_failures.lock().unwrap().insert(pool, 0);
+        self.priority_fee_paid_lamports
+            .fetch_add(fee.saturating_mul(cu_limit as u64) / 1_000_000, Ordering::Relaxed);
+        self.last_priority_fee_microlamports.store(fee, Ordering::Relaxed);
+    }
+
+    pub fn submission_failure(&self, pool: Pubkey, kind: &'static str) {
+        *self.submission_failures.lock().unwrap().entry(kind).or_insert(0) += 1;
+        *self.consecutive_submission_failures.lock().unwrap().entry(pool).or_insert(0) += 1;
+    }
+
+    pub fn consecutive_submission_failures(&self, pool: &Pubkey) -> u64 {
+        self.consecutive_submission_failures.lock().unwrap().get(pool).copied().unwrap_or(0)
+    }
+
+    pub fn claim_failure(&self) {
+        self.claim_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn claim_failures(&self) -> u64 {
+        self.claim_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn observe_db_write(&self, operation: &'static str, elapsed: Duration) {
+        Self::observe(&self.db_write_latency, operation, elapsed);
+    }
+
+    pub fn db_write_retry(&self, operation: &'static str) {
+        *self.db_write_retries.lock().unwrap().entry(operation).or_insert(0) += 1;
+    }
+
+    pub fn db_write_retries(&self) -> u64 {
+        self.db_write_retries.lock().unwrap().values().sum()
+    }
+
+    pub fn observe_rpc_call(&self, method: &'static str, elapsed: Duration) {
+        Self::observe(&self.rpc_call_latency, method, elapsed);
+    }
+
+    /// Time spent in one stage of preparing a mine transaction after cutoff.
+    pub fn observe_submission_stage(&self, stage: &'static str, elapsed: Duration) {
+        Self::observe(&self.submission_stage_latency, stage, elapsed);
+    }
+
+    pub fn set_miner_wallet_balance(&self, pool: Pubkey, lamports: u64) {
+        self.miner_wallet_sol_balance_lamports.lock().unwrap().insert(pool, lamports);
+    }
+
+    pub fn set_fee_wallet_balance(&self, lamports: u64) {
+        self.fee_wallet_sol_balance_lamports.store(lamports, Ordering::Relaxed);
+    }
+
+    fn observe(
+        latencies: &Mutex<BTreeMap<&'static str, Latency>>,
+        label: &'static str,
+        elapsed: Duration,
+    ) {
+        let mut latencies = latencies.lock().unwrap();
+        let latency = latencies.entry(label).or_default();
+        latency.sum_secs += elapsed.as_secs_f64();
+        latency.count += 1;
+    }
+
+    pub fn render(&self, gauges: StateGauges) -> String {
+        let mut out = String::new();
+
+        write_gauge(
+            &mut out,
+            "hashpoo_connected_sockets",
+            "Websocket connections currently open.",
+            gauges.connected_sockets as f64,
+        );
+        write_gauge(
+            &mut out,
+            "hashpoo_ready_clients",
+            "Clients waiting for their next mining mission.",
+            gauges.ready_clients as f64,
+        );
+        write_gauge(
+            &mut out,
+            "hashpoo_claims_queue_depth",
+            "Claims waiting to be processed.",
+            gauges.claims_queue_depth as f64,
+        );
+
+        write_header(
+            &mut out,
+            "hashpoo_contributions_total",
+            "Miner contributions by outcome.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "hashpoo_contributions_total{{result=\"accepted\",reason=\"\"}} {}",
+            self.contributions_accepted.load(Ordering::Relaxed)
+        );
+        for (reason, count) in self.contributions_rejected.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_contributions_total{{result=\"rejected\",reason=\"{}\"}} {}",
+                reason, count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "hashpoo_submission_attempts_total",
+            "Mine transaction submission attempts.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "hashpoo_submission_attempts_total {}",
+            self.submission_attempts.load(Ordering::Relaxed)
+        );
+        write_header(
+            &mut out,
+            "hashpoo_submission_successes_total",
+            "Mine transactions landed.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "hashpoo_submission_successes_total {}",
+            self.submission_successes.load(Ordering::Relaxed)
+        );
+        write_header(
+            &mut out,
+            "hashpoo_submission_failures_total",
+            "Failed mine transaction submissions by error kind.",
+            "counter",
+        );
+        for (kind, count) in self.submission_failures.lock().unwrap().iter() {
+            let _ =
+                writeln!(out, "hashpoo_submission_failures_total{{kind=\"{}\"}} {}", kind, count);
+        }
+        write_header(
+            &mut out,
+            "hashpoo_consecutive_submission_failures",
+            "Failed mine transaction submissions since the last landed one, by pool.",
+            "gauge",
+        );
+        for (pool, count) in self.consecutive_submission_failures.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_consecutive_submission_failures{{pool=\"{}\"}} {}",
+                pool, count
+            );
+        }
+        write_header(
+            &mut out,
+            "hashpoo_claim_failures_total",
+            "Failed claim transactions.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "hashpoo_claim_failures_total {}",
+            self.claim_failures.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut out,
+            "hashpoo_priority_fee_paid_lamports_total",
+            "Priority fee paid by landed mine transactions.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "hashpoo_priority_fee_paid_lamports_total {}",
+            self.priority_fee_paid_lamports.load(Ordering::Relaxed)
+        );
+        write_gauge(
+            &mut out,
+            "hashpoo_last_priority_fee_microlamports",
+            "Compute unit price of the last landed mine transaction.",
+            self.last_priority_fee_microlamports.load(Ordering::Relaxed) as f64,
+        );
+
+        write_latencies(
+            &mut out,
+            "hashpoo_db_write_seconds",
+            "Database write latency.",
+            "operation",
+            &self.db_write_latency.lock().unwrap(),
+        );
+        write_header(
+            &mut out,
+            "hashpoo_db_write_retries_total",
+            "Database writes retried after a failure.",
+            "counter",
+        );
+        for (operation, count) in self.db_write_retries.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_db_write_retries_total{{operation=\"{}\"}} {}",
+                operation, count
+            );
+        }
+        write_latencies(
+            &mut out,
+            "hashpoo_rpc_call_seconds",
+            "RPC call latency.",
+            "method",
+            &self.rpc_call_latency.lock().unwrap(),
+        );
+        write_latencies(
+            &mut out,
+            "hashpoo_submission_stage_seconds",
+            "Latency of each stage between cutoff and sending a mine transaction.",
+            "stage",
+            &self.submission_stage_latency.lock().unwrap(),
+        );
+
+        write_header(
+            &mut out,
+            "hashpoo_miner_wallet_sol_balance",
+            "SOL balance of the miner wallet, by pool.",
+            "gauge",
+        );
+        for (pool, lamports) in self.miner_wallet_sol_balance_lamports.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_miner_wallet_sol_balance{{pool=\"{}\"}} {}",
+                pool,
+                *lamports as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64
+            );
+        }
+        write_gauge(
+            &mut out,
+            "hashpoo_fee_wallet_sol_balance",
+            "SOL balance of the fee wallet paying the transaction fees.",
+            self.fee_wallet_sol_balance_lamports.load(Ordering::Relaxed) as f64
+                / solana_sdk::native_token::LAMPORTS_PER_SOL as f64,
+        );
+
+        out
+    }
+}
+
+pub(crate) fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+pub(crate) fn write_gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    write_header(out, name, help, "gauge");
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn write_latencies(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    latencies: &BTreeMap<&'static str, Latency>,
+) {
+    write_header(out, name, help, "summary");
+    for (value, latency) in latencies.iter() {
+        let _ = writeln!(out, "{}_sum{{{}=\"{}\"}} {}", name, label, value, latency.sum_secs);
+        let _ = writeln!(out, "{}_count{{{}=\"{}\"}} {}", name, label, value, latency.count);
+    }
+}
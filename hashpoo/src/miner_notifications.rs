@@ -0,0 +1,595 @@
+use {
+    crate::notification::{MinerEmailConfig, Notification, Notifier},
+    axum::{http::StatusCode, Json},
+    futures::future::BoxFuture,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::{
+        collections::HashMap,
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+#[cfg(feature = "powered-by-dbms-postgres")]
+use {
+    crate::{
+        database::{Database, PoweredByDbms},
+        models::InsertMinerNotification,
+        rate_limit::RateLimits,
+        signed_request::SignedRequests,
+        POWERED_BY_DBMS,
+    },
+    axum::{
+        body::Bytes,
+        extract::{FromRequestParts, OriginalUri, Query},
+        http::Method,
+        Extension,
+    },
+    axum_extra::{
+        headers::{authorization::Basic, Authorization},
+        TypedHeader,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::sync::Arc,
+    tracing::{error, info},
+};
+
+const DEFAULT_OFFLINE_MINS: u32 = 10;
+const MAX_OFFLINE_MINS: u32 = 1440;
+const MAX_TARGET_LEN: usize = 512;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+// MI: confirmation emails a miner may trigger, and an address may receive, per window
+const MAX_CODES_PER_MINER: usize = 3;
+const MAX_CODES_PER_ADDRESS: usize = 3;
+const CODE_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+type MinerNotificationResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+pub struct MinerNotificationsConfig {
+    /// None when the operator hasn't set up an smtp account for miners.
+    pub email: Option<MinerEmailConfig>,
+    /// Earnings digest period, 0 disables the digest.
+    pub digest_hours: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MinerEvent {
+    /// All workers of the miner disconnected for `offline_mins`.
+    Offline,
+    WorkersDropped,
+    EarningsDigest,
+    ClaimCompleted,
+}
+
+impl MinerEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MinerEvent::Offline => "offline",
+            MinerEvent::WorkersDropped => "workers_dropped",
+            MinerEvent::EarningsDigest => "earnings_digest",
+            MinerEvent::ClaimCompleted => "claim_completed",
+        }
+    }
+
+    /// Parses the comma separated event names stored with a subscription.
+    pub fn parse_list(events: &str) -> Vec<MinerEvent> {
+        events
+            .split(',')
+            .filter_map(|event| match event.trim() {
+                "offline" => Some(MinerEvent::Offline),
+                "workers_dropped" => Some(MinerEvent::WorkersDropped),
+                "earnings_digest" => Some(MinerEvent::EarningsDigest),
+                "claim_completed" => Some(MinerEvent::ClaimCompleted),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MinerChannel {
+    Webhook,
+    Email,
+}
+
+impl MinerChannel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MinerChannel::Webhook => "webhook",
+            MinerChannel::Email => "email",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MinerAuthParams {
+    timestamp: u64,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyCodeParam {
+    code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MinerNotificationRequest {
+    channel: MinerChannel,
+    /// https url for a webhook, address for an email.
+    target: String,
+    events: Vec<MinerEvent>,
+    offline_mins: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct MinerNotificationAck {
+    message: String,
+}
+
+/// What a miner request is authenticated with, besides its body.
+#[cfg(feature = "powered-by-dbms-postgres")]
+#[derive(FromRequestParts)]
+pub struct MinerAuth {
+    method: Method,
+    uri: OriginalUri,
+    auth_header: TypedHeader<Authorization<Basic>>,
+    params: Query<MinerAuthParams>,
+    signed_requests: Extension<Arc<SignedRequests>>,
+    rate_limits: Extension<Arc<RateLimits>>,
+}
+
+/// Same scheme as the admin api: basic auth with the miner pubkey as username and, as password,
+/// its signature over the method, path and query, body digest and `timestamp` query param.
+#[cfg(feature = "powered-by-dbms-postgres")]
+fn verify_miner(auth: &MinerAuth, body: &[u8]) -> Result<Pubkey, (StatusCode, String)> {
+    let uri = &auth.uri.0;
+    let path_and_query = uri.path_and_query().map(|path| path.as_str()).unwrap_or(uri.path());
+    let miner_pubkey = auth.signed_requests.verify(
+        &auth.auth_header,
+        &auth.method,
+        path_and_query,
+        body,
+        auth.params.timestamp,
+    )?;
+    auth.rate_limits
+        .check_http_pubkey(&miner_pubkey)
+        .map_err(|(status, msg)| (status, msg.to_string()))?;
+    Ok(miner_pubkey)
+}
+
+/// Confirmation codes emailed within [`CODE_WINDOW`], by miner and by address. Anyone may sign up
+/// any address, so the number of codes sent is capped whatever the rate limits of the api.
+#[derive(Default)]
+pub struct ConfirmationCodes {
+    sent: Mutex<HashMap<String, Vec<Instant>>>,
+}
+
+impl ConfirmationCodes {
+    /// Records a code sent by `miner` to `address`, unless either already had its share.
+    pub fn reserve(&self, miner: &str, address: &str) -> Result<(), String> {
+        self.reserve_at(Instant::now(), miner, address)
+    }
+
+    fn reserve_at(&self, now: Instant, miner: &str, address: &str) -> Result<(), String> {
+        let mut sent = self.sent.lock().unwrap();
+        sent.retain(|_, times| {
+            times.retain(|at| now.duration_since(*at) < CODE_WINDOW);
+            !times.is_empty()
+        });
+
+        let miner_key = format!("miner {}", miner);
+        let address_key = format!("address {}", address.to_lowercase());
+        if sent.get(&miner_key).map(Vec::len).unwrap_or(0) >= MAX_CODES_PER_MINER {
+            return Err("Too many confirmation codes requested, try again later.".to_string());
+        }
+        if sent.get(&address_key).map(Vec::len).unwrap_or(0) >= MAX_CODES_PER_ADDRESS {
+            return Err(
+                "Too many confirmation codes sent to this address, try again later.".to_string()
+            );
+        }
+
+        sent.entry(miner_key).or_default().push(now);
+        sent.entry(address_key).or_default().push(now);
+        Ok(())
+    }
+}
+
+/// Whether the server may post to `ip` on a miner's behalf: never the operator's own network,
+/// loopback, link local (169.254/16, cloud metadata) or any other non public range.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || octets[0] == 0
+                // shared address space 100.64/10
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64))
+        },
+        IpAddr::V6(ip) => {
+            // MI: addresses embedding an ipv4 one reach it, NAT64 64:ff9b::/96 as well as ipv4
+            // mapped ::ffff:0:0/96 and compatible ::/96
+            let embedded = match ip.segments() {
+                [0x64, 0xff9b, 0, 0, 0, 0, high, low] => {
+                    Some(Ipv4Addr::from(((high as u32) << 16) | low as u32))
+                },
+                _ => ip.to_ipv4(),
+            };
+            match embedded {
+                Some(ip) => is_public_ip(IpAddr::V4(ip)),
+                None => {
+                    let segments = ip.segments();
+                    !(ip.is_multicast()
+                        // unique local fc00::/7 and link local fe80::/10
+                        || (segments[0] & 0xfe00) == 0xfc00
+                        || (segments[0] & 0xffc0) == 0xfe80
+                        // local use NAT64 64:ff9b:1::/48
+                        || (segments[0] == 0x64 && segments[1] == 0xff9b && segments[2] == 1))
+                },
+            }
+        },
+    }
+}
+
+/// The server posts to the url on the miner's behalf, so only public https endpoints are
+/// accepted. Host names are checked again on every delivery, see [`MinerWebhookNotifier`].
+fn validate_webhook(target: &str) -> Result<(), String> {
+    let url = url::Url::parse(target).map_err(|e| format!("Invalid webhook url: {e}"))?;
+    if url.scheme() != "https" {
+        return Err("Webhook url must use https.".to_string());
+    }
+
+    let host = url.host_str().unwrap_or_default().trim_matches(|c| c == '[' || c == ']');
+    if host.is_empty() || host.eq_ignore_ascii_case("localhost") || host.ends_with(".localhost") {
+        return Err("Webhook host is not allowed.".to_string());
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        if !is_public_ip(ip) {
+            return Err("Webhook host is not allowed.".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the webhook host and fails unless every address it resolves to is public, a name
+/// that passed [`validate_webhook`] may point anywhere by the time a notification is sent.
+async fn resolve_public_addr(url: &url::Url) -> Result<(String, SocketAddr), String> {
+    let host = url.host_str().unwrap_or_default().trim_matches(|c| c == '[' || c == ']');
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve webhook host {}: {}", host, e))?
+        .collect();
+
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!("Webhook host {} resolves to non public address {}", host, addr.ip()));
+    }
+    match addrs.first() {
+        Some(addr) => Ok((host.to_string(), *addr)),
+        None => Err(format!("Webhook host {} resolves to no address", host)),
+    }
+}
+
+/// Posts a miner's notifications to the webhook they subscribed with. The host is resolved and
+/// checked on each delivery and the connection pinned to the checked address, without following
+/// redirects.
+pub struct MinerWebhookNotifier {
+    url: String,
+}
+
+impl MinerWebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        MinerWebhookNotifier { url: url.to_string() }
+    }
+}
+
+impl Notifier for MinerWebhookNotifier {
+    fn name(&self) -> &'static str {
+        "miner_webhook"
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            validate_webhook(&self.url)?;
+            let url = url::Url::parse(&self.url).map_err(|e| e.to_string())?;
+            let (host, addr) = resolve_public_addr(&url).await?;
+            let client = reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(WEBHOOK_TIMEOUT)
+                .resolve(&host, addr)
+                .build()
+                .map_err(|e| e.to_string())?;
+
+            let mut body = serde_json::to_value(notification).map_err(|e| e.to_string())?;
+            body["text"] = json!(text);
+            client
+                .post(url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+#[cfg(feature = "powered-by-dbms-postgres")]
+fn require_dbms() -> Result<(), (StatusCode, String)> {
+    let powered_by_dbms = POWERED_BY_DBMS.get().unwrap_or(&PoweredByDbms::Unavailable);
+    if powered_by_dbms == &PoweredByDbms::Unavailable {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Miner notifications require POWERED_BY_DBMS enabled.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "powered-by-dbms-postgres")]
+async fn get_miner_id(
+    database: &Arc<Database>,
+    miner_pubkey: &Pubkey,
+) -> Result<i64, (StatusCode, String)> {
+    database
+        .get_miner_by_pubkey_str(miner_pubkey.to_string())
+        .await
+        .map(|miner| miner.id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "Miner not registered with the pool.".to_string()))
+}
+
+/// Registers or replaces the miner's notification subscription. The JSON body is
+/// `{"channel": "webhook"|"email", "target": ..., "events": [...], "offline_mins": 10}`.
+/// An email target gets a confirmation code and is only notified once the code is confirmed
+/// through [`post_verify_miner_notifications`].
+#[cfg(feature = "powered-by-dbms-postgres")]
+pub async fn post_miner_notifications(
+    auth: MinerAuth,
+    Extension(database): Extension<Arc<Database>>,
+    Extension(config): Extension<Arc<MinerNotificationsConfig>>,
+    Extension(confirmation_codes): Extension<Arc<ConfirmationCodes>>,
+    body: Bytes,
+) -> MinerNotificationResult<MinerNotificationAck> {
+    let miner_pubkey = verify_miner(&auth, &body)?;
+    require_dbms()?;
+
+    let request: MinerNotificationRequest = serde_json::from_slice(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request body: {e}")))?;
+
+    if request.events.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No events selected.".to_string()));
+    }
+    let offline_mins = request.offline_mins.unwrap_or(DEFAULT_OFFLINE_MINS);
+    if !(1..=MAX_OFFLINE_MINS).contains(&offline_mins) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("offline_mins must be between 1 and {}.", MAX_OFFLINE_MINS),
+        ));
+    }
+    if request.target.len() > MAX_TARGET_LEN {
+        return Err((StatusCode::BAD_REQUEST, "Target too long.".to_string()));
+    }
+    let email = match request.channel {
+        MinerChannel::Webhook => {
+            validate_webhook(&request.target).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            None
+        },
+        MinerChannel::Email => {
+            let Some(email) = config.email.as_ref() else {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Email notifications are not available on this pool.".to_string(),
+                ));
+            };
+            if request.target.parse::<lettre::Address>().is_err() {
+                return Err((StatusCode::BAD_REQUEST, "Invalid email address.".to_string()));
+            }
+            Some(email)
+        },
+    };
+
+    let miner_id = get_miner_id(&database, &miner_pubkey).await?;
+
+    let mut events: Vec<&str> = request.events.iter().map(|event| event.as_str()).collect();
+    events.sort();
+    events.dedup();
+    let events = events.join(",");
+
+    // MI: anyone can sign up any address, so emails wait for the owner of the address to confirm
+    let verify_code = email.map(|_| uuid::Uuid::new_v4().simple().to_string());
+    let subscription = InsertMinerNotification {
+        miner_id,
+        channel: request.channel.as_str().to_string(),
+        target: request.target.clone(),
+        events: events.clone(),
+        offline_mins: offline_mins as i32,
+        verified: verify_code.is_none(),
+        verify_code: verify_code.clone(),
+    };
+    let verified = match database.upsert_miner_notification(subscription).await {
+        Ok(verified) => verified,
+        Err(e) => {
+            error!(target: "server_log", "Failed to save notification subscription of miner {}: {:?}", miner_pubkey, e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save subscription".to_string(),
+            ));
+        },
+    };
+
+    info!(target: "server_log", "Miner {} subscribed to {} notifications: {}", miner_pubkey, request.channel.as_str(), events);
+    match (email, verify_code) {
+        (Some(email), Some(code)) if !verified => {
+            confirmation_codes
+                .reserve(&miner_pubkey.to_string(), &request.target)
+                .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, e))?;
+            send_verify_code(email, &miner_pubkey, &request.target, &code).await?;
+            Ok(Json(MinerNotificationAck {
+                message: format!(
+                    "Subscribed to {} via email, confirm with the code sent to {}.",
+                    events, request.target
+                ),
+            }))
+        },
+        _ => Ok(Json(MinerNotificationAck {
+            message: format!("Subscribed to {} via {}.", events, request.channel.as_str()),
+        })),
+    }
+}
+
+#[cfg(feature = "powered-by-dbms-postgres")]
+async fn send_verify_code(
+    email: &MinerEmailConfig,
+    miner_pubkey: &Pubkey,
+    target: &str,
+    code: &str,
+) -> Result<(), (StatusCode, String)> {
+    let notifier = email.notifier(target).map_err(|e| {
+        error!(target: "server_log", "Miner {} email notifier: {}", miner_pubkey, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to send the confirmation code".to_string())
+    })?;
+    let text = format!(
+        "Miner {} subscribed this address to its pool notifications.\nConfirmation code: {}\nIgnore this email if you didn't subscribe.",
+        miner_pubkey, code
+    );
+    let notification = Notification::Miner {
+        pubkey: miner_pubkey.to_string(),
+        event: "verify_email".to_string(),
+        message: text.clone(),
+    };
+    notifier.send(&notification, &text).await.map_err(|e| {
+        error!(target: "server_log", "Failed to email the confirmation code of miner {}: {}", miner_pubkey, e);
+        (StatusCode::BAD_GATEWAY, "Failed to send the confirmation code".to_string())
+    })
+}
+
+/// Confirms an email subscription with the `code` query param sent to the address.
+#[cfg(feature = "powered-by-dbms-postgres")]
+pub async fn post_verify_miner_notifications(
+    auth: MinerAuth,
+    Query(verify): Query<VerifyCodeParam>,
+    Extension(database): Extension<Arc<Database>>,
+    body: Bytes,
+) -> MinerNotificationResult<MinerNotificationAck> {
+    let miner_pubkey = verify_miner(&auth, &body)?;
+    require_dbms()?;
+
+    let miner_id = get_miner_id(&database, &miner_pubkey).await?;
+    match database.verify_miner_notification(miner_id, verify.code).await {
+        Ok(0) => Err((StatusCode::BAD_REQUEST, "Invalid confirmation code.".to_string())),
+        Ok(_) => {
+            info!(target: "server_log", "Miner {} confirmed its notification email", miner_pubkey);
+            Ok(Json(MinerNotificationAck { message: "Email confirmed.".to_string() }))
+        },
+        Err(e) => {
+            error!(target: "server_log", "Failed to confirm notification email of miner {}: {:?}", miner_pubkey, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to confirm email".to_string()))
+        },
+    }
+}
+
+/// Removes the miner's subscription.
+#[cfg(feature = "powered-by-dbms-postgres")]
+pub async fn delete_miner_notifications(
+    auth: MinerAuth,
+    Extension(database): Extension<Arc<Database>>,
+    body: Bytes,
+) -> MinerNotificationResult<MinerNotificationAck> {
+    let miner_pubkey = verify_miner(&auth, &body)?;
+    require_dbms()?;
+
+    let miner_id = get_miner_id(&database, &miner_pubkey).await?;
+    match database.delete_miner_notification(miner_id).await {
+        Ok(0) => Err((StatusCode::NOT_FOUND, "No subscription found.".to_string())),
+        Ok(_) => {
+            info!(target: "server_log", "Miner {} unsubscribed from notifications", miner_pubkey);
+            Ok(Json(MinerNotificationAck { message: "Unsubscribed.".to_string() }))
+        },
+        Err(e) => {
+            error!(target: "server_log", "Failed to delete notification subscription of miner {}: {:?}", miner_pubkey, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete subscription".to_string()))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_public_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "::10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "64:ff9b::127.0.0.1",
+            "64:ff9b:1::1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in
+            ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111", "::ffff:1.1.1.1", "64:ff9b::101:101"]
+        {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn validates_webhook_urls() {
+        assert!(validate_webhook("https://example.com/hook").is_ok());
+        assert!(validate_webhook("http://example.com/hook").is_err());
+        assert!(validate_webhook("https://localhost/hook").is_err());
+        assert!(validate_webhook("https://169.254.169.254/latest").is_err());
+        assert!(validate_webhook("https://[::ffff:10.0.0.1]/hook").is_err());
+    }
+
+    #[test]
+    fn caps_confirmation_codes_per_miner_and_address() {
+        let codes = ConfirmationCodes::default();
+        let now = Instant::now();
+        for i in 0..MAX_CODES_PER_MINER {
+            assert!(codes.reserve_at(now, "miner", &format!("{i}@example.com")).is_ok());
+        }
+        assert!(codes.reserve_at(now, "miner", "other@example.com").is_err());
+
+        for i in 0..MAX_CODES_PER_ADDRESS {
+            assert!(codes.reserve_at(now, &format!("miner{i}"), "victim@example.com").is_ok());
+        }
+        assert!(codes.reserve_at(now, "another", "Victim@Example.com").is_err());
+
+        let later = now + CODE_WINDOW;
+        assert!(codes.reserve_at(later, "miner", "other@example.com").is_ok());
+        assert!(codes.reserve_at(later, "another", "victim@example.com").is_ok());
+    }
+
+    #[tokio::test]
+    async fn refuses_hosts_resolving_to_private_addresses() {
+        let url = url::Url::parse("https://localhost.:8443/hook").unwrap();
+        assert!(resolve_public_addr(&url).await.is_err());
+        let url = url::Url::parse("https://127.0.0.1/hook").unwrap();
+        assert!(resolve_public_addr(&url).await.is_err());
+    }
+}
@@ -79,6 +79,36 @@ pub struct InsertTransaction {
     pub pool_id: i32,
 }
 
+// one attempt to submit a mine transaction, send and confirm times in unix milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsertSubmissionAttempt {
+    pub pool_id: i32,
+    pub challenge_id: Option<i64>,
+    pub attempt: i32,
+    // rpc, race, tpu or bundle
+    pub method: String,
+    pub difficulty: i32,
+    pub priority_fee: i64,
+    pub cu_limit: i32,
+    pub tip: i64,
+    pub blockhash: Option<String>,
+    pub signature: Option<String>,
+    pub sent_at_ms: i64,
+    pub confirmed_at_ms: Option<i64>,
+    pub slot: Option<i64>,
+    // landed or failed
+    pub outcome: String,
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+}
+
+// one recent mine submission, landed or failed to land, for the fee history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MineFee {
+    pub priority_fee: i64,
+    pub landed: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Challenge {
     pub id: i64,
@@ -120,6 +150,7 @@ pub struct InsertReward {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct UpdateReward {
     pub miner_id: i64,
+    pub pool_id: i32,
     pub balance: i64,
 }
 
@@ -129,6 +160,12 @@ pub struct Reward {
     pub miner_id: i64,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PoolReward {
+    pub pool_id: i32,
+    pub balance: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claim {
     pub miner_id: i64,
@@ -160,3 +197,93 @@ pub struct Summary {
     pub earning_sub_total: i64,
     pub percent: f64,
 }
+
+// one paid submission attempt of the fee budget window, base fee not included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSpend {
+    pub age_secs: i64,
+    pub lamports: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolReportTotals {
+    pub num_challenges: i64,
+    pub num_solved: i64,
+    pub rewards: i64,
+    pub commissions: i64,
+    pub num_miners: i64,
+    pub num_mine_transactions: i64,
+    // submission attempts that paid fees: landed, or landed with an error
+    pub num_paid_attempts: i64,
+    // priority fees and tips of those attempts, in lamports
+    pub attempt_fees: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengesInPeriod {
+    pub period: NaiveDateTime,
+    pub num_solved: i64,
+    pub rewards: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DifficultyCount {
+    pub difficulty: i16,
+    pub num_challenges: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolStatsSummary {
+    pub num_challenges: i64,
+    pub num_solved: i64,
+    pub num_mine_transactions: i64,
+    pub rewards: i64,
+    pub avg_best_difficulty: f64,
+    // sum of 2^difficulty over all contributions of the window
+    pub contributed_hashes: f64,
+    // solved challenges whose best difficulty reached the luck difficulty
+    pub num_solves: i64,
+    // seconds since the oldest challenge of the window, less than 24h after a fresh start
+    pub covered_secs: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub challenges_per_hour: Vec<ChallengesInPeriod>,
+    pub challenges_per_day: Vec<ChallengesInPeriod>,
+    pub difficulty_histogram: Vec<DifficultyCount>,
+    pub num_challenges_24h: i64,
+    pub num_solved_24h: i64,
+    pub submission_success_rate: f64,
+    pub average_reward_per_challenge: i64,
+    pub rewards_per_hour: i64,
+    pub rewards_per_day: i64,
+    pub average_best_difficulty: f64,
+    pub luck_difficulty: u32,
+    pub expected_solves: f64,
+    pub actual_solves: i64,
+    pub luck: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinerNotification {
+    pub miner_id: i64,
+    pub pubkey: String,
+    pub channel: String,
+    pub target: String,
+    // comma separated event names
+    pub events: String,
+    pub offline_mins: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsertMinerNotification {
+    pub miner_id: i64,
+    pub channel: String,
+    pub target: String,
+    pub events: String,
+    pub offline_mins: i32,
+    // false until the miner confirms verify_code, kept when the target is unchanged
+    pub verified: bool,
+    pub verify_code: Option<String>,
+}
@@ -1,150 +1,750 @@
 // use serenity::builder::ExecuteWebhook;
 use {
-    serenity::{http::Http, model::webhook::Webhook},
+    crate::reports::{window_label, PoolReport},
+    futures::future::BoxFuture,
+    lettre::{
+        message::header::ContentType, transport::smtp::authentication::Credentials,
+        AsyncSmtpTransport, AsyncTransport, Message as EmailMessage, Tokio1Executor,
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    serenity::{
+        http::Http,
+        model::{channel::Embed, webhook::Webhook},
+    },
     slack_messaging::Message as SlackChannelMessage,
-    std::{fmt, io, str::FromStr, time::Duration},
+    std::{collections::HashMap, sync::Arc, time::Duration},
     tokio::sync::mpsc::UnboundedReceiver,
     tracing::{error, info, warn},
 };
 
-#[derive(Debug)]
-pub enum RewardsMessage {
-    // Rewards(/* difficulty: */ u32, /* rewards: */ f64, /* balance: */ f64, /* num_clients: */ u32, /* num_contributors: */ u32),
-    Rewards(u32, f64, f64, u32, u32),
+const NUM_RETRIES: u32 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RewardsMessage {
+    pub difficulty: u32,
+    pub rewards: f64,
+    pub balance: f64,
+    pub num_miners: u32,
+    pub num_contributors: u32,
+    pub signature: Option<String>,
+    // MI: compute unit price in micro-lamports
+    pub priority_fee: u64,
 }
 
-#[derive(Debug)]
-enum SrcType {
-    Pool,
-    Solo,
+/// Event kinds a channel can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Rewards,
+    Alert,
+    Miner,
+    Report,
 }
 
-impl fmt::Display for SrcType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl NotificationKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Rewards => "rewards",
+            NotificationKind::Alert => "alert",
+            NotificationKind::Miner => "miner",
+            NotificationKind::Report => "report",
+        }
+    }
+
+    fn default_template(&self) -> &'static str {
         match self {
-            SrcType::Pool => write!(f, "pool"),
-            SrcType::Solo => write!(f, "solo"),
+            NotificationKind::Rewards => {
+                "Pool rewards  D: {difficulty}\nRewards: {rewards} ORE\nBalance: {balance} ORE\nMiners: {miners}   Contributors: {contributors}\nFee: {fee}\nSig: {signature}"
+            },
+            NotificationKind::Alert | NotificationKind::Miner => "{message}",
+            NotificationKind::Report => {
+                "Pool report, last {window}\nChallenges solved: {solved}/{challenges}\nRewards: {rewards} ORE\nCommissions: {commissions} ORE\nMiners: {miners}\nMine txns: {transactions}\nFees spent: ~{fees} SOL"
+            },
         }
     }
 }
 
-impl FromStr for SrcType {
-    type Err = io::Error;
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    Rewards(RewardsMessage),
+    Alert {
+        message: String,
+    },
+    /// Sent to the miner's own subscription rather than the pool channels.
+    Miner {
+        pubkey: String,
+        // MI: `event` is the tag already
+        #[serde(rename = "miner_event")]
+        event: String,
+        message: String,
+    },
+    Report(PoolReport),
+}
+
+impl Notification {
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            Notification::Rewards(_) => NotificationKind::Rewards,
+            Notification::Alert { .. } => NotificationKind::Alert,
+            Notification::Miner { .. } => NotificationKind::Miner,
+            Notification::Report(_) => NotificationKind::Report,
+        }
+    }
+
+    /// Compared against each channel's min_difficulty, None for events not tied to a solution.
+    fn difficulty(&self) -> Option<u32> {
+        match self {
+            Notification::Rewards(rewards) => Some(rewards.difficulty),
+            Notification::Alert { .. } | Notification::Miner { .. } | Notification::Report(_) => {
+                None
+            },
+        }
+    }
+
+    fn title(&self) -> String {
+        match self {
+            Notification::Rewards(rewards) => {
+                format!("Pool rewards, difficulty {}", rewards.difficulty)
+            },
+            Notification::Alert { .. } => "Pool alert".to_string(),
+            Notification::Miner { event, .. } => format!("Miner {}", event.replace('_', " ")),
+            Notification::Report(report) => {
+                format!("Pool {} report, last {}", report.pool, window_label(report.window_hours))
+            },
+        }
+    }
+
+    /// The placeholders available to templates.
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Notification::Rewards(rewards) => vec![
+                ("difficulty", rewards.difficulty.to_string()),
+                ("rewards", rewards.rewards.to_string()),
+                ("balance", rewards.balance.to_string()),
+                ("miners", rewards.num_miners.to_string()),
+                ("contributors", rewards.num_contributors.to_string()),
+                ("signature", rewards.signature.clone().unwrap_or_else(|| "-".to_string())),
+                ("fee", rewards.priority_fee.to_string()),
+            ],
+            Notification::Alert { message } => vec![("message", message.clone())],
+            Notification::Miner { pubkey, event, message } => vec![
+                ("pubkey", pubkey.clone()),
+                ("event", event.clone()),
+                ("message", message.clone()),
+            ],
+            Notification::Report(report) => vec![
+                ("pool", report.pool.clone()),
+                ("window", window_label(report.window_hours)),
+                ("challenges", report.num_challenges.to_string()),
+                ("solved", report.num_solved.to_string()),
+                ("rewards", report.rewards.to_string()),
+                ("commissions", report.commissions.to_string()),
+                ("miners", report.num_miners.to_string()),
+                ("transactions", report.num_mine_transactions.to_string()),
+                ("fees", report.fees_spent.to_string()),
+            ],
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "pool" => Ok(SrcType::Pool),
-            "solo" => Ok(SrcType::Solo),
-            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown source type")),
+    /// Replaces every `{field}` in `template` by its value, passed through `escape`.
+    fn render(&self, template: &str, escape: impl Fn(&str) -> String) -> String {
+        let mut text = template.to_string();
+        for (name, value) in self.fields() {
+            text = text.replace(&format!("{{{}}}", name), &escape(&value));
         }
+        text
     }
 }
 
-pub(crate) async fn slack_messaging_processor(
-    slack_webhook: String,
-    mut receiver_channel: UnboundedReceiver<RewardsMessage>,
-) {
-    loop {
-        while let Some(slack_message) = receiver_channel.recv().await {
-            match slack_message {
-                RewardsMessage::Rewards(d, r, b, c, m) => {
-                    slack_messaging(slack_webhook.clone(), SrcType::Pool, d, r, b, c, m).await
-                },
-            }
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Escapes `text` so the channel shows it as is rather than as markup.
+    fn escape(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// `text` is the notification rendered with the channel's template.
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>>;
+}
+
+pub struct SlackNotifier {
+    webhook: url::Url,
+}
+
+impl SlackNotifier {
+    pub fn new(webhook: &str) -> Result<Self, String> {
+        let webhook =
+            url::Url::parse(webhook).map_err(|e| format!("Invalid slack webhook: {e}"))?;
+        Ok(SlackNotifier { webhook })
+    }
+}
+
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn send<'a>(
+        &'a self,
+        _notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let message = SlackChannelMessage::builder().text(text.to_string()).build();
+            reqwest::Client::new()
+                .post(self.webhook.clone())
+                .json(&message)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+pub struct DiscordNotifier {
+    webhook: String,
+    embeds: bool,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook: &str, embeds: bool) -> Self {
+        DiscordNotifier { webhook: webhook.to_string(), embeds }
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            // You don't need a token when you are only dealing with webhooks.
+            let http = Http::new("");
+            let webhook = Webhook::from_url(&http, &self.webhook)
+                .await
+                .map_err(|e| format!("Invalid discord webhook: {e}"))?;
+            webhook
+                .execute(&http, false, |w| {
+                    w.username("Mirabot");
+                    if self.embeds {
+                        w.embeds(vec![Embed::fake(|e| {
+                            e.title(notification.title()).description(text)
+                        })])
+                    } else {
+                        w.content(text)
+                    }
+                })
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+pub struct TelegramNotifier {
+    bot_token: String,
+    chat_id: String,
+    // MI: "MarkdownV2" or "HTML", plain text when None
+    parse_mode: Option<String>,
+}
+
+impl TelegramNotifier {
+    pub fn new(bot_token: &str, chat_id: &str, parse_mode: Option<String>) -> Self {
+        TelegramNotifier {
+            bot_token: bot_token.to_string(),
+            chat_id: chat_id.to_string(),
+            parse_mode,
         }
     }
 }
 
-pub(crate) async fn discord_messaging_processor(
-    discord_webhook: String,
-    mut receiver_channel: UnboundedReceiver<RewardsMessage>,
-) {
-    loop {
-        while let Some(discord_message) = receiver_channel.recv().await {
-            match discord_message {
-                RewardsMessage::Rewards(d, r, b, c, m) => {
-                    discord_messaging(discord_webhook.clone(), SrcType::Pool, d, r, b, c, m).await
-                },
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn escape(&self, text: &str) -> String {
+        match self.parse_mode.as_deref() {
+            Some("MarkdownV2") => escape_markdown_v2(text),
+            Some("HTML") => escape_html(text),
+            _ => text.to_string(),
+        }
+    }
+
+    fn send<'a>(
+        &'a self,
+        _notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+            reqwest::Client::new()
+                .post(url)
+                .json(&json!({
+                    "chat_id": self.chat_id,
+                    "text": text,
+                    "parse_mode": self.parse_mode,
+                }))
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map(|_| ())
+                // MI: the bot token is part of the url, never log it
+                .map_err(|e| e.without_url().to_string())
+        })
+    }
+}
+
+// MI: the characters telegram requires escaped anywhere in a MarkdownV2 text
+const MARKDOWN_V2_RESERVED: &str = "\\_*[]()~`>#+-=|{}.!";
+
+fn escape_markdown_v2(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if MARKDOWN_V2_RESERVED.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub struct EmailNotifier {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+    to: Vec<String>,
+    subject: String,
+}
+
+impl EmailNotifier {
+    pub fn new(
+        smtp_host: &str,
+        smtp_port: Option<u16>,
+        username: &str,
+        password: &str,
+        from: &str,
+        to: &[String],
+        subject: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
+            .map_err(|e| format!("Invalid smtp host: {e}"))?
+            .credentials(Credentials::new(username.to_string(), password.to_string()));
+        if let Some(port) = smtp_port {
+            builder = builder.port(port);
+        }
+
+        Ok(EmailNotifier {
+            mailer: builder.build(),
+            from: from.to_string(),
+            to: to.to_vec(),
+            subject: subject.unwrap_or("Hashpoo pool notification").to_string(),
+        })
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn send<'a>(
+        &'a self,
+        _notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let mut builder = EmailMessage::builder()
+                .from(self.from.parse().map_err(|e| format!("Invalid from address: {e}"))?)
+                .subject(self.subject.clone())
+                .header(ContentType::TEXT_PLAIN);
+            for to in self.to.iter() {
+                builder = builder.to(to.parse().map_err(|e| format!("Invalid to address: {e}"))?);
             }
+            let email = builder.body(text.to_string()).map_err(|e| e.to_string())?;
+
+            self.mailer.send(email).await.map(|_| ()).map_err(|e| e.to_string())
+        })
+    }
+}
+
+pub struct WebhookNotifier {
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str, headers: HashMap<String, String>) -> Self {
+        WebhookNotifier { url: url.to_string(), headers }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification,
+        text: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let mut body = serde_json::to_value(notification).map_err(|e| e.to_string())?;
+            body["text"] = json!(text);
+            let mut req = reqwest::Client::new().post(&self.url).json(&body);
+            for (name, value) in self.headers.iter() {
+                req = req.header(name, value);
+            }
+            req.send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    Slack {
+        webhook: String,
+    },
+    Discord {
+        webhook: String,
+        embeds: Option<bool>,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+        parse_mode: Option<String>,
+    },
+    Email {
+        smtp_host: String,
+        smtp_port: Option<u16>,
+        username: String,
+        password: String,
+        from: String,
+        to: Vec<String>,
+        subject: Option<String>,
+    },
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+impl NotifierConfig {
+    pub fn build(&self) -> Result<Box<dyn Notifier>, String> {
+        Ok(match self {
+            NotifierConfig::Slack { webhook } => Box::new(SlackNotifier::new(webhook)?),
+            NotifierConfig::Discord { webhook, embeds } => {
+                Box::new(DiscordNotifier::new(webhook, embeds.unwrap_or(true)))
+            },
+            NotifierConfig::Telegram { bot_token, chat_id, parse_mode } => {
+                Box::new(TelegramNotifier::new(bot_token, chat_id, parse_mode.clone()))
+            },
+            NotifierConfig::Email {
+                smtp_host,
+                smtp_port,
+                username,
+                password,
+                from,
+                to,
+                subject,
+            } => Box::new(EmailNotifier::new(
+                smtp_host,
+                *smtp_port,
+                username,
+                password,
+                from,
+                to,
+                subject.as_deref(),
+            )?),
+            NotifierConfig::Webhook { url, headers } => {
+                Box::new(WebhookNotifier::new(url, headers.clone()))
+            },
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelConfig {
+    #[serde(flatten)]
+    pub notifier: NotifierConfig,
+    /// Min difficulty of the events carrying one, defaults to `--messaging-diff`.
+    pub min_difficulty: Option<u32>,
+    /// Event kinds delivered to this channel, defaults to all of them.
+    pub events: Option<Vec<NotificationKind>>,
+    /// Message template per event kind, `{field}` placeholders are replaced by the event's values.
+    #[serde(default)]
+    pub templates: HashMap<String, String>,
+}
+
+/// SMTP account used to email the miners subscribed to their own notifications.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MinerEmailConfig {
+    pub smtp_host: String,
+    pub smtp_port: Option<u16>,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub subject: Option<String>,
+}
+
+impl MinerEmailConfig {
+    pub fn notifier(&self, to: &str) -> Result<EmailNotifier, String> {
+        EmailNotifier::new(
+            &self.smtp_host,
+            self.smtp_port,
+            &self.username,
+            &self.password,
+            &self.from,
+            &[to.to_string()],
+            self.subject.as_deref(),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationsConfig {
+    #[serde(default)]
+    channels: Vec<ChannelConfig>,
+    miner_email: Option<MinerEmailConfig>,
+}
+
+fn read_notifications_config(path: &str) -> Result<NotificationsConfig, config::ConfigError> {
+    config::Config::builder()
+        .add_source(config::File::with_name(path))
+        .build()
+        .and_then(|c| c.try_deserialize::<NotificationsConfig>())
+}
+
+pub struct NotificationChannel {
+    notifier: Arc<dyn Notifier>,
+    min_difficulty: u32,
+    events: Option<Vec<NotificationKind>>,
+    templates: HashMap<String, String>,
+}
+
+impl NotificationChannel {
+    pub fn new(config: &ChannelConfig, default_min_difficulty: u32) -> Result<Self, String> {
+        Ok(NotificationChannel {
+            notifier: config.notifier.build()?.into(),
+            min_difficulty: config.min_difficulty.unwrap_or(default_min_difficulty),
+            events: config.events.clone(),
+            templates: config.templates.clone(),
+        })
+    }
+
+    fn accepts(&self, notification: &Notification) -> bool {
+        let kind_allowed = match &self.events {
+            Some(events) => events.contains(&notification.kind()),
+            None => true,
+        };
+        let difficulty_allowed = match notification.difficulty() {
+            Some(difficulty) => difficulty >= self.min_difficulty,
+            None => true,
+        };
+        kind_allowed && difficulty_allowed
+    }
+
+    fn render(&self, notification: &Notification) -> String {
+        let kind = notification.kind();
+        match self.templates.get(kind.as_str()) {
+            // MI: templates are written in the channel's markup, only the values need escaping
+            Some(template) => notification.render(template, |value| self.notifier.escape(value)),
+            None => self
+                .notifier
+                .escape(&notification.render(kind.default_template(), |value| value.to_string())),
         }
     }
 }
 
-async fn slack_messaging(
-    slack_webhook: String,
-    source: SrcType,
-    difficulty: u32,
-    rewards: f64,
-    balance: f64,
-    num_clients: u32,
-    num_contributors: u32,
+/// Loads the channels listed in the file at `NOTIFICATIONS_CONFIG`, plus the legacy
+/// `SLACK_WEBHOOK` and `DISCORD_WEBHOOK` env vars.
+pub fn load_notification_channels(default_min_difficulty: u32) -> Vec<NotificationChannel> {
+    let mut configs = vec![];
+
+    match std::env::var("NOTIFICATIONS_CONFIG") {
+        Ok(path) => match read_notifications_config(&path) {
+            Ok(notifications_config) => configs.extend(notifications_config.channels),
+            Err(e) => {
+                error!(target: "server_log", "Failed to load notification channels from {path}: {e}")
+            },
+        },
+        Err(_) => {
+            info!(target: "server_log", "NOTIFICATIONS_CONFIG not set, no notification channels configured from file.")
+        },
+    }
+
+    if let Ok(webhook) = std::env::var("SLACK_WEBHOOK") {
+        configs.push(ChannelConfig {
+            notifier: NotifierConfig::Slack { webhook },
+            min_difficulty: None,
+            events: None,
+            templates: HashMap::new(),
+        });
+    }
+    if let Ok(webhook) = std::env::var("DISCORD_WEBHOOK") {
+        configs.push(ChannelConfig {
+            notifier: NotifierConfig::Discord { webhook, embeds: Some(false) },
+            min_difficulty: None,
+            events: None,
+            templates: HashMap::new(),
+        });
+    }
+
+    configs
+        .iter()
+        .filter_map(|config| match NotificationChannel::new(config, default_min_difficulty) {
+            Ok(channel) => {
+                info!(target: "server_log", "Notification channel enabled: {}", channel.notifier.name());
+                Some(channel)
+            },
+            Err(e) => {
+                error!(target: "server_log", "Skipping notification channel: {e}");
+                None
+            },
+        })
+        .collect()
+}
+
+/// The `[miner_email]` section of the `NOTIFICATIONS_CONFIG` file, None when miners can't
+/// subscribe by email.
+pub fn load_miner_email_config() -> Option<MinerEmailConfig> {
+    let path = std::env::var("NOTIFICATIONS_CONFIG").ok()?;
+    match read_notifications_config(&path) {
+        Ok(notifications_config) => notifications_config.miner_email,
+        Err(e) => {
+            error!(target: "server_log", "Failed to load miner email settings from {path}: {e}");
+            None
+        },
+    }
+}
+
+pub(crate) async fn send_with_retries(
+    notifier: &dyn Notifier,
+    notification: &Notification,
+    text: &str,
 ) {
-    let text = format!(
-        "S: {}   D: {}\nR: {}\nB: {}\nC: {}   M: {}\n________________",
-        source, difficulty, rewards, balance, num_clients, num_contributors
-    );
-    let slack_webhook_url =
-        url::Url::parse(&slack_webhook).expect("Failed to parse slack webhook url");
-    let message = SlackChannelMessage::builder().text(text).build();
-    let req = reqwest::Client::new().post(slack_webhook_url).json(&message);
     let mut num_retries = 0;
     loop {
-        if let Err(err) = req.try_clone().unwrap().send().await {
-            // eprintln!("{}", err);
-            // error!(target: "server_log", "{}", err);
-            error!(target: "server_log", "Err sending slack webhook: {:?}", err);
-            if num_retries < 3 {
+        if let Err(err) = notifier.send(notification, text).await {
+            error!(target: "server_log", "Err sending {} notification: {}", notifier.name(), err);
+            if num_retries < NUM_RETRIES {
                 info!(target: "server_log", "retry...");
                 num_retries += 1;
                 tokio::time::sleep(Duration::from_millis(1_000)).await;
                 continue;
             } else {
-                warn!(target: "server_log", "Failed 3 attempts to send message to slack. No more retry.");
+                warn!(target: "server_log",
+                    "Failed {} attempts to send message to {}. No more retry.",
+                    NUM_RETRIES,
+                    notifier.name()
+                );
             }
         }
         break;
     }
 }
 
-async fn discord_messaging(
-    discord_webhook: String,
-    source: SrcType,
-    difficulty: u32,
-    rewards: f64,
-    balance: f64,
-    num_clients: u32,
-    num_contributors: u32,
+async fn deliver(
+    notifier: Arc<dyn Notifier>,
+    mut receiver: UnboundedReceiver<(Notification, String)>,
 ) {
-    let text = format!(
-        "S: {}   D: {}\nR: {}\nB: {}\nC: {}   M: {}\n________________",
-        source, difficulty, rewards, balance, num_clients, num_contributors
-    );
-    // You don't need a token when you are only dealing with webhooks.
-    let http = Http::new("");
-    let discord_webhook = Webhook::from_url(&http, &discord_webhook)
-        .await
-        .expect("Failed to parse discord webhook url");
-
-    // let builder = ExecuteWebhook::new().content(&text).username("Mirabot");
-    // discord_webhook.execute(&http, false, builder).await.expect("Could not execute webhook.");
+    while let Some((notification, text)) = receiver.recv().await {
+        send_with_retries(notifier.as_ref(), &notification, &text).await;
+    }
+}
 
-    let mut num_retries = 0;
-    loop {
-        // if let Err(err) = discord_webhook.execute(&http, false, builder).await {
-        if let Err(err) =
-            discord_webhook.execute(&http, false, |w| w.content(&text).username("Mirabot")).await
-        {
-            // eprintln!("{}", err);
-            error!(target: "server_log", "Err sending discord webhook: {:?}", err);
-            if num_retries < 3 {
-                info!(target: "server_log", "retry...");
-                num_retries += 1;
-                tokio::time::sleep(Duration::from_millis(1_000)).await;
-                continue;
-            } else {
-                warn!(target: "server_log", "Failed 3 attempts to send message to discord. No more retry.");
+/// Fans notifications out to every channel accepting them. Each channel is served by its own
+/// task, so a slow or failing backend doesn't hold up the others.
+pub(crate) async fn notification_processor(
+    channels: Vec<NotificationChannel>,
+    mut receiver_channel: UnboundedReceiver<Notification>,
+) {
+    let mut senders = vec![];
+    let mut tasks = vec![];
+    for channel in channels {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<(Notification, String)>();
+        tasks.push(tokio::spawn(deliver(channel.notifier.clone(), receiver)));
+        senders.push((channel, sender));
+    }
+
+    while let Some(notification) = receiver_channel.recv().await {
+        for (channel, sender) in senders.iter() {
+            if channel.accepts(&notification) {
+                let _ = sender.send((notification.clone(), channel.render(&notification)));
             }
         }
-        break;
+    }
+
+    // MI: once all senders are gone, let the pending notifications go out, `hps report` exits
+    // right after
+    drop(senders);
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn telegram(parse_mode: Option<&str>) -> NotificationChannel {
+        NotificationChannel {
+            notifier: Arc::new(TelegramNotifier::new(
+                "token",
+                "chat",
+                parse_mode.map(String::from),
+            )),
+            min_difficulty: 0,
+            events: None,
+            templates: HashMap::from([("alert".to_string(), "*{message}*".to_string())]),
+        }
+    }
+
+    #[test]
+    fn escapes_telegram_values_for_the_parse_mode() {
+        let alert =
+            Notification::Alert { message: "Fee payer 1.5 SOL, below 2_000 <min>!".to_string() };
+
+        assert_eq!(
+            telegram(Some("MarkdownV2")).render(&alert),
+            "*Fee payer 1\\.5 SOL, below 2\\_000 <min\\>\\!*"
+        );
+        assert_eq!(
+            telegram(Some("HTML")).render(&alert),
+            "*Fee payer 1.5 SOL, below 2_000 &lt;min&gt;!*"
+        );
+        assert_eq!(telegram(None).render(&alert), "*Fee payer 1.5 SOL, below 2_000 <min>!*");
+    }
+
+    #[test]
+    fn escapes_the_whole_default_template() {
+        let mut channel = telegram(Some("MarkdownV2"));
+        channel.templates.clear();
+        let alert = Notification::Alert { message: "a-b".to_string() };
+        assert_eq!(channel.render(&alert), "a\\-b");
     }
 }
@@ -0,0 +1,177 @@
+use {
+    crate::{
+        hashrate::HashrateEstimator, proof_source::ProofSource, AppState, ClientMessage,
+        EpochHashes, MineConfig, WalletExtension,
+    },
+    axum::{extract::State, Extension, Json},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{pubkey::Pubkey, signer::Signer},
+    std::{
+        collections::{HashMap, HashSet},
+        net::SocketAddr,
+        ops::Range,
+        str::FromStr,
+        sync::{atomic::AtomicBool, Arc},
+    },
+    tokio::sync::{mpsc::UnboundedSender, Mutex, RwLock},
+};
+
+/// Nonce range of the current mission by miner pubkey.
+pub type ClientNonceRanges = Arc<RwLock<HashMap<Pubkey, Range<u64>>>>;
+
+/// One mini-pool proof served by this process. Each pool runs its own challenge loop for the
+/// miners assigned to it, while sockets, claims and the database are shared.
+pub struct PoolInstance {
+    pub authority: Pubkey,
+    pub wallet: Arc<WalletExtension>,
+    pub mine_config: Arc<MineConfig>,
+    pub proof_source: Arc<ProofSource>,
+    pub epoch_hashes: Arc<RwLock<EpochHashes>>,
+    /// Set while the pool submits its best solution, no new missions go out meanwhile.
+    pub paused: Arc<AtomicBool>,
+    pub ready_clients: Arc<Mutex<HashSet<SocketAddr>>>,
+    pub client_nonce_ranges: ClientNonceRanges,
+    pub client_channel: UnboundedSender<ClientMessage>,
+}
+
+/// The pools in WALLET_PATH order, the first one is the primary pool that the single-pool
+/// routes and reports keep serving.
+pub struct Pools {
+    instances: Vec<Arc<PoolInstance>>,
+}
+
+impl Pools {
+    pub fn new(instances: Vec<Arc<PoolInstance>>) -> Self {
+        assert!(!instances.is_empty(), "At least one pool is required.");
+        Pools { instances }
+    }
+
+    pub fn primary(&self) -> &Arc<PoolInstance> {
+        &self.instances[0]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<PoolInstance>> {
+        self.instances.iter()
+    }
+
+    pub fn by_authority(&self, authority: &Pubkey) -> Option<&Arc<PoolInstance>> {
+        self.instances.iter().find(|pool| pool.authority.eq(authority))
+    }
+
+    /// The pool a per pool route asked for by authority, the primary pool when none was given.
+    pub fn select(&self, pool: Option<&str>) -> Result<&Arc<PoolInstance>, String> {
+        let Some(pool) = pool else {
+            return Ok(self.primary());
+        };
+        let authority = Pubkey::from_str(pool).map_err(|_| "Invalid pool pubkey".to_string())?;
+        self.by_authority(&authority).ok_or_else(|| "Pool is not served by this server".to_string())
+    }
+
+    /// Picks the pool of a new worker: the one the miner asked for, else the one the miner
+    /// already mines on, so its contributions stay together, else the pool with the least
+    /// hashpower. None when the requested pool is not served here.
+    pub async fn assign(
+        &self,
+        requested: Option<Pubkey>,
+        miner: &Pubkey,
+        app_state: &Arc<RwLock<AppState>>,
+        hashrate_estimator: &Arc<RwLock<HashrateEstimator>>,
+    ) -> Option<Arc<PoolInstance>> {
+        if let Some(requested) = requested {
+            return self.by_authority(&requested).cloned();
+        }
+        if self.instances.len() == 1 {
+            return Some(self.primary().clone());
+        }
+
+        let sockets: Vec<(SocketAddr, Pubkey, Pubkey)> = app_state
+            .read()
+            .await
+            .sockets
+            .iter()
+            .map(|(addr, client_connection)| {
+                (*addr, client_connection.pubkey, client_connection.pool)
+            })
+            .collect();
+
+        if let Some((_, _, pool)) = sockets.iter().find(|(_, pubkey, _)| pubkey.eq(miner)) {
+            if let Some(pool) = self.by_authority(pool) {
+                return Some(pool.clone());
+            }
+        }
+
+        let loads = self.loads(&sockets, hashrate_estimator).await;
+        // MI: workers without samples yet weigh nothing, the worker count breaks the tie
+        self.instances
+            .iter()
+            .zip(loads)
+            .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(pool, _)| pool.clone())
+    }
+
+    /// 10 minutes hashrate and number of connected workers of each pool.
+    async fn loads(
+        &self,
+        sockets: &[(SocketAddr, Pubkey, Pubkey)],
+        hashrate_estimator: &Arc<RwLock<HashrateEstimator>>,
+    ) -> Vec<(f64, usize)> {
+        let estimator = hashrate_estimator.read().await;
+        self.instances
+            .iter()
+            .map(|instance| {
+                sockets.iter().filter(|(_, _, pool)| pool.eq(&instance.authority)).fold(
+                    (0f64, 0usize),
+                    |(hashrate, workers), (addr, _, _)| {
+                        (hashrate + estimator.worker_hashrate(addr).hashrate_10m, workers + 1)
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Query param of the per pool routes, the authority of the pool to report on.
+#[derive(Deserialize)]
+pub struct PoolParam {
+    pub pool: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PoolSummary {
+    authority: String,
+    fee_payer: String,
+    pool_id: i32,
+    challenge: String,
+    num_workers: usize,
+    hashrate_10m: f64,
+}
+
+pub async fn get_pools(
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Extension(pools): Extension<Arc<Pools>>,
+    Extension(hashrate_estimator): Extension<Arc<RwLock<HashrateEstimator>>>,
+) -> Json<Vec<PoolSummary>> {
+    let sockets: Vec<(SocketAddr, Pubkey, Pubkey)> = app_state
+        .read()
+        .await
+        .sockets
+        .iter()
+        .map(|(addr, client_connection)| (*addr, client_connection.pubkey, client_connection.pool))
+        .collect();
+    let loads = pools.loads(&sockets, &hashrate_estimator).await;
+
+    let mut summaries = vec![];
+    for (pool, (hashrate_10m, num_workers)) in pools.iter().zip(loads) {
+        summaries.push(PoolSummary {
+            authority: pool.authority.to_string(),
+            fee_payer: pool.wallet.fee_wallet.pubkey().to_string(),
+            pool_id: pool.mine_config.pool_id,
+            challenge: BASE64_STANDARD.encode(pool.epoch_hashes.read().await.challenge),
+            num_workers,
+            hashrate_10m,
+        });
+    }
+
+    Json(summaries)
+}
@@ -0,0 +1,176 @@
+use {
+    crate::{dynamic_fee as pfee, dynamic_fee::FeeEstimator, metrics::METRICS},
+    ore_api::{consts::CONFIG_ADDRESS, state::Config},
+    ore_utils::AccountDeserialize,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{clock::Clock, hash::Hash, sysvar},
+    std::{
+        sync::{Arc, RwLock},
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tracing::debug,
+};
+
+// MI: how often each value is refetched, and how old it may get before the submission loop
+// goes back to fetching it itself. A blockhash stays valid for about 60s, the transaction needs
+// most of that to land.
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(2);
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(10);
+const CONFIG_REFRESH: Duration = Duration::from_secs(10);
+const CONFIG_MAX_AGE: Duration = Duration::from_secs(30);
+const CLOCK_REFRESH: Duration = Duration::from_secs(10);
+const CLOCK_MAX_AGE: Duration = Duration::from_secs(60);
+const FEE_REFRESH: Duration = Duration::from_secs(5);
+const FEE_MAX_AGE: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Copy)]
+struct Prefetched<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+/// Values a mine transaction needs besides the solution, kept fresh in the background during
+/// the epoch so that at cutoff the transaction is only built and signed.
+pub struct SubmissionPrefetch {
+    rpc_client: Arc<RpcClient>,
+    /// None without dynamic fee, the static fee needs no fetching.
+    fee_estimator: Option<Arc<dyn FeeEstimator>>,
+    priority_fee_cap: Option<u64>,
+    blockhash: RwLock<Option<Prefetched<Hash>>>,
+    config: RwLock<Option<Prefetched<Config>>>,
+    /// On-chain clock unix timestamp minus local unix time, in seconds.
+    clock_offset: RwLock<Option<Prefetched<i64>>>,
+    fee: RwLock<Option<Prefetched<u64>>>,
+}
+
+impl SubmissionPrefetch {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        fee_estimator: Option<Arc<dyn FeeEstimator>>,
+        priority_fee_cap: Option<u64>,
+    ) -> Self {
+        SubmissionPrefetch {
+            rpc_client,
+            fee_estimator,
+            priority_fee_cap,
+            blockhash: RwLock::new(None),
+            config: RwLock::new(None),
+            clock_offset: RwLock::new(None),
+            fee: RwLock::new(None),
+        }
+    }
+
+    pub fn blockhash(&self) -> Option<Hash> {
+        fresh(&self.blockhash, BLOCKHASH_MAX_AGE)
+    }
+
+    pub fn config(&self) -> Option<Config> {
+        fresh(&self.config, CONFIG_MAX_AGE)
+    }
+
+    /// Current on-chain clock unix timestamp, estimated from the local time.
+    pub fn chain_timestamp(&self) -> Option<i64> {
+        fresh(&self.clock_offset, CLOCK_MAX_AGE).map(|offset| local_timestamp() + offset)
+    }
+
+    /// Dynamic fee estimate, already capped by the priority fee cap.
+    pub fn fee(&self) -> Option<u64> {
+        fresh(&self.fee, FEE_MAX_AGE)
+    }
+
+    /// Refetches the values that are due, concurrently. Failures keep the previous value,
+    /// which the submission loop stops using once it is too old.
+    pub async fn refresh(&self) {
+        tokio::join!(
+            self.refresh_blockhash(),
+            self.refresh_config(),
+            self.refresh_clock(),
+            self.refresh_fee()
+        );
+    }
+
+    async fn refresh_blockhash(&self) {
+        if !due(&self.blockhash, BLOCKHASH_REFRESH) {
+            return;
+        }
+        let started = Instant::now();
+        let res = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(self.rpc_client.commitment())
+            .await;
+        METRICS.observe_rpc_call("prefetch_latest_blockhash", started.elapsed());
+        match res {
+            Ok((hash, _slot)) => store(&self.blockhash, hash),
+            Err(err) => debug!(target: "server_log", "Failed to prefetch blockhash: {}", err),
+        }
+    }
+
+    async fn refresh_config(&self) {
+        if !due(&self.config, CONFIG_REFRESH) {
+            return;
+        }
+        let started = Instant::now();
+        let res = self.rpc_client.get_account_data(&CONFIG_ADDRESS).await;
+        METRICS.observe_rpc_call("prefetch_config", started.elapsed());
+        match res {
+            Ok(data) => match Config::try_from_bytes(&data) {
+                Ok(config) => store(&self.config, *config),
+                Err(err) => {
+                    debug!(target: "server_log", "Failed to parse prefetched config: {:?}", err)
+                },
+            },
+            Err(err) => debug!(target: "server_log", "Failed to prefetch config: {}", err),
+        }
+    }
+
+    async fn refresh_clock(&self) {
+        if !due(&self.clock_offset, CLOCK_REFRESH) {
+            return;
+        }
+        let started = Instant::now();
+        let res = self.rpc_client.get_account_data(&sysvar::clock::ID).await;
+        METRICS.observe_rpc_call("prefetch_clock", started.elapsed());
+        match res.map(|data| bincode::deserialize::<Clock>(&data)) {
+            Ok(Ok(clock)) => store(&self.clock_offset, clock.unix_timestamp - local_timestamp()),
+            Ok(Err(err)) => {
+                debug!(target: "server_log", "Failed to parse prefetched clock: {}", err)
+            },
+            Err(err) => debug!(target: "server_log", "Failed to prefetch clock: {}", err),
+        }
+    }
+
+    async fn refresh_fee(&self) {
+        let Some(ref fee_estimator) = self.fee_estimator else {
+            return;
+        };
+        if !due(&self.fee, FEE_REFRESH) {
+            return;
+        }
+        match pfee::dynamic_fee(&**fee_estimator, self.priority_fee_cap).await {
+            Ok(fee) => store(&self.fee, fee),
+            Err(err) => debug!(target: "server_log", "Failed to prefetch fee estimate: {}", err),
+        }
+    }
+}
+
+fn fresh<T: Copy>(slot: &RwLock<Option<Prefetched<T>>>, max_age: Duration) -> Option<T> {
+    let prefetched = *slot.read().unwrap();
+    prefetched
+        .filter(|prefetched| prefetched.fetched_at.elapsed() < max_age)
+        .map(|prefetched| prefetched.value)
+}
+
+fn due<T>(slot: &RwLock<Option<Prefetched<T>>>, refresh: Duration) -> bool {
+    match *slot.read().unwrap() {
+        Some(ref prefetched) => prefetched.fetched_at.elapsed() >= refresh,
+        None => true,
+    }
+}
+
+fn store<T>(slot: &RwLock<Option<Prefetched<T>>>, value: T) {
+    *slot.write().unwrap() = Some(Prefetched { value, fetched_at: Instant::now() });
+}
+
+fn local_timestamp() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
@@ -0,0 +1,216 @@
+use {
+    crate::{
+        metrics::METRICS, notification::Notification, pools::Pools,
+        profitability::ProfitabilityGuard, AppState,
+    },
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey},
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::{mpsc::UnboundedSender, RwLock},
+    tracing::{info, warn},
+};
+
+const ALERT_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// MI: claim failures and db write retries are counted over this sliding window
+const ALERT_WINDOW: Duration = Duration::from_secs(600);
+
+/// Alert rule thresholds, 0 disables a rule.
+pub struct AlertConfig {
+    pub min_sol_balance: u64,
+    pub submission_failures: u64,
+    pub proof_stale_mins: u64,
+    pub claim_failures: u64,
+    pub db_write_retries: u64,
+    pub no_miners_mins: u64,
+    pub cooldown_mins: u64,
+}
+
+#[derive(Default)]
+struct AlertState {
+    firing: bool,
+    last_sent: Option<Instant>,
+}
+
+fn notify(sender: &UnboundedSender<Notification>, message: String) {
+    let _ = sender.send(Notification::Alert { message });
+}
+
+pub async fn alert_processor(
+    config: AlertConfig,
+    rpc_client: Arc<RpcClient>,
+    fee_payer: Pubkey,
+    pools: Arc<Pools>,
+    profitability: Arc<ProfitabilityGuard>,
+    app_state: Arc<RwLock<AppState>>,
+    notification_sender: UnboundedSender<Notification>,
+) {
+    let cooldown = Duration::from_secs(config.cooldown_mins * 60);
+    // MI: per pool rules are keyed by rule and pool authority
+    let mut alerts: HashMap<String, AlertState> = HashMap::new();
+    // (sampled at, claim failures, db write retries)
+    let mut samples: VecDeque<(Instant, u64, u64)> = VecDeque::new();
+    let mut no_miners_since: Option<Instant> = None;
+
+    loop {
+        tokio::time::sleep(ALERT_CHECK_INTERVAL).await;
+
+        let now = Instant::now();
+        let claim_failures = METRICS.claim_failures();
+        let db_write_retries = METRICS.db_write_retries();
+        samples.push_back((now, claim_failures, db_write_retries));
+        while samples.front().is_some_and(|(at, _, _)| now.duration_since(*at) > ALERT_WINDOW) {
+            samples.pop_front();
+        }
+        let (_, oldest_claim_failures, oldest_db_write_retries) = samples[0];
+
+        // None leaves the rule as is, Some(None) clears it and Some(Some(msg)) raises it
+        let mut checks: Vec<(String, Option<Option<String>>)> = vec![];
+
+        if config.min_sol_balance > 0 {
+            let balance = rpc_client.get_balance(&fee_payer).await;
+            checks.push(("low_sol_balance".to_string(), match balance {
+                Ok(balance) => {
+                    METRICS.set_fee_wallet_balance(balance);
+                    Some((balance < config.min_sol_balance).then(|| {
+                        format!(
+                            "Fee payer {} balance is {} SOL, below {} SOL.",
+                            fee_payer,
+                            lamports_to_sol(balance),
+                            lamports_to_sol(config.min_sol_balance)
+                        )
+                    }))
+                },
+                Err(e) => {
+                    warn!(target: "server_log", "Alert check failed to load fee payer balance: {:?}", e);
+                    None
+                },
+            }));
+        }
+
+        // MI: mining is paused meanwhile, always worth an alert
+        checks.push((
+            "fee_budget_spent".to_string(),
+            Some(profitability.budget_spent().then(|| {
+                "Daily fee budget is spent, mining is paused until older fees leave the 24h window."
+                    .to_string()
+            })),
+        ));
+
+        for pool in pools.iter() {
+            if config.submission_failures > 0 {
+                let failures = METRICS.consecutive_submission_failures(&pool.authority);
+                checks.push((
+                    format!("submission_failures {}", pool.authority),
+                    Some((failures >= config.submission_failures).then(|| {
+                        format!(
+                            "{} consecutive mine transaction submissions of pool {} failed.",
+                            failures, pool.authority
+                        )
+                    })),
+                ));
+            }
+
+            if config.proof_stale_mins > 0 {
+                let age = pool.proof_source.update_age();
+                // MI: without miners a pool submits nothing, its proof is expected to stand still
+                let num_sockets = app_state
+                    .read()
+                    .await
+                    .sockets
+                    .values()
+                    .filter(|client_connection| client_connection.pool.eq(&pool.authority))
+                    .count();
+                checks.push((
+                    format!("stale_proof {}", pool.authority),
+                    Some((num_sockets > 0 && age >= config.proof_stale_mins * 60).then(|| {
+                        format!(
+                            "No proof update received for pool {} for {} minutes.",
+                            pool.authority,
+                            age / 60
+                        )
+                    })),
+                ));
+            }
+        }
+
+        if config.claim_failures > 0 {
+            let failures = claim_failures - oldest_claim_failures;
+            checks.push((
+                "claim_failures".to_string(),
+                Some((failures >= config.claim_failures).then(|| {
+                    format!(
+                        "{} claim failures in the last {} minutes.",
+                        failures,
+                        ALERT_WINDOW.as_secs() / 60
+                    )
+                })),
+            ));
+        }
+
+        if config.db_write_retries > 0 {
+            let retries = db_write_retries - oldest_db_write_retries;
+            checks.push((
+                "db_write_retries".to_string(),
+                Some((retries >= config.db_write_retries).then(|| {
+                    format!(
+                        "{} db write retries in the last {} minutes.",
+                        retries,
+                        ALERT_WINDOW.as_secs() / 60
+                    )
+                })),
+            ));
+        }
+
+        if config.no_miners_mins > 0 {
+            let num_sockets = app_state.read().await.sockets.len();
+            if num_sockets > 0 {
+                no_miners_since = None;
+            } else if no_miners_since.is_none() {
+                no_miners_since = Some(now);
+            }
+            let idle = no_miners_since.map_or(Duration::ZERO, |since| now.duration_since(since));
+            checks.push((
+                "no_miners".to_string(),
+                Some(
+                    (idle.as_secs() >= config.no_miners_mins * 60).then(|| {
+                        format!("No miners connected for {} minutes.", idle.as_secs() / 60)
+                    }),
+                ),
+            ));
+        }
+
+        for (rule, check) in checks {
+            let Some(check) = check else {
+                continue;
+            };
+            let state = alerts.entry(rule.clone()).or_default();
+            match check {
+                Some(msg) => {
+                    // MI: at most one alert per rule and cooldown, a flapping or persisting
+                    // condition doesn't flood the channels
+                    let due = match state.last_sent {
+                        Some(at) => now.duration_since(at) >= cooldown,
+                        None => true,
+                    };
+                    if due {
+                        info!(target: "server_log", "Alert {}: {}", rule, msg);
+                        notify(&notification_sender, format!("[ALERT] {}", msg));
+                        state.last_sent = Some(now);
+                    }
+                    state.firing = true;
+                },
+                None => {
+                    if state.firing {
+                        info!(target: "server_log", "Alert {} resolved", rule);
+                        notify(&notification_sender, format!("[RESOLVED] {}", rule));
+                        state.firing = false;
+                    }
+                },
+            }
+        }
+    }
+}
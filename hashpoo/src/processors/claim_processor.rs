@@ -1,28 +1,88 @@
 use {
     crate::{
         database::Database,
+        dry_run::DryRun,
+        durable_nonce::DurableNonce,
+        events::{publish, PoolEvent},
+        metrics::METRICS,
+        pools::Pools,
         utils::{self, ORE_TOKEN_DECIMALS},
         ClaimsQueue, InsertClaim, InsertTransaction, CREATE_ATA_DEDUCTION,
     },
     solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig},
     solana_sdk::{
         compute_budget::ComputeBudgetInstruction,
+        pubkey::Pubkey,
         signature::{Keypair, Signature},
         signer::Signer,
         transaction::Transaction,
     },
     solana_transaction_status::TransactionConfirmationStatus,
     spl_associated_token_account::get_associated_token_address,
-    std::{sync::Arc, time::Duration},
-    tokio::time::Instant,
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    tokio::{sync::broadcast, time::Instant},
     tracing::{error, info},
 };
 
+// MI: resigns on the same nonce with a raised fee before the claim is given up
+const CLAIM_NONCE_RESIGNS: usize = 3;
+const CLAIM_FEE_STEP_PERCENT: u32 = 50;
+
+/// One claim instruction, paid from the proof of pool `pool_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ClaimPart {
+    pool_id: i32,
+    amount: u64,
+}
+
+/// Splits a claim over the pools the miner earned in, in the given order. `pools` holds the pool
+/// id, the miner's rewards in the pool and the balance of the pool proof, a part takes the lower
+/// of both. None when the pools can't cover the amount together.
+fn split_claim(amount: u64, pools: &[(i32, u64, u64)]) -> Option<Vec<ClaimPart>> {
+    let mut parts = vec![];
+    let mut remaining = amount;
+    for &(pool_id, rewards, proof_balance) in pools {
+        if remaining == 0 {
+            break;
+        }
+        let part = remaining.min(rewards).min(proof_balance);
+        if part > 0 {
+            parts.push(ClaimPart { pool_id, amount: part });
+            remaining -= part;
+        }
+    }
+    (remaining == 0).then_some(parts)
+}
+
+/// Amounts of the claim instructions once `deduction` is taken from the parts, in order. The
+/// deducted ORE stays in the pool proofs while the rewards balances drop by the full parts.
+fn claim_ix_amounts(parts: &[ClaimPart], deduction: u64) -> Vec<u64> {
+    let mut deduction = deduction;
+    parts
+        .iter()
+        .map(|part| {
+            let deducted = deduction.min(part.amount);
+            deduction -= deducted;
+            part.amount - deducted
+        })
+        .collect()
+}
+
+/// Priority fee of the claim tx signed after `num_signed` ones that did not land.
+fn raised_claim_fee(prio_fee: u32, num_signed: usize) -> u32 {
+    let step = 100 + CLAIM_FEE_STEP_PERCENT as u64 * num_signed as u64;
+    (prio_fee as u64 * step / 100).min(u32::MAX as u64) as u32
+}
+
 pub async fn claim_processor(
     claims_queue: Arc<ClaimsQueue>,
     rpc_client: Arc<RpcClient>,
-    wallet: Arc<Keypair>,
+    pools: Arc<Pools>,
+    fee_wallet: Arc<Keypair>,
+    claim_nonces: HashMap<Pubkey, Arc<DurableNonce>>,
+    dry_run: Option<Arc<DryRun>>,
     database: Arc<Database>,
+    event_sender: broadcast::Sender<PoolEvent>,
 ) {
     loop {
         let mut claim = None;
@@ -35,11 +95,62 @@ pub async fn claim_processor(
 
         if let Some((miner_pubkey, claim_queue_item)) = claim {
             info!(target: "server_log", "Processing claim...");
+            let Ok(miner) = database.get_miner_by_pubkey_str(miner_pubkey.to_string()).await else {
+                error!(target: "server_log", "Failed to load miner {} for claim, will retry on next iteration.", miner_pubkey);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            };
+            let Ok(pool_rewards) = database.get_miner_pool_rewards(miner.id).await else {
+                error!(target: "server_log", "Failed to load rewards of miner {} for claim, will retry on next iteration.", miner_pubkey);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            };
+
+            // MI: rewards are kept per pool, each pool pays its share from its own proof
+            let mut balances = vec![];
+            let mut proofs_loaded = true;
+            for reward in pool_rewards.iter() {
+                let Some(pool) =
+                    pools.iter().find(|pool| pool.mine_config.pool_id == reward.pool_id)
+                else {
+                    continue;
+                };
+                match utils::get_mini_pool_proof(&rpc_client, pool.authority).await {
+                    Ok(proof) => {
+                        balances.push((reward.pool_id, reward.balance as u64, proof.balance))
+                    },
+                    Err(e) => {
+                        error!(target: "server_log", "Failed to load proof of pool {}: {}", pool.authority, e);
+                        proofs_loaded = false;
+                    },
+                }
+            }
+            if !proofs_loaded {
+                METRICS.claim_failure();
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            }
+            let Some(parts) = split_claim(claim_queue_item.amount, &balances) else {
+                error!(target: "server_log", "Claim of {} grains by miner {} exceeds what its pools can pay, dropping it.", claim_queue_item.amount, miner_pubkey);
+                METRICS.claim_failure();
+                claims_queue.queue.write().await.remove(&miner_pubkey);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            };
+            let wallets: Vec<Arc<Keypair>> = parts
+                .iter()
+                .filter_map(|part| {
+                    pools.iter().find(|pool| pool.mine_config.pool_id == part.pool_id)
+                })
+                .map(|pool| pool.wallet.miner_wallet.clone())
+                .collect();
+            let wallet = wallets[0].clone();
+
             let ore_mint = utils::get_ore_mint();
             let receiver_pubkey = claim_queue_item.receiver_pubkey;
             let receiver_token_account = get_associated_token_address(&receiver_pubkey, &ore_mint);
 
-            let prio_fee: u32 = 20_000;
+            let mut prio_fee: u32 = 20_000;
 
             let mut is_creating_ata = false;
             let mut ixs = Vec::new();
@@ -54,7 +165,7 @@ pub async fn claim_processor(
                     info!(target: "server_log", "will create token account for miner");
                     ixs.push(
                         spl_associated_token_account::instruction::create_associated_token_account(
-                            &wallet.pubkey(),
+                            &fee_wallet.pubkey(),
                             &receiver_pubkey,
                             &ore_api::consts::MINT_ADDRESS,
                             &spl_token::id(),
@@ -66,7 +177,7 @@ pub async fn claim_processor(
                 is_creating_ata = true;
                 ixs.push(
                     spl_associated_token_account::instruction::create_associated_token_account(
-                        &wallet.pubkey(),
+                        &fee_wallet.pubkey(),
                         &receiver_pubkey,
                         &ore_api::consts::MINT_ADDRESS,
                         &spl_token::id(),
@@ -76,23 +187,52 @@ pub async fn claim_processor(
 
             let amount = claim_queue_item.amount;
 
-            let mut claim_amount = amount;
             // 0.00400000000 ORE
-            if is_creating_ata {
-                // claim_amount = amount - 400_000_000
-                claim_amount = amount - CREATE_ATA_DEDUCTION
-            }
+            let deduction = if is_creating_ata { CREATE_ATA_DEDUCTION } else { 0 };
             // claim from mini pool (same as operator's solo proof)
-            let ix = utils::get_claim_ix(wallet.pubkey(), receiver_token_account, claim_amount);
-            ixs.push(ix);
+            for (wallet, claim_amount) in wallets.iter().zip(claim_ix_amounts(&parts, deduction)) {
+                let ix = utils::get_claim_ix(wallet.pubkey(), receiver_token_account, claim_amount);
+                ixs.push(ix);
+            }
 
-            if let Ok((hash, _slot)) =
-                rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment()).await
-            {
+            let claim_nonce = claim_nonces.get(&wallet.pubkey());
+            let blockhash = if let Some(claim_nonce) = claim_nonce {
+                ixs.insert(0, claim_nonce.advance_ix());
+                claim_nonce.blockhash(&rpc_client).await.ok()
+            } else {
+                rpc_client
+                    .get_latest_blockhash_with_commitment(rpc_client.commitment())
+                    .await
+                    .ok()
+                    .map(|(hash, _slot)| hash)
+            };
+
+            if let Some(hash) = blockhash {
                 let expired_timer = Instant::now();
-                let mut tx = Transaction::new_with_payer(&ixs, Some(&wallet.pubkey()));
+                let mut nonce_timer = Instant::now();
+                let mut nonce_used = false;
+                let mut tx = Transaction::new_with_payer(&ixs, Some(&fee_wallet.pubkey()));
 
-                tx.sign(&[&wallet], hash);
+                let mut signers: Vec<&Keypair> = vec![&fee_wallet];
+                signers.extend(wallets.iter().map(|wallet| &**wallet));
+                tx.sign(&signers, hash);
+
+                if let Some(ref dry_run) = dry_run {
+                    // MI: dry run, the claim is only simulated and leaves the queue, rewards
+                    // balances stay as they are
+                    let res = dry_run.simulate(&rpc_client, &tx).await;
+                    dry_run.record(
+                        "claim",
+                        wallet.pubkey(),
+                        &tx.signatures[0],
+                        prio_fee as u64,
+                        None,
+                        &res,
+                    );
+                    claims_queue.queue.write().await.remove(&miner_pubkey);
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    continue;
+                }
 
                 let rpc_config = RpcSendTransactionConfig {
                     preflight_commitment: Some(rpc_client.commitment().commitment),
@@ -107,68 +247,139 @@ pub async fn claim_processor(
                         break;
                     } else {
                         error!(target: "server_log", "Failed to send claim transaction. retrying in 2 seconds...");
+                        METRICS.claim_failure();
                         tokio::time::sleep(Duration::from_millis(2000)).await;
                     }
                 }
 
-                let result: Result<Signature, String> = loop {
-                    if expired_timer.elapsed().as_secs() >= 200 {
+                // MI: every tx signed on the nonce with its fee, at most one of them can land
+                let mut signed = vec![(signature, prio_fee)];
+                let result: Result<(Signature, u32), String> = loop {
+                    if let Some(claim_nonce) = claim_nonce {
+                        // MI: a nonce tx never expires, it lands or another tx uses the nonce.
+                        // Giving up before would risk paying the claim twice.
+                        if nonce_timer.elapsed().as_secs() >= 30 {
+                            nonce_timer = Instant::now();
+                            match claim_nonce.fetch(&rpc_client).await {
+                                Ok(nonce) if nonce != hash => {
+                                    if nonce_used {
+                                        break Err("Nonce used by another transaction".to_string());
+                                    }
+                                    // MI: one more status check with history before giving up
+                                    nonce_used = true;
+                                },
+                                _ if nonce_used => {},
+                                _ if signed.len() <= CLAIM_NONCE_RESIGNS => {
+                                    // MI: not landing, outbid on the same nonce value
+                                    prio_fee = raised_claim_fee(prio_fee, signed.len());
+                                    ixs[1] = ComputeBudgetInstruction::set_compute_unit_price(
+                                        prio_fee as u64,
+                                    );
+                                    tx = Transaction::new_with_payer(
+                                        &ixs,
+                                        Some(&fee_wallet.pubkey()),
+                                    );
+                                    tx.sign(&signers, hash);
+                                    info!(target: "server_log", "Claim tx not landed yet, resigned with priority fee {}", prio_fee);
+                                    let _ = rpc_client
+                                        .send_transaction_with_config(&tx, rpc_config)
+                                        .await;
+                                    signed.push((tx.signatures[0], prio_fee));
+                                },
+                                _ => {
+                                    // MI: give up, advancing the nonce voids every tx signed on
+                                    // it so the claim can be retried without paying it twice
+                                    error!(target: "server_log", "Claim tx not landed after {} signatures, advancing the nonce to void them.", signed.len());
+                                    match claim_nonce
+                                        .advance(&rpc_client, &fee_wallet, &wallet)
+                                        .await
+                                    {
+                                        Ok(_) => nonce_used = true,
+                                        Err(e) => {
+                                            error!(target: "server_log", "Failed to advance claim nonce: {}", e)
+                                        },
+                                    }
+                                },
+                            }
+                        }
+                    } else if expired_timer.elapsed().as_secs() >= 200 {
                         break Err("Transaction Expired".to_string());
                     }
-                    let results = rpc_client.get_signature_statuses(&[signature]).await;
+                    let signatures: Vec<Signature> = signed.iter().map(|(sig, _)| *sig).collect();
+                    let results = if nonce_used {
+                        rpc_client.get_signature_statuses_with_history(&signatures).await
+                    } else {
+                        rpc_client.get_signature_statuses(&signatures).await
+                    };
                     if let Ok(response) = results {
-                        let statuses = response.value;
-                        if let Some(status) = &statuses[0] {
-                            if status.confirmation_status()
-                                == TransactionConfirmationStatus::Confirmed
-                            {
-                                if status.err.is_some() {
-                                    let e_str = format!("Transaction Failed: {:?}", status.err);
-                                    break Err(e_str);
-                                }
-                                break Ok(signature);
+                        let landed =
+                            response.value.iter().zip(signed.iter()).find(|(status, _)| {
+                                status.as_ref().is_some_and(|status| {
+                                    status.confirmation_status()
+                                        == TransactionConfirmationStatus::Confirmed
+                                })
+                            });
+                        if let Some((Some(status), &(sig, fee))) = landed {
+                            if status.err.is_some() {
+                                let e_str = format!("Transaction Failed: {:?}", status.err);
+                                break Err(e_str);
                             }
+                            break Ok((sig, fee));
                         }
                     }
                     tokio::time::sleep(Duration::from_millis(500)).await;
                 };
+                if let Some(claim_nonce) = claim_nonce {
+                    claim_nonce.invalidate();
+                }
 
                 match result {
-                    Ok(sig) => {
+                    Ok((sig, prio_fee)) => {
                         let amount_dec = amount as f64 / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
                         info!(target: "server_log", "Miner {} successfully claimed {}.\nSig: {}", miner_pubkey.to_string(), amount_dec, sig.to_string());
+                        publish(
+                            &event_sender,
+                            PoolEvent::ClaimProcessed {
+                                pubkey: miner_pubkey.to_string(),
+                                amount: amount_dec,
+                                signature: sig.to_string(),
+                            },
+                        );
 
                         // TODO: use transacions, or at least put them into one query
-                        let miner = database
-                            .get_miner_by_pubkey_str(miner_pubkey.to_string())
-                            .await
-                            .unwrap();
-                        let mining_pool = database
-                            .get_pool_by_authority_pubkey(wallet.pubkey().to_string())
-                            .await
-                            .unwrap();
-                        while let Err(_) =
-                            database.decrease_miner_reward(miner.id, amount as i64).await
-                        {
-                            error!(target: "server_log", "Failed to decrease miner rewards! Retrying...");
-                            tokio::time::sleep(Duration::from_millis(2000)).await;
-                        }
-                        while let Err(_) = database
-                            .update_pool_claimed(wallet.pubkey().to_string(), amount as i64)
-                            .await
-                        {
-                            error!(target: "server_log", "Failed to increase pool claimed amount! Retrying...");
-                            tokio::time::sleep(Duration::from_millis(2000)).await;
+                        for (part, wallet) in parts.iter().zip(wallets.iter()) {
+                            while database
+                                .decrease_miner_reward(miner.id, part.pool_id, part.amount as i64)
+                                .await
+                                .is_err()
+                            {
+                                error!(target: "server_log", "Failed to decrease miner rewards! Retrying...");
+                                METRICS.db_write_retry("decrease_miner_reward");
+                                tokio::time::sleep(Duration::from_millis(2000)).await;
+                            }
+                            while database
+                                .update_pool_claimed(
+                                    wallet.pubkey().to_string(),
+                                    part.amount as i64,
+                                )
+                                .await
+                                .is_err()
+                            {
+                                error!(target: "server_log", "Failed to increase pool claimed amount! Retrying...");
+                                METRICS.db_write_retry("update_pool_claimed");
+                                tokio::time::sleep(Duration::from_millis(2000)).await;
+                            }
                         }
 
                         let itxn = InsertTransaction {
                             transaction_type: "claim".to_string(),
                             signature: sig.to_string(),
                             priority_fee: prio_fee as i32,
-                            pool_id: mining_pool.id,
+                            pool_id: parts[0].pool_id,
                         };
                         while let Err(_) = database.add_new_transaction(itxn.clone()).await {
                             error!(target: "server_log", "Failed to increase pool claimed amount! Retrying...");
+                            METRICS.db_write_retry("add_new_transaction");
                             tokio::time::sleep(Duration::from_millis(2000)).await;
                         }
 
@@ -185,15 +396,18 @@ pub async fn claim_processor(
                             }
                         }
 
-                        let iclaim = InsertClaim {
-                            miner_id: miner.id,
-                            pool_id: mining_pool.id,
-                            transaction_id: txn_id,
-                            amount: amount as i64,
-                        };
-                        while let Err(_) = database.add_new_claim(iclaim).await {
-                            error!(target: "server_log", "Failed add new claim to db! Retrying...");
-                            tokio::time::sleep(Duration::from_millis(2000)).await;
+                        for part in parts.iter() {
+                            let iclaim = InsertClaim {
+                                miner_id: miner.id,
+                                pool_id: part.pool_id,
+                                transaction_id: txn_id,
+                                amount: part.amount as i64,
+                            };
+                            while database.add_new_claim(iclaim).await.is_err() {
+                                error!(target: "server_log", "Failed add new claim to db! Retrying...");
+                                METRICS.db_write_retry("add_new_claim");
+                                tokio::time::sleep(Duration::from_millis(2000)).await;
+                            }
                         }
 
                         let mut writer = claims_queue.queue.write().await;
@@ -204,13 +418,56 @@ pub async fn claim_processor(
                     },
                     Err(e) => {
                         error!(target: "server_log", "ERROR: {:?}", e);
+                        METRICS.claim_failure();
                     },
                 }
             } else {
                 error!(target: "server_log", "Failed to confirm transaction, will retry on next iteration.");
+                METRICS.claim_failure();
             }
         }
 
         tokio::time::sleep(Duration::from_secs(10)).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_claim_one_pool_covers_stays_whole() {
+        let parts = split_claim(100, &[(1, 150, 1_000), (2, 50, 1_000)]).unwrap();
+        assert_eq!(parts, vec![ClaimPart { pool_id: 1, amount: 100 }]);
+    }
+
+    #[test]
+    fn a_claim_is_split_over_rewards_and_proof_balances() {
+        // pool 1 has the rewards but its proof is short, pool 2 pays the rest
+        let parts = split_claim(100, &[(1, 80, 60), (2, 50, 1_000), (3, 10, 1_000)]).unwrap();
+        assert_eq!(
+            parts,
+            vec![ClaimPart { pool_id: 1, amount: 60 }, ClaimPart { pool_id: 2, amount: 40 },]
+        );
+    }
+
+    #[test]
+    fn a_claim_the_pools_cannot_cover_is_refused() {
+        assert_eq!(split_claim(100, &[(1, 80, 1_000), (2, 50, 10)]), None);
+        assert_eq!(split_claim(100, &[]), None);
+    }
+
+    #[test]
+    fn resigned_claims_raise_the_fee() {
+        assert_eq!(raised_claim_fee(20_000, 1), 30_000);
+        assert_eq!(raised_claim_fee(30_000, 2), 60_000);
+        assert_eq!(raised_claim_fee(u32::MAX, 3), u32::MAX);
+    }
+
+    #[test]
+    fn the_ata_deduction_spills_over_parts() {
+        let parts = [ClaimPart { pool_id: 1, amount: 30 }, ClaimPart { pool_id: 2, amount: 70 }];
+        assert_eq!(claim_ix_amounts(&parts, 0), vec![30, 70]);
+        assert_eq!(claim_ix_amounts(&parts, 40), vec![0, 60]);
+    }
+}
@@ -1,5 +1,9 @@
 #[allow(unused_imports)]
 use crate::{
+    events::{publish, PoolEvent},
+    hashrate::HashrateEstimator,
+    metrics::METRICS,
+    pools::ClientNonceRanges,
     utils, AppState, ClientMessage, EpochHashes, InternalMessageContribution, LastPong,
     HASHPOWER_CAP, MIN_DIFF, UNIT_HASHPOWER,
 };
@@ -10,8 +14,8 @@ use {
     futures::SinkExt,
     ore_api::state::Proof,
     solana_sdk::pubkey::Pubkey,
-    std::{collections::HashMap, net::SocketAddr, ops::Range, sync::Arc},
-    tokio::sync::{mpsc::UnboundedReceiver, Mutex, RwLock},
+    std::{net::SocketAddr, ops::Range, sync::Arc},
+    tokio::sync::{broadcast, mpsc::UnboundedReceiver, Mutex, RwLock},
     tracing::{debug, error, info, warn},
     uuid::Uuid,
 };
@@ -24,8 +28,10 @@ pub async fn client_contributions_processor(
     mut receiver_channel: UnboundedReceiver<ClientBestSolution>,
     proof: Arc<Mutex<Proof>>,
     epoch_hashes: Arc<RwLock<EpochHashes>>,
-    client_nonce_ranges: Arc<RwLock<HashMap<Pubkey, Range<u64>>>>,
+    client_nonce_ranges: ClientNonceRanges,
     app_state: Arc<RwLock<AppState>>,
+    hashrate_estimator: Arc<RwLock<HashrateEstimator>>,
+    event_sender: broadcast::Sender<PoolEvent>,
     min_difficulty: u32,
 ) {
     loop {
@@ -45,6 +51,7 @@ pub async fn client_contributions_processor(
                         nr.clone()
                     } else {
                         error!(target: "server_log", "Client nonce range not set!");
+                        METRICS.contribution_rejected("nonce_range_not_set");
                         return;
                     }
                 };
@@ -55,6 +62,7 @@ pub async fn client_contributions_processor(
 
                 if !nonce_range.contains(&nonce) {
                     error!(target: "server_log", "❌ Client submitted nonce out of assigned range");
+                    METRICS.contribution_rejected("nonce_out_of_range");
                     continue;
                 }
 
@@ -64,6 +72,7 @@ pub async fn client_contributions_processor(
                     miner_id = app_client_socket.miner_id;
                 } else {
                     error!(target: "server_log", "Failed to get client socket for addr: {}", addr);
+                    METRICS.contribution_rejected("unknown_socket");
                     continue;
                 }
                 drop(reader);
@@ -112,6 +121,10 @@ pub async fn client_contributions_processor(
                         None, // No hashpower cap
                     );
 
+                    // the worker's best submission of this epoch stands for its work
+                    hashrate_estimator.write().await.record(pubkey, addr, challenge, diff);
+                    METRICS.contribution_accepted();
+
                     {
                         let reader = epoch_hashes.read().await;
                         let subs = reader.contributions.clone();
@@ -136,6 +149,13 @@ pub async fn client_contributions_processor(
                                     info!(target: "contribution_log", "{} - New best diff: {}", contribution_uuid, diff);
                                     epoch_hashes.best_hash.difficulty = diff;
                                     epoch_hashes.best_hash.solution = Some(solution);
+                                    publish(
+                                        &event_sender,
+                                        PoolEvent::BestDifficultyUpdated {
+                                            difficulty: diff,
+                                            pubkey: pubkey_str.clone(),
+                                        },
+                                    );
                                 }
                                 drop(epoch_hashes);
                             } else {
@@ -161,6 +181,13 @@ pub async fn client_contributions_processor(
                                 info!(target: "contribution_log", "{} - New best diff: {}", contribution_uuid, diff);
                                 epoch_hashes.best_hash.difficulty = diff;
                                 epoch_hashes.best_hash.solution = Some(solution);
+                                publish(
+                                    &event_sender,
+                                    PoolEvent::BestDifficultyUpdated {
+                                        difficulty: diff,
+                                        pubkey: pubkey_str.clone(),
+                                    },
+                                );
                             }
                             drop(epoch_hashes);
                             // info!(target: "contribution_log", "{} - Added {}
@@ -170,6 +197,7 @@ pub async fn client_contributions_processor(
                     }
                     // tokio::time::sleep(Duration::from_millis(100)).await;
                 } else {
+                    METRICS.contribution_rejected("invalid_solution");
                     error!(target: "server_log",
                         "{} returned an invalid solution for latest challenge!",
                         // pubkey
@@ -187,6 +215,7 @@ pub async fn client_contributions_processor(
                 }
             } else {
                 warn!(target: "server_log", "Diff too low, skipping");
+                METRICS.contribution_rejected("diff_too_low");
             }
         } else {
             // receiver_channel got None, the stream ended.
@@ -1,20 +1,15 @@
+use crate::pools::ClientNonceRanges;
 #[allow(unused_imports)]
 use crate::{
-    utils, AppState, ClientMessage, EpochHashes, InternalMessageContribution, LastPong,
-    HASHPOWER_CAP, MIN_DIFF, UNIT_HASHPOWER,
+    events::PoolEvent, hashrate::HashrateEstimator, utils, AppState, ClientMessage, EpochHashes,
+    InternalMessageContribution, LastPong, HASHPOWER_CAP, MIN_DIFF, UNIT_HASHPOWER,
 };
 use {
     super::client_contributions_processor::{client_contributions_processor, ClientBestSolution},
     ore_api::state::Proof,
-    solana_sdk::pubkey::Pubkey,
-    std::{
-        collections::{HashMap, HashSet},
-        net::SocketAddr,
-        ops::Range,
-        sync::Arc,
-    },
+    std::{collections::HashSet, net::SocketAddr, sync::Arc},
     tokio::{
-        sync::{mpsc::UnboundedReceiver, Mutex, RwLock},
+        sync::{broadcast, mpsc::UnboundedReceiver, Mutex, RwLock},
         time::Instant,
     },
     tracing::{info, warn},
@@ -26,8 +21,10 @@ pub async fn client_message_processor(
     epoch_hashes: Arc<RwLock<EpochHashes>>,
     ready_clients: Arc<Mutex<HashSet<SocketAddr>>>,
     proof: Arc<Mutex<Proof>>,
-    client_nonce_ranges: Arc<RwLock<HashMap<Pubkey, Range<u64>>>>,
+    client_nonce_ranges: ClientNonceRanges,
     app_pongs: Arc<RwLock<LastPong>>,
+    hashrate_estimator: Arc<RwLock<HashrateEstimator>>,
+    event_sender: broadcast::Sender<PoolEvent>,
     min_difficulty: u32,
 ) {
     let (s, r) = tokio::sync::mpsc::unbounded_channel::<ClientBestSolution>();
@@ -43,6 +40,8 @@ pub async fn client_message_processor(
             app_epoch_hashes,
             app_client_nonce_ranges,
             app_app_state,
+            hashrate_estimator,
+            event_sender,
             min_difficulty,
         )
         .await;
@@ -15,7 +15,10 @@ pub async fn messaging_all_clients_processor(
         while let Some(msg) = all_clients_receiver.recv().await {
             {
                 let shared_state = app_shared_state.read().await;
-                let socks = shared_state.sockets.clone();
+                let socks = match msg.pool.as_ref() {
+                    Some(pool) => shared_state.pool_sockets(pool),
+                    None => shared_state.sockets.clone(),
+                };
                 drop(shared_state);
                 for (_socket_addr, socket_sender) in socks.iter() {
                     let text = msg.text.clone();
@@ -0,0 +1,205 @@
+use {
+    crate::{
+        database::Database,
+        events::PoolEvent,
+        miner_notifications::{MinerEvent, MinerNotificationsConfig, MinerWebhookNotifier},
+        models::MinerNotification,
+        notification::{send_with_retries, Notification, Notifier},
+        utils::ORE_TOKEN_DECIMALS,
+        AppState,
+    },
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::{
+        broadcast::{self, error::RecvError},
+        RwLock,
+    },
+    tracing::{error, warn},
+};
+
+// MI: subscriptions are reloaded on every check, so changes apply within a minute
+const MINER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct MinerWatch {
+    workers: usize,
+    offline_since: Option<Instant>,
+    offline_notified: bool,
+}
+
+fn ore(grains: i64) -> f64 {
+    grains as f64 / 10f64.powf(ORE_TOKEN_DECIMALS as f64)
+}
+
+/// Delivers in the background, retries of a slow webhook or smtp server don't hold up the checks.
+fn notify(
+    config: &Arc<MinerNotificationsConfig>,
+    subscription: &MinerNotification,
+    event: MinerEvent,
+    message: String,
+) {
+    let notifier: Box<dyn Notifier> = match subscription.channel.as_str() {
+        "webhook" => Box::new(MinerWebhookNotifier::new(&subscription.target)),
+        "email" => match config.email.as_ref().map(|email| email.notifier(&subscription.target)) {
+            Some(Ok(notifier)) => Box::new(notifier),
+            Some(Err(e)) => {
+                error!(target: "server_log", "Miner {} email notifier: {}", subscription.pubkey, e);
+                return;
+            },
+            None => {
+                warn!(target: "server_log", "Miner {} subscribed by email, but no miner_email is configured.", subscription.pubkey);
+                return;
+            },
+        },
+        channel => {
+            warn!(target: "server_log", "Miner {} has unknown notification channel {}", subscription.pubkey, channel);
+            return;
+        },
+    };
+
+    let text = message.clone();
+    let notification = Notification::Miner {
+        pubkey: subscription.pubkey.clone(),
+        event: event.as_str().to_string(),
+        message,
+    };
+    tokio::spawn(async move {
+        send_with_retries(notifier.as_ref(), &notification, &text).await;
+    });
+}
+
+/// Watches the connected workers of each subscribed miner, sends the periodic earnings digest
+/// and forwards the miner's processed claims.
+pub async fn miner_notification_processor(
+    config: Arc<MinerNotificationsConfig>,
+    database: Arc<Database>,
+    app_state: Arc<RwLock<AppState>>,
+    mut events: broadcast::Receiver<PoolEvent>,
+) {
+    let mut interval = tokio::time::interval(MINER_CHECK_INTERVAL);
+    let digest_interval = Duration::from_secs(config.digest_hours * 3600);
+    let mut last_digest = Instant::now();
+    let mut subscriptions: HashMap<String, MinerNotification> = HashMap::new();
+    let mut watches: HashMap<String, MinerWatch> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            event = events.recv() => {
+                match event {
+                    Ok(PoolEvent::ClaimProcessed { pubkey, amount, signature }) => {
+                        if let Some(subscription) = subscriptions.get(&pubkey) {
+                            if MinerEvent::parse_list(&subscription.events)
+                                .contains(&MinerEvent::ClaimCompleted)
+                            {
+                                let message = format!(
+                                    "Claim of {} ORE completed.\nSig: {}",
+                                    amount, signature
+                                );
+                                notify(&config, subscription, MinerEvent::ClaimCompleted, message);
+                            }
+                        }
+                    },
+                    Ok(_) | Err(RecvError::Lagged(_)) => {},
+                    Err(RecvError::Closed) => return,
+                }
+                continue;
+            },
+        }
+
+        match database.get_miner_notifications().await {
+            Ok(rows) => {
+                subscriptions = rows.into_iter().map(|row| (row.pubkey.clone(), row)).collect();
+            },
+            Err(e) => {
+                error!(target: "server_log", "Failed to load miner notification subscriptions: {:?}", e);
+                continue;
+            },
+        }
+        watches.retain(|pubkey, _| subscriptions.contains_key(pubkey));
+
+        let mut workers: HashMap<String, usize> = HashMap::new();
+        for client_connection in app_state.read().await.sockets.values() {
+            *workers.entry(client_connection.pubkey.to_string()).or_default() += 1;
+        }
+
+        let now = Instant::now();
+        for (pubkey, subscription) in subscriptions.iter() {
+            let subscribed = MinerEvent::parse_list(&subscription.events);
+            let num_workers = workers.get(pubkey).copied().unwrap_or(0);
+            let watch = watches
+                .entry(pubkey.clone())
+                .or_insert_with(|| MinerWatch { workers: num_workers, ..Default::default() });
+
+            if subscribed.contains(&MinerEvent::WorkersDropped) && num_workers < watch.workers {
+                let message =
+                    format!("Connected workers dropped from {} to {}.", watch.workers, num_workers);
+                notify(&config, subscription, MinerEvent::WorkersDropped, message);
+            }
+
+            if num_workers > 0 {
+                if watch.offline_notified && subscribed.contains(&MinerEvent::Offline) {
+                    let message = format!("Back online with {} workers.", num_workers);
+                    notify(&config, subscription, MinerEvent::Offline, message);
+                }
+                watch.offline_since = None;
+                watch.offline_notified = false;
+            } else {
+                // MI: also counts from the first check, a rig that never came back after a
+                // server restart is offline as well
+                let since = *watch.offline_since.get_or_insert(now);
+                let offline_mins = now.duration_since(since).as_secs() / 60;
+                if subscribed.contains(&MinerEvent::Offline)
+                    && !watch.offline_notified
+                    && offline_mins >= subscription.offline_mins as u64
+                {
+                    let message =
+                        format!("All workers have been offline for {} minutes.", offline_mins);
+                    notify(&config, subscription, MinerEvent::Offline, message);
+                    watch.offline_notified = true;
+                }
+            }
+            watch.workers = num_workers;
+        }
+
+        if config.digest_hours > 0 && now.duration_since(last_digest) >= digest_interval {
+            last_digest = now;
+            for (pubkey, subscription) in subscriptions.iter() {
+                if !MinerEvent::parse_list(&subscription.events)
+                    .contains(&MinerEvent::EarningsDigest)
+                {
+                    continue;
+                }
+                let earned = match database
+                    .get_miner_earnings_for_last_hours(
+                        subscription.miner_id,
+                        config.digest_hours as i32,
+                    )
+                    .await
+                {
+                    Ok(earned) => earned,
+                    Err(e) => {
+                        error!(target: "server_log", "Failed to load earnings of miner {}: {:?}", pubkey, e);
+                        continue;
+                    },
+                };
+                let balance = database
+                    .get_miner_rewards(pubkey.clone())
+                    .await
+                    .map(|reward| reward.balance)
+                    .unwrap_or(0);
+                let message = format!(
+                    "Earned {} ORE in the last {} hours.\nUnclaimed rewards: {} ORE\nConnected workers: {}",
+                    ore(earned),
+                    config.digest_hours,
+                    ore(balance),
+                    workers.get(pubkey).copied().unwrap_or(0)
+                );
+                notify(&config, subscription, MinerEvent::EarningsDigest, message);
+            }
+        }
+    }
+}
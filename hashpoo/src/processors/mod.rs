@@ -1,6 +1,9 @@
 #[cfg(feature = "powered-by-dbms-postgres")]
 pub mod claim_processor;
+#[cfg(feature = "powered-by-dbms-postgres")]
+pub mod miner_notification_processor;
 
+pub mod alert_processor;
 pub mod client_contributions_processor;
 pub mod client_message_processor;
 pub mod messaging_all_clients_processor;
@@ -11,3 +14,5 @@ pub mod pool_submission_processor;
 pub mod proof_tracking_processor;
 pub mod ready_clients_processor;
 pub mod reporting_processor;
+pub mod rpc_health_processor;
+pub mod submission_prefetch_processor;
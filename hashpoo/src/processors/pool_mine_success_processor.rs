@@ -1,7 +1,9 @@
 use {
     crate::{
         database::{Database, PoweredByDbms},
+        events::{publish, PoolEvent},
         message::ServerMessagePoolSubmissionResult,
+        metrics::METRICS,
         utils::ORE_TOKEN_DECIMALS,
         AppState, ClientVersion, InsertContribution, InsertEarning, InternalMessageContribution,
         MessageInternalMineSuccess, MineConfig, UpdateReward, WalletExtension, POWERED_BY_DBMS,
@@ -13,7 +15,7 @@ use {
     solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer},
     std::{ops::Div, str::FromStr, sync::Arc, time::Duration},
     tokio::{
-        sync::{mpsc::UnboundedReceiver, RwLock},
+        sync::{broadcast, mpsc::UnboundedReceiver, RwLock},
         time::Instant,
     },
     tracing::{error, info},
@@ -26,9 +28,12 @@ pub async fn pool_mine_success_processor(
     app_database: Arc<Database>,
     app_wallet: Arc<WalletExtension>,
     mut mine_success_receiver: UnboundedReceiver<MessageInternalMineSuccess>,
+    event_sender: broadcast::Sender<PoolEvent>,
 ) {
     let database = app_database;
     let mine_config = app_mine_config;
+    // MI: only the workers of this pool are told about its results
+    let pool_authority = app_wallet.miner_wallet.pubkey();
     let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
         let key = "POWERED_BY_DBMS";
         match std::env::var(key) {
@@ -49,12 +54,13 @@ pub async fn pool_mine_success_processor(
             };
             let id = uuid::Uuid::new_v4();
             let c = BASE64_STANDARD.encode(msg.challenge);
+            let num_contributors = msg.contributions.len();
             info!(target: "server_log", "{} - Processing internal mine success for challenge: {}", id, c);
             let instant = Instant::now();
             info!(target: "server_log", "{} - Getting sockets.", id);
             let shared_state = app_shared_state.read().await;
-            let len = shared_state.sockets.len();
-            let socks = shared_state.sockets.clone();
+            let socks = shared_state.pool_sockets(&pool_authority);
+            let len = socks.len();
             drop(shared_state);
             info!(target: "server_log", "{} - Got sockets in {}ms.", id, instant.elapsed().as_millis());
 
@@ -96,6 +102,7 @@ pub async fn pool_mine_success_processor(
 
                     let new_reward = UpdateReward {
                         miner_id: msg_contribution.miner_id,
+                        pool_id: mine_config.pool_id,
                         balance: earned_rewards,
                     };
 
@@ -190,11 +197,13 @@ pub async fn pool_mine_success_processor(
                     for batch in i_earnings.chunks(batch_size) {
                         while let Err(_) = database.add_new_earnings_batch(batch.to_vec()).await {
                             tracing::error!(target: "server_log", "{} - Failed to add new earnings batch to db. Retrying...", id);
+                            METRICS.db_write_retry("add_new_earnings_batch");
                             tokio::time::sleep(Duration::from_millis(500)).await;
                         }
                         tokio::time::sleep(Duration::from_millis(200)).await;
                     }
                     info!(target: "server_log", "{} - Successfully added earnings batch", id);
+                    METRICS.observe_db_write("add_new_earnings_batch", instant.elapsed());
                 }
                 info!(target: "server_log", "{} - Added earnings in {}ms", id, instant.elapsed().as_millis());
 
@@ -209,6 +218,7 @@ pub async fn pool_mine_success_processor(
                         info!(target: "server_log", "{} - Updating reward batch {}", id, batch_num);
                         while let Err(_) = database.update_rewards(batch.to_vec()).await {
                             error!(target: "server_log", "{} - Failed to update rewards in db. Retrying...", id);
+                            METRICS.db_write_retry("update_rewards");
                             tokio::time::sleep(Duration::from_millis(500)).await;
                         }
                         info!(target: "server_log", "{} - Updated reward batch {} in {}ms", id, batch_num, instant.elapsed().as_millis());
@@ -216,6 +226,7 @@ pub async fn pool_mine_success_processor(
                         tokio::time::sleep(Duration::from_millis(200)).await;
                     }
                     info!(target: "server_log", "{} - Successfully updated rewards", id);
+                    METRICS.observe_db_write("update_rewards", instant.elapsed());
                 }
                 info!(target: "server_log", "{} - Updated rewards in {}ms", id, instant.elapsed().as_millis());
 
@@ -230,12 +241,14 @@ pub async fn pool_mine_success_processor(
                             database.add_new_contributions_batch(batch.to_vec()).await
                         {
                             error!(target: "server_log", "{} - Failed to add new contributions batch. Retrying...", id);
+                            METRICS.db_write_retry("add_new_contributions_batch");
                             tokio::time::sleep(Duration::from_millis(500)).await;
                         }
                         tokio::time::sleep(Duration::from_millis(200)).await;
                     }
 
                     info!(target: "server_log", "{} - Successfully added contributions batch", id);
+                    METRICS.observe_db_write("add_new_contributions_batch", instant.elapsed());
                 }
                 info!(target: "server_log", "{} - Added contributions in {}ms", id, instant.elapsed().as_millis());
 
@@ -248,8 +261,10 @@ pub async fn pool_mine_success_processor(
                     .await
                 {
                     error!(target: "server_log", "{} - Failed to update pool rewards! Retrying...", id);
+                    METRICS.db_write_retry("update_pool_rewards");
                     tokio::time::sleep(Duration::from_millis(1000)).await;
                 }
+                METRICS.observe_db_write("update_pool_rewards", instant.elapsed());
                 info!(target: "server_log", "{} - Updated pool rewards in {}ms", id, instant.elapsed().as_millis());
 
                 tokio::time::sleep(Duration::from_millis(200)).await;
@@ -288,8 +303,8 @@ pub async fn pool_mine_success_processor(
                 // let decimals = 10f64.powf(ORE_TOKEN_DECIMALS as f64);
                 let pool_rewards_dec = (msg.rewards as f64).div(decimals);
                 let shared_state = app_shared_state.read().await;
-                let len = shared_state.sockets.len();
-                let socks = shared_state.sockets.clone();
+                let socks = shared_state.pool_sockets(&pool_authority);
+                let len = socks.len();
                 drop(shared_state);
 
                 for (_addr, client_connection) in socks.iter() {
@@ -379,10 +394,23 @@ pub async fn pool_mine_success_processor(
                 }
             }
 
+            publish(
+                &event_sender,
+                PoolEvent::RewardsDistributed {
+                    challenge: c,
+                    difficulty: msg.difficulty,
+                    rewards: (msg.rewards as f64).div(decimals),
+                    balance: msg.total_balance,
+                    num_contributors,
+                },
+            );
+
             if sol_balance_checking % 10 == 0 {
-                if let Ok(balance) =
-                    app_rpc_client.get_balance(&app_wallet.miner_wallet.pubkey()).await
-                {
+                let instant = Instant::now();
+                let balance = app_rpc_client.get_balance(&app_wallet.miner_wallet.pubkey()).await;
+                METRICS.observe_rpc_call("get_balance", instant.elapsed());
+                if let Ok(balance) = balance {
+                    METRICS.set_miner_wallet_balance(app_wallet.miner_wallet.pubkey(), balance);
                     info!(target: "server_log",
                         "Sol Balance(of miner wallet): {:.9}",
                         balance as f64 / LAMPORTS_PER_SOL as f64
@@ -390,6 +418,19 @@ pub async fn pool_mine_success_processor(
                 } else {
                     error!(target: "server_log", "Failed to load balance");
                 }
+
+                let instant = Instant::now();
+                let balance = app_rpc_client.get_balance(&app_wallet.fee_wallet.pubkey()).await;
+                METRICS.observe_rpc_call("get_balance", instant.elapsed());
+                if let Ok(balance) = balance {
+                    METRICS.set_fee_wallet_balance(balance);
+                    info!(target: "server_log",
+                        "Sol Balance(of fee wallet): {:.9}",
+                        balance as f64 / LAMPORTS_PER_SOL as f64
+                    );
+                } else {
+                    error!(target: "server_log", "Failed to load fee wallet balance");
+                }
             }
             sol_balance_checking += 1;
         }
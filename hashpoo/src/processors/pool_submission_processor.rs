@@ -1,17 +1,32 @@
 use {
     crate::{
+        compute_units::ComputeUnitEstimator,
         database::{Database, PoweredByDbms},
-        get_messaging_flags, models,
-        notification::RewardsMessage,
-        pfee, tpu,
+        dry_run::DryRun,
+        durable_nonce::DurableNonce,
+        dynamic_fee::FeeEstimator,
+        events::{publish, PoolEvent},
+        health::HEALTH,
+        jito::JitoClient,
+        metrics::METRICS,
+        models,
+        notification::{Notification, RewardsMessage},
+        pfee,
+        prefetch::SubmissionPrefetch,
+        profitability::{self, ProfitabilityGuard},
+        proof_source::{ProofOrigin, ProofSource},
+        reports::BASE_FEE_LAMPORTS,
+        rpc_pool::RpcPool,
+        tpu,
         utils::{
-            self, get_auth_ix, get_clock, get_config_and_proof, get_cutoff, get_cutoff_with_risk,
-            get_mine_ix, get_mini_pool_proof, get_reset_ix, ORE_TOKEN_DECIMALS,
+            self, cutoff_at, get_auth_ix, get_clock, get_config_and_proof, get_cutoff,
+            get_cutoff_with_risk, get_mine_ix, get_mini_pool_proof, get_reset_ix,
+            ORE_TOKEN_DECIMALS,
         },
-        AppState, EpochHashes, InsertTransaction, MessageInternalAllClients,
-        MessageInternalMineSuccess, MessagingFlags, MineConfig, WalletExtension, CHECK_LIMIT,
-        EPOCH_DURATION, NO_BEST_SOLUTION_INTERVAL, PAUSED, POWERED_BY_DBMS, RPC_RETRIES,
-        SUBMIT_LIMIT,
+        AppState, EpochHashes, InsertSubmissionAttempt, InsertTransaction,
+        MessageInternalAllClients, MessageInternalMineSuccess, MineConfig, WalletExtension,
+        EPOCH_DURATION, NO_BEST_SOLUTION_INTERVAL, POWERED_BY_DBMS, PROOF_UPDATE_TIMEOUT,
+        RPC_RETRIES, SUBMIT_LIMIT,
     },
     base64::{prelude::BASE64_STANDARD, Engine},
     chrono::Local,
@@ -20,63 +35,80 @@ use {
     solana_client::{
         client_error::ClientErrorKind, nonblocking::rpc_client::RpcClient,
         rpc_config::RpcSendTransactionConfig,
-        send_and_confirm_transactions_in_parallel::SendAndConfirmConfig,
     },
     solana_sdk::{
         commitment_config::CommitmentLevel, compute_budget::ComputeBudgetInstruction,
-        signer::Signer, transaction::Transaction,
+        pubkey::Pubkey, signature::Signature, signer::Signer, transaction::Transaction,
     },
     solana_transaction_status::UiTransactionEncoding,
     std::{
         collections::HashMap,
         ops::Mul,
         str::FromStr,
-        sync::{atomic::Ordering::Relaxed, Arc},
-        time::{Duration, SystemTime, UNIX_EPOCH},
+        sync::{
+            atomic::{AtomicBool, Ordering::Relaxed},
+            Arc,
+        },
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     },
-    tokio::sync::{mpsc::UnboundedSender, Mutex, RwLock},
+    tokio::sync::{broadcast, mpsc::UnboundedSender, Mutex, RwLock},
     tracing::{debug, error, info, warn},
 };
 
+// MI: compute unit limit of a mine transaction without the reset instruction
+pub const MINE_TX_CU_LIMIT: u32 = 480_000;
+// MI: and with it, both only used when simulating the transaction fails
+const MINE_RESET_TX_CU_LIMIT: u32 = 500_000;
+// MI: a sent tx that was not confirmed is looked up again after this, its blockhash expired
+const LATE_LANDING_DELAY: Duration = Duration::from_secs(90);
+
 pub async fn pool_submission_processor<'a>(
     app_rpc_client: Arc<RpcClient>,
     app_mine_config: Arc<MineConfig>,
     app_shared_state: Arc<RwLock<AppState>>,
     app_proof: Arc<Mutex<Proof>>,
+    proof_source: Arc<ProofSource>,
     app_epoch_hashes: Arc<RwLock<EpochHashes>>,
+    app_paused: Arc<AtomicBool>,
     app_wallet: Arc<WalletExtension>,
     app_nonce: Arc<Mutex<u64>>,
     app_dynamic_fee: Arc<bool>,
-    app_dynamic_fee_url: Arc<Option<String>>,
+    app_fee_estimator: Arc<dyn FeeEstimator>,
     app_priority_fee: Arc<Option<u64>>,
     app_priority_fee_cap: Arc<Option<u64>>,
     app_extra_fee_difficulty: Arc<u32>,
     app_extra_fee_percent: Arc<u64>,
     app_send_tpu_mine_tx: Arc<bool>,
+    app_jito_client: Option<Arc<JitoClient>>,
+    app_mine_nonce: Option<Arc<DurableNonce>>,
+    app_race_mine_tx: Arc<bool>,
+    app_rpc_pool: Arc<RpcPool>,
+    app_cu_estimator: Option<Arc<ComputeUnitEstimator>>,
+    app_prefetch: Arc<SubmissionPrefetch>,
+    app_profitability: Arc<ProfitabilityGuard>,
+    app_dry_run: Option<Arc<DryRun>>,
     app_no_sound_notification: Arc<bool>,
     app_database: Arc<Database>,
     app_all_clients_sender: UnboundedSender<MessageInternalAllClients>,
     mine_success_sender: UnboundedSender<MessageInternalMineSuccess>,
-    app_slack_message_sender: UnboundedSender<RewardsMessage>,
-    app_discord_message_sender: UnboundedSender<RewardsMessage>,
-    app_slack_difficulty: Arc<u32>,
-    app_messaging_diff: Arc<u32>,
+    app_notification_sender: UnboundedSender<Notification>,
     app_buffer_time: Arc<u64>,
     app_risk_time: Arc<u64>,
+    event_sender: broadcast::Sender<PoolEvent>,
 ) {
     let rpc_client = app_rpc_client;
     let mine_config = app_mine_config;
     let database = app_database;
     let send_tpu_mine_tx = app_send_tpu_mine_tx;
-    let slack_message_sender = app_slack_message_sender;
-    let discord_message_sender = app_discord_message_sender;
-    let slack_difficulty = app_slack_difficulty;
-    let messaging_diff = app_messaging_diff;
-    let messaging_flags = get_messaging_flags();
+    let notification_sender = app_notification_sender;
 
     let mut solution_is_none_counter = 0;
     let mut num_waiting = 0;
+    // MI: unix timestamp of the last simulated mine tx in dry run, 0 otherwise
+    let mut dry_run_epoch_at: i64 = 0;
+    let pool_authority = app_wallet.miner_wallet.pubkey();
     loop {
+        HEALTH.submission_loop_tick(pool_authority);
         let old_proof: Proof;
         let mut lock = app_proof.try_lock();
         if let Ok(ref mut mutex) = lock {
@@ -88,10 +120,15 @@ pub async fn pool_submission_processor<'a>(
         }
         drop(lock);
 
-        let cutoff = if (*app_risk_time).gt(&0) {
-            get_cutoff_with_risk(&rpc_client, old_proof, *app_buffer_time, *app_risk_time).await
+        // MI: in dry run the proof never changes, epochs are timed from the last simulation
+        let mut cutoff_proof = old_proof;
+        cutoff_proof.last_hash_at = cutoff_proof.last_hash_at.max(dry_run_epoch_at);
+        let cutoff = if let Some(timestamp) = app_prefetch.chain_timestamp() {
+            cutoff_at(cutoff_proof, *app_buffer_time, *app_risk_time, timestamp)
+        } else if (*app_risk_time).gt(&0) {
+            get_cutoff_with_risk(&rpc_client, cutoff_proof, *app_buffer_time, *app_risk_time).await
         } else {
-            get_cutoff(&rpc_client, old_proof, *app_buffer_time).await
+            get_cutoff(&rpc_client, cutoff_proof, *app_buffer_time).await
         };
         debug!(target: "server_log", "Enter new loop iteration. Let's check current cutoff value: {cutoff}");
         if cutoff <= 0_i64 {
@@ -101,17 +138,28 @@ pub async fn pool_submission_processor<'a>(
             drop(reader);
 
             let shared_state_lock = app_shared_state.read().await;
-            let num_active_miners = shared_state_lock.sockets.len();
+            let num_active_miners = shared_state_lock
+                .sockets
+                .values()
+                .filter(|client_connection| client_connection.pool.eq(&pool_authority))
+                .count();
             drop(shared_state_lock);
 
             // start to process solution
             if solution.is_some() {
                 let signer = app_wallet.clone().miner_wallet.clone();
+                // MI: the fee wallet pays, the proof authority only signs
+                let fee_payer = app_wallet.fee_wallet.clone();
                 let wallet_pubkey = signer.pubkey();
 
                 let bus = rand::thread_rng().gen_range(0..BUS_COUNT);
 
                 let mut success = false;
+                let mut simulated = false;
+                // MI: the fee guard turned the solution down, not a failure to land
+                let mut not_submitted = false;
+                let mut landed_signature: Option<String> = None;
+                let mut landed_fee: u64 = 0;
                 let reader = app_epoch_hashes.read().await;
                 let best_solution = reader.best_hash.solution.clone();
                 let contributions = reader.contributions.clone();
@@ -134,11 +182,18 @@ pub async fn pool_submission_processor<'a>(
 
                 // set mining pause flag before submitting best solution
                 info!(target: "server_log", "pause new mining mission for pool submission.");
-                PAUSED.store(true, Relaxed);
+                app_paused.store(true, Relaxed);
 
+                // MI: attempts stop one epoch past the cutoff, by then the solution is stale
+                let submit_deadline =
+                    Instant::now() + Duration::from_secs((EPOCH_DURATION + cutoff).max(0) as u64);
                 for i in 0..SUBMIT_LIMIT {
                     if let Some(best_solution) = best_solution {
                         let difficulty = best_solution.to_hash().difficulty();
+                        if Instant::now() >= submit_deadline {
+                            warn!(target: "server_log", "Submission deadline passed after {} attempts. Not submitting.", i);
+                            break;
+                        }
 
                         info!(target: "server_log",
                             "Submitting attempt {} with ✨ diff {} ✨ of {} qualified contributions at {}.",
@@ -152,8 +207,21 @@ pub async fn pool_submission_processor<'a>(
                             BASE64_STANDARD.encode(old_proof.challenge)
                         );
 
-                        info!(target: "server_log", "Getting current/latest on-chain config and proof.");
-                        let ore_config = if let Ok((loaded_config, loaded_proof)) =
+                        // MI: the first attempt goes with the prefetched values, retries refetch
+                        // them as the failure may come from stale ones
+                        let attempt_started = Instant::now();
+                        let stage_started = Instant::now();
+                        let prefetched_config = if i == 0 { app_prefetch.config() } else { None };
+                        let ore_config = if let Some(loaded_config) = prefetched_config {
+                            // MI: the proof source keeps the proof up to date meanwhile
+                            let latest_proof = *app_proof.lock().await;
+                            if !best_solution.is_valid(&latest_proof.challenge) {
+                                error!(target: "server_log", "❌ SOLUTION IS NOT VALID ANYMORE!");
+                                break;
+                            }
+
+                            Some(loaded_config)
+                        } else if let Ok((loaded_config, loaded_proof)) =
                             get_config_and_proof(&rpc_client, signer.pubkey()).await
                         {
                             info!(target: "server_log",
@@ -164,9 +232,7 @@ pub async fn pool_submission_processor<'a>(
                             if !best_solution.is_valid(&loaded_proof.challenge) {
                                 error!(target: "server_log", "❌ SOLUTION IS NOT VALID ANYMORE!");
                                 info!(target: "server_log", "Updating to latest proof on-chain.");
-                                let mut lock = app_proof.lock().await;
-                                *lock = loaded_proof;
-                                drop(lock);
+                                proof_source.apply(loaded_proof, ProofOrigin::Rpc).await;
                                 break;
                             }
 
@@ -175,24 +241,27 @@ pub async fn pool_submission_processor<'a>(
                             None
                         };
 
-                        let _now = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .expect("Time went backwards")
-                            .as_secs();
+                        METRICS
+                            .observe_submission_stage("config_and_proof", stage_started.elapsed());
 
-                        let clock = get_clock(&rpc_client).await;
-                        let current_timestamp = clock.unix_timestamp;
+                        let stage_started = Instant::now();
+                        let current_timestamp = match app_prefetch.chain_timestamp() {
+                            Some(timestamp) => timestamp,
+                            None => get_clock(&rpc_client).await.unix_timestamp,
+                        };
+                        METRICS.observe_submission_stage("clock", stage_started.elapsed());
                         let mut ixs = vec![];
                         let _ = app_all_clients_sender.send(MessageInternalAllClients {
                             text: String::from("Server is submitting mining transaction..."),
+                            pool: Some(wallet_pubkey),
                         });
 
-                        let mut cu_limit = 480_000;
+                        let mut cu_limit = MINE_TX_CU_LIMIT;
                         let should_add_reset_ix = if let Some(config) = ore_config {
                             let time_to_reset =
                                 (config.last_reset_at + EPOCH_DURATION) - current_timestamp as i64;
                             if time_to_reset <= 5 {
-                                cu_limit = 500_000;
+                                cu_limit = MINE_RESET_TX_CU_LIMIT;
                                 info!(target: "server_log", "Including reset tx.");
                                 true
                             } else {
@@ -202,20 +271,19 @@ pub async fn pool_submission_processor<'a>(
                             false
                         };
 
-                        let cu_limit_ix =
-                            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
-                        ixs.push(cu_limit_ix);
-
+                        let stage_started = Instant::now();
                         let mut fee_type: &str = "static";
                         let fee: u64 = if *app_dynamic_fee {
                             fee_type = "estimate";
-                            match pfee::dynamic_fee(
-                                &rpc_client,
-                                (*app_dynamic_fee_url).clone(),
-                                *app_priority_fee_cap,
-                            )
-                            .await
-                            {
+                            let prefetched_fee = if i == 0 { app_prefetch.fee() } else { None };
+                            let estimate = match prefetched_fee {
+                                Some(fee) => Ok(fee),
+                                None => {
+                                    pfee::dynamic_fee(&*app_fee_estimator, *app_priority_fee_cap)
+                                        .await
+                                },
+                            };
+                            match estimate {
                                 Ok(fee) => {
                                     let mut prio_fee = fee;
                                     // MI: calc uplimit of priority fee for precious diff
@@ -292,9 +360,9 @@ pub async fn pool_submission_processor<'a>(
                             prio_fee
                         };
 
-                        let prio_fee_ix = ComputeBudgetInstruction::set_compute_unit_price(fee);
-                        ixs.push(prio_fee_ix);
+                        METRICS.observe_submission_stage("fee", stage_started.elapsed());
 
+                        let stage_started = Instant::now();
                         let noop_ix = get_auth_ix(signer.pubkey());
                         ixs.push(noop_ix);
 
@@ -306,28 +374,350 @@ pub async fn pool_submission_processor<'a>(
                         let ix_mine = get_mine_ix(signer.pubkey(), best_solution, bus);
                         ixs.push(ix_mine);
 
+                        if let Some(ref cu_estimator) = app_cu_estimator {
+                            cu_limit = cu_estimator
+                                .cu_limit(&rpc_client, &ixs, &fee_payer.pubkey(), cu_limit)
+                                .await;
+                        }
+
+                        // MI: keep fees within a share of what the solution is worth and within
+                        // the daily budget
+                        let num_signatures =
+                            if fee_payer.pubkey() == signer.pubkey() { 1 } else { 2 };
+                        let jito_tip = app_jito_client.as_ref().map_or(0, |jito_client| {
+                            jito_client.config().tip_for(
+                                difficulty,
+                                *app_extra_fee_difficulty,
+                                *app_extra_fee_percent,
+                            )
+                        });
+                        let expected_reward = ore_config.map(|config| {
+                            profitability::expected_reward(&config, difficulty, old_proof.balance)
+                        });
+                        let limits = match app_profitability.limit_fees(
+                            expected_reward,
+                            cu_limit,
+                            num_signatures * BASE_FEE_LAMPORTS,
+                            jito_tip,
+                        ) {
+                            Ok(limits) => limits,
+                            Err(err) => {
+                                warn!(target: "server_log", "{}. Not submitting.", err);
+                                not_submitted = true;
+                                break;
+                            },
+                        };
+                        if limits.tip < jito_tip {
+                            info!(target: "server_log", "Jito tip {} lowered to {} by expected reward and fee budget", jito_tip, limits.tip);
+                        }
+                        let jito_tip = limits.tip;
+                        let fee = match limits.max_priority_fee {
+                            Some(max_fee) if fee > max_fee => {
+                                info!(target: "server_log", "Priority fee {} capped to {} by expected reward and fee budget", fee, max_fee);
+                                max_fee
+                            },
+                            _ => fee,
+                        };
+                        let fixed_lamports = num_signatures * BASE_FEE_LAMPORTS + jito_tip;
+                        let fee_lamports =
+                            fixed_lamports + fee.saturating_mul(cu_limit as u64) / 1_000_000;
+
+                        let prio_fee_ix = ComputeBudgetInstruction::set_compute_unit_price(fee);
+                        ixs.insert(0, prio_fee_ix);
+                        let cu_limit_ix =
+                            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit);
+                        ixs.insert(0, cu_limit_ix);
+                        if let Some(ref mine_nonce) = app_mine_nonce {
+                            ixs.insert(0, mine_nonce.advance_ix());
+                        }
+                        METRICS.observe_submission_stage("build", stage_started.elapsed());
+
                         // so far all ixs are constructed, next submit-and-confirm
-                        if *send_tpu_mine_tx {
+                        publish(
+                            &event_sender,
+                            PoolEvent::SubmissionAttempt {
+                                attempt: i + 1,
+                                difficulty,
+                                num_contributions,
+                                priority_fee: fee,
+                                fee_type: fee_type.to_string(),
+                            },
+                        );
+                        let mut attempt = InsertSubmissionAttempt {
+                            pool_id: mine_config.pool_id,
+                            challenge_id: None,
+                            attempt: (i + 1) as i32,
+                            method: String::new(),
+                            difficulty: difficulty as i32,
+                            priority_fee: fee as i64,
+                            cu_limit: cu_limit as i32,
+                            tip: 0,
+                            blockhash: None,
+                            signature: None,
+                            sent_at_ms: now_ms(),
+                            confirmed_at_ms: None,
+                            slot: None,
+                            outcome: String::new(),
+                            error_code: None,
+                            error: None,
+                        };
+                        if let Some(ref jito_client) = app_jito_client {
+                            let tip = jito_tip;
+                            attempt.method = "bundle".to_string();
+                            attempt.tip = tip as i64;
+                            let blockhash = if let Some(ref mine_nonce) = app_mine_nonce {
+                                mine_nonce.blockhash(&rpc_client).await.ok()
+                            } else if i == 0 {
+                                app_prefetch.blockhash()
+                            } else {
+                                None
+                            };
+                            let started = Instant::now();
+                            let res = match jito_client
+                                .bundle_transaction(
+                                    &rpc_client,
+                                    &ixs,
+                                    &fee_payer,
+                                    &signer,
+                                    tip,
+                                    blockhash,
+                                )
+                                .await
+                            {
+                                Ok(tx) => {
+                                    attempt.blockhash =
+                                        Some(tx.message.recent_blockhash.to_string());
+                                    attempt.signature = Some(tx.signatures[0].to_string());
+                                    attempt.sent_at_ms = now_ms();
+                                    if let Some(ref dry_run) = app_dry_run {
+                                        simulate_mine_tx(
+                                            dry_run,
+                                            &rpc_client,
+                                            &database,
+                                            &event_sender,
+                                            MineTxAttempt {
+                                                pool: pool_authority,
+                                                challenge: old_proof.challenge,
+                                                attempt: &attempt,
+                                                tx: &tx,
+                                            },
+                                        )
+                                        .await;
+                                        simulated = true;
+                                        break;
+                                    }
+                                    info!(target: "server_log",
+                                        "Sending jito bundle... with {} priority fee {} and tip {} lamports",
+                                        fee_type, fee, tip
+                                    );
+                                    METRICS.submission_attempt();
+                                    METRICS.observe_submission_stage(
+                                        "to_send",
+                                        attempt_started.elapsed(),
+                                    );
+                                    jito_client
+                                        .send_and_confirm(tx, tip, submit_deadline.into())
+                                        .await
+                                },
+                                Err(e) => Err(e),
+                            };
+                            METRICS.observe_rpc_call("jito_send_and_confirm", started.elapsed());
+                            if let Some(ref mine_nonce) = app_mine_nonce {
+                                mine_nonce.invalidate();
+                            }
+                            match res {
+                                Ok(sig) => {
+                                    success = true;
+                                    landed_signature = Some(sig.to_string());
+                                    landed_fee = fee;
+                                    METRICS.submission_success(pool_authority, fee, cu_limit);
+                                    app_profitability
+                                        .record_spend(old_proof.challenge, fee_lamports);
+                                    record_submission_attempt(
+                                        &database,
+                                        &rpc_client,
+                                        &attempt,
+                                        old_proof.challenge,
+                                        AttemptOutcome::Landed(Some(sig.to_string())),
+                                    );
+                                    publish(
+                                        &event_sender,
+                                        PoolEvent::SubmissionResult {
+                                            attempt: i + 1,
+                                            success: true,
+                                            signature: Some(sig.to_string()),
+                                            error: None,
+                                        },
+                                    );
+                                    info!(target: "server_log", "✅ Success!!");
+                                    info!(target: "server_log", "Sig: {}", sig);
+
+                                    // MI: the tip paid as its price per compute unit on top
+                                    record_mine_transaction(
+                                        &database,
+                                        &mine_config,
+                                        "mine",
+                                        sig.to_string(),
+                                        fee.saturating_add(
+                                            tip.saturating_mul(1_000_000) / cu_limit.max(1) as u64,
+                                        ),
+                                    );
+                                },
+                                Err(e) => {
+                                    METRICS.submission_failure(pool_authority, "jito_error");
+                                    record_unconfirmed_attempt(
+                                        &database,
+                                        &rpc_client,
+                                        &app_profitability,
+                                        &attempt,
+                                        old_proof.challenge,
+                                        AttemptOutcome::Failed("jito_error", e.clone()),
+                                        fee_lamports,
+                                    );
+                                    publish(
+                                        &event_sender,
+                                        PoolEvent::SubmissionResult {
+                                            attempt: i + 1,
+                                            success: false,
+                                            signature: attempt.signature.clone(),
+                                            error: Some(e.clone()),
+                                        },
+                                    );
+                                    error!(target: "server_log", "Error occurred within jito send_and_confirm: {}", e)
+                                },
+                            }
+                        } else if *send_tpu_mine_tx && !*app_race_mine_tx {
                             info!(target: "server_log", "Send tpu mine tx flag is on.");
-                            let config = SendAndConfirmConfig {
-                                resign_txs_count: Some(5),
-                                with_spinner: true,
+                            attempt.method = "tpu".to_string();
+                            let started = Instant::now();
+                            let prefetched_blockhash =
+                                if i == 0 { app_prefetch.blockhash() } else { None };
+                            let latest_blockhash = match prefetched_blockhash {
+                                Some(hash) => Ok(hash),
+                                None => rpc_client
+                                    .get_latest_blockhash_with_commitment(rpc_client.commitment())
+                                    .await
+                                    .map(|(hash, _slot)| hash),
                             };
-                            info!(target: "server_log",
-                                "Sending tpu and confirm... with {} priority fee {}",
-                                fee_type, fee
-                            );
-                            match tpu::send_and_confirm(&rpc_client, &ixs, &*signer, config).await {
-                                Ok(_) => {
+                            let res = match latest_blockhash {
+                                Ok(hash) => {
+                                    let mut tx = Transaction::new_with_payer(
+                                        &ixs,
+                                        Some(&fee_payer.pubkey()),
+                                    );
+                                    tx.sign(&[&fee_payer, &signer], hash);
+                                    attempt.blockhash = Some(hash.to_string());
+                                    attempt.signature = Some(tx.signatures[0].to_string());
+                                    attempt.sent_at_ms = now_ms();
+                                    if let Some(ref dry_run) = app_dry_run {
+                                        simulate_mine_tx(
+                                            dry_run,
+                                            &rpc_client,
+                                            &database,
+                                            &event_sender,
+                                            MineTxAttempt {
+                                                pool: pool_authority,
+                                                challenge: old_proof.challenge,
+                                                attempt: &attempt,
+                                                tx: &tx,
+                                            },
+                                        )
+                                        .await;
+                                        simulated = true;
+                                        break;
+                                    }
+                                    info!(target: "server_log",
+                                        "Sending tpu and confirm... with {} priority fee {}",
+                                        fee_type, fee
+                                    );
+                                    METRICS.submission_attempt();
+                                    METRICS.observe_submission_stage(
+                                        "to_send",
+                                        attempt_started.elapsed(),
+                                    );
+                                    tpu::send_and_confirm(&rpc_client, &tx).await
+                                },
+                                Err(e) => Err(e),
+                            };
+                            METRICS.observe_rpc_call("tpu_send_and_confirm", started.elapsed());
+                            match res {
+                                Ok(sig) => {
                                     success = true;
+                                    landed_signature = Some(sig.to_string());
+                                    landed_fee = fee;
+                                    METRICS.submission_success(pool_authority, fee, cu_limit);
+                                    app_profitability
+                                        .record_spend(old_proof.challenge, fee_lamports);
+                                    record_submission_attempt(
+                                        &database,
+                                        &rpc_client,
+                                        &attempt,
+                                        old_proof.challenge,
+                                        AttemptOutcome::Landed(Some(sig.to_string())),
+                                    );
+                                    publish(
+                                        &event_sender,
+                                        PoolEvent::SubmissionResult {
+                                            attempt: i + 1,
+                                            success: true,
+                                            signature: Some(sig.to_string()),
+                                            error: None,
+                                        },
+                                    );
                                     info!(target: "server_log", "✅ Success!!");
+                                    info!(target: "server_log", "Sig: {}", sig);
+
+                                    record_mine_transaction(
+                                        &database,
+                                        &mine_config,
+                                        "mine",
+                                        sig.to_string(),
+                                        fee,
+                                    );
                                 },
                                 Err(e) => {
+                                    // MI: landed with an error, the fees are paid anyway
+                                    if matches!(e.kind, ClientErrorKind::TransactionError(_)) {
+                                        METRICS.submission_failure(pool_authority, "landed_error");
+                                        app_profitability
+                                            .record_spend(old_proof.challenge, fee_lamports);
+                                        record_submission_attempt(
+                                            &database,
+                                            &rpc_client,
+                                            &attempt,
+                                            old_proof.challenge,
+                                            AttemptOutcome::Failed("landed_error", e.to_string()),
+                                        );
+                                    } else {
+                                        METRICS.submission_failure(pool_authority, "tpu_error");
+                                        record_unconfirmed_attempt(
+                                            &database,
+                                            &rpc_client,
+                                            &app_profitability,
+                                            &attempt,
+                                            old_proof.challenge,
+                                            AttemptOutcome::Failed("tpu_error", e.to_string()),
+                                            fee_lamports,
+                                        );
+                                    }
+                                    publish(
+                                        &event_sender,
+                                        PoolEvent::SubmissionResult {
+                                            attempt: i + 1,
+                                            success: false,
+                                            signature: attempt.signature.clone(),
+                                            error: Some(e.to_string()),
+                                        },
+                                    );
                                     error!(target: "server_log", "Error occurred within tpu::send_and_confirm: {}", e)
                                 },
                             }
                         } else {
-                            info!(target: "server_log", "Send tpu mine tx flag is off. Use RPC call instead.");
+                            if *app_race_mine_tx {
+                                info!(target: "server_log", "Race mine tx flag is on. Send to all rpc endpoints.");
+                            } else {
+                                info!(target: "server_log", "Send tpu mine tx flag is off. Use RPC call instead.");
+                            }
                             // vanilla rpc approach
                             let send_cfg = RpcSendTransactionConfig {
                                 skip_preflight: true,
@@ -337,67 +727,148 @@ pub async fn pool_submission_processor<'a>(
                                 min_context_slot: None,
                             };
 
-                            if let Ok((hash, _slot)) = rpc_client
-                                .get_latest_blockhash_with_commitment(rpc_client.commitment())
-                                .await
-                            {
+                            let stage_started = Instant::now();
+                            let prefetched_blockhash =
+                                if i == 0 { app_prefetch.blockhash() } else { None };
+                            // MI: with a durable nonce the signed tx stays valid across retries
+                            // and failovers, and only one tx per nonce value can land
+                            let latest_blockhash = if let Some(ref mine_nonce) = app_mine_nonce {
+                                mine_nonce.blockhash(&rpc_client).await.ok()
+                            } else if let Some(hash) = prefetched_blockhash {
+                                Some(hash)
+                            } else {
+                                let started = Instant::now();
+                                let res = rpc_client
+                                    .get_latest_blockhash_with_commitment(rpc_client.commitment())
+                                    .await;
+                                METRICS.observe_rpc_call("get_latest_blockhash", started.elapsed());
+                                res.ok().map(|(hash, _slot)| hash)
+                            };
+                            METRICS.observe_submission_stage("blockhash", stage_started.elapsed());
+                            if let Some(hash) = latest_blockhash {
+                                let stage_started = Instant::now();
                                 let mut tx =
-                                    Transaction::new_with_payer(&ixs, Some(&signer.pubkey()));
-
-                                tx.sign(&[&signer], hash);
+                                    Transaction::new_with_payer(&ixs, Some(&fee_payer.pubkey()));
+
+                                tx.sign(&[&fee_payer, &signer], hash);
+                                METRICS.observe_submission_stage("sign", stage_started.elapsed());
+                                attempt.method =
+                                    if *app_race_mine_tx { "race" } else { "rpc" }.to_string();
+                                attempt.blockhash = Some(hash.to_string());
+                                attempt.signature = Some(tx.signatures[0].to_string());
+                                attempt.sent_at_ms = now_ms();
+                                if let Some(ref dry_run) = app_dry_run {
+                                    simulate_mine_tx(
+                                        dry_run,
+                                        &rpc_client,
+                                        &database,
+                                        &event_sender,
+                                        MineTxAttempt {
+                                            pool: pool_authority,
+                                            challenge: old_proof.challenge,
+                                            attempt: &attempt,
+                                            tx: &tx,
+                                        },
+                                    )
+                                    .await;
+                                    simulated = true;
+                                    break;
+                                }
                                 info!(target: "server_log",
                                     "Sending rpc signed tx... with {} priority fee {}",
                                     fee_type, fee
                                 );
                                 info!(target: "server_log", "attempt: {}", i + 1);
-                                match rpc_client
-                                    .send_and_confirm_transaction_with_spinner_and_config(
-                                        &tx,
-                                        rpc_client.commitment(),
-                                        send_cfg,
-                                    )
-                                    .await
-                                {
+                                METRICS.submission_attempt();
+                                METRICS
+                                    .observe_submission_stage("to_send", attempt_started.elapsed());
+                                debug!(target: "server_log", "Mine tx ready to send {}ms after attempt start", attempt_started.elapsed().as_millis());
+                                let started = Instant::now();
+                                let res = if *app_race_mine_tx {
+                                    let res = app_rpc_pool
+                                        .race_send_and_confirm(
+                                            &rpc_client,
+                                            &tx,
+                                            send_cfg,
+                                            *send_tpu_mine_tx,
+                                        )
+                                        .await;
+                                    METRICS.observe_rpc_call(
+                                        "race_send_and_confirm",
+                                        started.elapsed(),
+                                    );
+                                    res
+                                } else {
+                                    let res = rpc_client
+                                        .send_and_confirm_transaction_with_spinner_and_config(
+                                            &tx,
+                                            rpc_client.commitment(),
+                                            send_cfg,
+                                        )
+                                        .await;
+                                    METRICS.observe_rpc_call(
+                                        "send_and_confirm_transaction",
+                                        started.elapsed(),
+                                    );
+                                    res
+                                };
+                                if let Some(ref mine_nonce) = app_mine_nonce {
+                                    mine_nonce.invalidate();
+                                }
+                                match res {
                                     Ok(sig) => {
                                         // success
                                         success = true;
+                                        landed_signature = Some(sig.to_string());
+                                        landed_fee = fee;
+                                        METRICS.submission_success(pool_authority, fee, cu_limit);
+                                        app_profitability
+                                            .record_spend(old_proof.challenge, fee_lamports);
+                                        record_submission_attempt(
+                                            &database,
+                                            &rpc_client,
+                                            &attempt,
+                                            old_proof.challenge,
+                                            AttemptOutcome::Landed(Some(sig.to_string())),
+                                        );
+                                        publish(
+                                            &event_sender,
+                                            PoolEvent::SubmissionResult {
+                                                attempt: i + 1,
+                                                success: true,
+                                                signature: Some(sig.to_string()),
+                                                error: None,
+                                            },
+                                        );
                                         info!(target: "server_log", "✅ Success!!");
                                         info!(target: "server_log", "Sig: {}", sig);
 
-                                        let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
-                                            let key = "POWERED_BY_DBMS";
-                                            match std::env::var(key) {
-                                                Ok(val) => PoweredByDbms::from_str(&val).expect(
-                                                    "POWERED_BY_DBMS must be set correctly.",
-                                                ),
-                                                Err(_) => PoweredByDbms::Unavailable,
-                                            }
-                                        });
-
-                                        if powered_by_dbms == &PoweredByDbms::Postgres
-                                            || powered_by_dbms == &PoweredByDbms::Sqlite
-                                        {
-                                            // spawn task #1: Txn writer
-                                            let itxn = InsertTransaction {
-                                                transaction_type: "mine".to_string(),
-                                                signature: sig.to_string(),
-                                                priority_fee: fee as i32,
-                                                pool_id: mine_config.pool_id,
-                                            };
-                                            let app_db = database.clone();
-                                            tokio::spawn(async move {
-                                                while let Err(_) =
-                                                    app_db.add_new_transaction(itxn.clone()).await
-                                                {
-                                                    error!(target: "server_log", "Failed to add tx record to db! Retrying...");
-                                                    tokio::time::sleep(Duration::from_millis(1000))
-                                                        .await;
-                                                }
-                                            });
-                                        }
+                                        // spawn task #1: Txn writer
+                                        record_mine_transaction(
+                                            &database,
+                                            &mine_config,
+                                            "mine",
+                                            sig.to_string(),
+                                            fee,
+                                        );
                                     },
 
                                     Err(err) => {
+                                        // MI: landed with an error, the fees are paid anyway
+                                        if matches!(err.kind, ClientErrorKind::TransactionError(_))
+                                        {
+                                            app_profitability
+                                                .record_spend(old_proof.challenge, fee_lamports);
+                                        }
+                                        publish(
+                                            &event_sender,
+                                            PoolEvent::SubmissionResult {
+                                                attempt: i + 1,
+                                                success: false,
+                                                signature: None,
+                                                error: Some(err.to_string()),
+                                            },
+                                        );
                                         match err.kind {
                                                     ClientErrorKind::TransactionError(solana_sdk::transaction::TransactionError::InstructionError(_, err)) => {
                                                         match err {
@@ -405,16 +876,40 @@ pub async fn pool_submission_processor<'a>(
                                                             solana_program::instruction::InstructionError::Custom(err_code) => {
                                                                 match err_code {
                                                                     e if e == OreError::NeedsReset as u32 => {
+                                                                        METRICS.submission_failure(pool_authority, "needs_reset");
+                                                                        record_submission_attempt(
+                                                                            &database,
+                                                                            &rpc_client,
+                                                                            &attempt,
+                                                                            old_proof.challenge,
+                                                                            AttemptOutcome::Failed("needs_reset", err.to_string()),
+                                                                        );
                                                                         error!(target: "server_log", "Ore: The epoch has ended and needs reset. Retrying...");
                                                                         continue;
                                                                     }
                                                                     e if e == OreError::HashInvalid as u32 => {
+                                                                        METRICS.submission_failure(pool_authority, "hash_invalid");
+                                                                        record_submission_attempt(
+                                                                            &database,
+                                                                            &rpc_client,
+                                                                            &attempt,
+                                                                            old_proof.challenge,
+                                                                            AttemptOutcome::Failed("hash_invalid", err.to_string()),
+                                                                        );
                                                                         error!(target: "server_log", "❌ Ore: The provided hash is invalid. See you next solution.");
 
                                                                         // break for (0..SUBMIT_LIMIT), re-enter outer loop to restart
                                                                         break;
                                                                     }
                                                                     _ => {
+                                                                        METRICS.submission_failure(pool_authority, "ore_error");
+                                                                        record_submission_attempt(
+                                                                            &database,
+                                                                            &rpc_client,
+                                                                            &attempt,
+                                                                            old_proof.challenge,
+                                                                            AttemptOutcome::Failed("ore_error", err.to_string()),
+                                                                        );
                                                                         error!(target: "server_log", "{}", &err.to_string());
                                                                         continue;
                                                                     }
@@ -423,6 +918,14 @@ pub async fn pool_submission_processor<'a>(
 
                                                             // Non custom instruction error, return
                                                             _ => {
+                                                                METRICS.submission_failure(pool_authority, "instruction_error");
+                                                                record_submission_attempt(
+                                                                    &database,
+                                                                    &rpc_client,
+                                                                    &attempt,
+                                                                    old_proof.challenge,
+                                                                    AttemptOutcome::Failed("instruction_error", err.to_string()),
+                                                                );
                                                                 error!(target: "server_log", "{}", &err.to_string());
                                                             }
                                                         }
@@ -430,6 +933,16 @@ pub async fn pool_submission_processor<'a>(
 
                                                     // MI: other error like what?
                                                     _ => {
+                                                        METRICS.submission_failure(pool_authority, "rpc_error");
+                                                        record_unconfirmed_attempt(
+                                                            &database,
+                                                            &rpc_client,
+                                                            &app_profitability,
+                                                            &attempt,
+                                                            old_proof.challenge,
+                                                            AttemptOutcome::Failed("rpc_error", err.to_string()),
+                                                            fee_lamports,
+                                                        );
                                                         error!(target: "server_log", "{}", &err.to_string());
                                                     }
                                                 }
@@ -438,6 +951,28 @@ pub async fn pool_submission_processor<'a>(
                                     },
                                 }
                             } else {
+                                METRICS.submission_failure(pool_authority, "blockhash_unavailable");
+                                attempt.method =
+                                    if *app_race_mine_tx { "race" } else { "rpc" }.to_string();
+                                record_submission_attempt(
+                                    &database,
+                                    &rpc_client,
+                                    &attempt,
+                                    old_proof.challenge,
+                                    AttemptOutcome::Failed(
+                                        "blockhash_unavailable",
+                                        "Failed to get latest blockhash".to_string(),
+                                    ),
+                                );
+                                publish(
+                                    &event_sender,
+                                    PoolEvent::SubmissionResult {
+                                        attempt: i + 1,
+                                        success: false,
+                                        signature: None,
+                                        error: Some("Failed to get latest blockhash".to_string()),
+                                    },
+                                );
                                 error!(target: "server_log", "Failed to get latest blockhash. retrying...");
                                 tokio::time::sleep(Duration::from_millis(1_000)).await;
                             }
@@ -451,204 +986,132 @@ pub async fn pool_submission_processor<'a>(
                             let app_app_nonce = app_nonce.clone();
                             let app_app_database = database.clone();
                             let app_app_config = mine_config.clone();
-                            let app_app_rpc_client = rpc_client.clone();
-                            let app_app_slack_message_sender = slack_message_sender.clone();
-                            let app_app_discord_message_sender = discord_message_sender.clone();
-                            let app_app_slack_difficulty = slack_difficulty.clone();
-                            let app_app_messaging_diff = messaging_diff.clone();
-                            let app_app_proof = app_proof.clone();
-                            let app_app_wallet = app_wallet.clone();
+                            let app_app_notification_sender = notification_sender.clone();
+                            let app_app_proof_source = proof_source.clone();
                             let app_app_epoch_hashes = app_epoch_hashes.clone();
+                            let app_app_event_sender = event_sender.clone();
+                            let app_app_profitability = app_profitability.clone();
+                            let app_app_paused = app_paused.clone();
                             tokio::spawn(async move {
                                 let mine_success_sender = app_app_mine_success_sender;
                                 let app_nonce = app_app_nonce;
                                 let database = app_app_database;
                                 let mine_config = app_app_config;
-                                let rpc_client = app_app_rpc_client;
-                                let slack_message_sender = app_app_slack_message_sender.clone();
-                                let discord_message_sender = app_app_discord_message_sender.clone();
-                                let slack_difficulty = app_app_slack_difficulty.clone();
-                                let messaging_diff = app_app_messaging_diff.clone();
-                                let app_proof = app_app_proof;
-                                let app_wallet = app_app_wallet;
+                                let notification_sender = app_app_notification_sender;
+                                let proof_source = app_app_proof_source;
                                 let app_epoch_hashes = app_app_epoch_hashes;
+                                let app_paused = app_app_paused;
+
+                                // update proof, resolved by whichever ws subscription or rpc poll
+                                // of the proof source sees the new challenge first
+                                info!(target: "server_log", "Waiting for proof challenge update");
+                                let Some(latest_proof) = proof_source
+                                    .wait_for_new_challenge(
+                                        old_proof.challenge,
+                                        PROOF_UPDATE_TIMEOUT,
+                                    )
+                                    .await
+                                else {
+                                    warn!(target: "server_log", "No challenge update detected after {}s. No more waiting, just keep going...", PROOF_UPDATE_TIMEOUT.as_secs());
+                                    return;
+                                };
+                                info!(target: "server_log", "Proof challenge updated!");
+
+                                publish(
+                                    &app_app_event_sender,
+                                    PoolEvent::NewChallenge {
+                                        challenge: BASE64_STANDARD.encode(latest_proof.challenge),
+                                    },
+                                );
 
-                                // update proof
-                                // limit number of checking no more than CHECK_LIMIT
-                                let mut num_checking = 0;
-                                loop {
-                                    info!(target: "server_log", "Waiting & Checking for proof challenge update");
-                                    // Wait 500ms, then check for proof update, which may be caused by WS subscription.
-                                    tokio::time::sleep(Duration::from_millis(500)).await;
-                                    let lock = app_proof.lock().await;
-                                    let latest_proof = lock.clone();
-                                    drop(lock);
-
-                                    // Once proof(hash) updated, rewards should be updated. Proof change are detected via 2 ways:
-                                    // a) active rpc call
-                                    // b) passive proof subscription via websocket
-
-                                    // proof basic concepts:
-                                    // proof is the application level contents mapping of (mini) pool proof account on-chain
-                                    // old_proof is the proof snapshot at the beginning of each loop iteration
-                                    // app_proof is proof mutex lock
-                                    // latest_proof(here) is a proof copy and need sync with proof ASAP
-                                    // if proof on-chain changed/updated, app_proof and latest_proof should be updated with the new proof
-                                    // proof subscription/tracking will automatically update/sync application level app_proof with on-chain proof
-                                    // proof change detected by rpc call needs manually update/sync to app_proof
-
-                                    if old_proof.challenge.eq(&latest_proof.challenge) {
-                                        // old proof is still latest proof
-                                        info!(target: "server_log", "Proof challenge not updated yet..");
-                                        num_checking += 1;
-                                        if num_checking >= CHECK_LIMIT {
-                                            warn!(target: "server_log", "No challenge update detected after {CHECK_LIMIT} checkpoints. No more waiting, just keep going...");
-                                            break;
-                                        }
-                                        // a) active rpc call to fetch on-chain proof
-                                        if let Ok(p) = get_mini_pool_proof(
-                                            &rpc_client,
-                                            app_wallet.miner_wallet.pubkey(),
-                                        )
-                                        .await
-                                        {
-                                            info!(target: "server_log",
-                                                "OLD PROOF CHALLENGE: {}",
-                                                BASE64_STANDARD.encode(old_proof.challenge)
-                                            );
-                                            info!(target: "server_log",
-                                                "RPC PROOF CHALLENGE: {}",
-                                                BASE64_STANDARD.encode(p.challenge)
-                                            );
+                                // Process rewards in 3 steps
+                                info!(target: "server_log", "Checking rewards earned.");
+                                let mut submission_challenge_id = i64::MAX;
 
-                                            if old_proof.challenge.ne(&p.challenge) {
-                                                // proof on-chain has changed
-                                                info!(target: "server_log", "Found new proof from rpc call other than websocket.");
+                                // Rewards processing step I: calc. balances, commissions and write logs
+                                let balance = (latest_proof.balance as f64)
+                                    / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
+                                info!(target: "server_log", "New balance: {}", balance);
 
-                                                // explicitly refresh/sync app_proof with newest proof on-chain
-                                                let mut lock = app_proof.lock().await;
-                                                *lock = p;
-                                                drop(lock);
-                                            } else {
-                                                continue; // loop
-                                            }
-                                        } else {
-                                            error!(target: "server_log", "Failed to get proof via rpc call.");
-                                            continue;
-                                        }
+                                let multiplier = if let Some(config) = ore_config {
+                                    if config.top_balance > 0 {
+                                        1.0 + (latest_proof.balance as f64
+                                            / config.top_balance as f64)
+                                            .min(1.0f64)
                                     } else {
-                                        // old proof is not latest proof any more. Detected by:
-                                        // b) passive proof subscription via websocket
-                                        info!(target: "server_log", "Proof challenge updated! Detected by ws subscription.");
+                                        1.0f64
+                                    }
+                                } else {
+                                    1.0f64
+                                };
+                                info!(target: "server_log", "Multiplier: {}", multiplier);
+
+                                let pool_rewards =
+                                    (latest_proof.balance - old_proof.balance) as i64;
+                                let commissions = pool_rewards.mul(5).saturating_div(100);
+                                let miners_rewards = pool_rewards - commissions;
+
+                                let dec_pool_rewards =
+                                    (pool_rewards as f64) / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
+                                let dec_miners_rewards =
+                                    (miners_rewards as f64) / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
+                                let dec_commissions =
+                                    (commissions as f64) / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
+
+                                info!(target: "server_log", "Pool Rewards: {} ORE", dec_pool_rewards);
+                                info!(target: "server_log", "Miners Rewards: {} ORE", dec_miners_rewards);
+                                info!(target: "server_log", "Operator Commission: {} ORE", dec_commissions);
+                                app_app_profitability.settle(
+                                    mine_config.pool_id,
+                                    old_proof.challenge,
+                                    difficulty,
+                                    expected_reward,
+                                    pool_rewards.max(0) as u64,
+                                );
 
-                                        // Note: app_proof has been automatically updated via ws subscription. No more action here.
+                                // Rewards processing step II: if powered by dbms, do database crud routines.
+                                let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
+                                    let key = "POWERED_BY_DBMS";
+                                    match std::env::var(key) {
+                                        Ok(val) => PoweredByDbms::from_str(&val)
+                                            .expect("POWERED_BY_DBMS must be set correctly."),
+                                        Err(_) => PoweredByDbms::Unavailable,
                                     }
-                                    // arrrived here, means proof change detected, either by rpc call or ws subscription.
-                                    // refresh latest proof with new change
-                                    let lock = app_proof.lock().await;
-                                    let latest_proof = lock.clone();
-                                    drop(lock);
-
-                                    // Process rewards in 3 steps
-                                    info!(target: "server_log", "Checking rewards earned.");
-                                    let mut submission_challenge_id = i64::MAX;
-
-                                    // Rewards processing step I: calc. balances, commissions and write logs
-                                    let balance = (latest_proof.balance as f64)
-                                        / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
-                                    info!(target: "server_log", "New balance: {}", balance);
-
-                                    let multiplier = if let Some(config) = ore_config {
-                                        if config.top_balance > 0 {
-                                            1.0 + (latest_proof.balance as f64
-                                                / config.top_balance as f64)
-                                                .min(1.0f64)
+                                });
+                                if powered_by_dbms == &PoweredByDbms::Postgres
+                                    || powered_by_dbms == &PoweredByDbms::Sqlite
+                                {
+                                    // 1) Fetch old proof challenge record (its id will be used later)
+                                    //    from db or insert old proof if necessary/missing
+                                    info!(target: "server_log", "Check if old/last challenge for the pool exists in the database");
+                                    let submission_challenge;
+                                    'db_challenge: loop {
+                                        if let Ok(c) = database
+                                            .get_challenge_by_challenge(
+                                                old_proof.challenge.to_vec(),
+                                            )
+                                            .await
+                                        {
+                                            submission_challenge = c;
+                                            submission_challenge_id = submission_challenge.id;
+                                            break;
                                         } else {
-                                            1.0f64
-                                        }
-                                    } else {
-                                        1.0f64
-                                    };
-                                    info!(target: "server_log", "Multiplier: {}", multiplier);
-
-                                    let pool_rewards =
-                                        (latest_proof.balance - old_proof.balance) as i64;
-                                    let commissions = pool_rewards.mul(5).saturating_div(100);
-                                    let miners_rewards = pool_rewards - commissions;
-
-                                    let dec_pool_rewards = (pool_rewards as f64)
-                                        / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
-                                    let dec_miners_rewards = (miners_rewards as f64)
-                                        / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
-                                    let dec_commissions = (commissions as f64)
-                                        / 10f64.powf(ORE_TOKEN_DECIMALS as f64);
-
-                                    info!(target: "server_log", "Pool Rewards: {} ORE", dec_pool_rewards);
-                                    info!(target: "server_log", "Miners Rewards: {} ORE", dec_miners_rewards);
-                                    info!(target: "server_log", "Operator Commission: {} ORE", dec_commissions);
-
-                                    // Rewards processing step II: if powered by dbms, do database crud routines.
-                                    let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
-                                        let key = "POWERED_BY_DBMS";
-                                        match std::env::var(key) {
-                                            Ok(val) => PoweredByDbms::from_str(&val)
-                                                .expect("POWERED_BY_DBMS must be set correctly."),
-                                            Err(_) => PoweredByDbms::Unavailable,
-                                        }
-                                    });
-                                    if powered_by_dbms == &PoweredByDbms::Postgres
-                                        || powered_by_dbms == &PoweredByDbms::Sqlite
-                                    {
-                                        // 1) Fetch old proof challenge record (its id will be used later)
-                                        //    from db or insert old proof if necessary/missing
-                                        info!(target: "server_log", "Check if old/last challenge for the pool exists in the database");
-                                        let submission_challenge;
-                                        'db_challenge: loop {
-                                            if let Ok(c) = database
-                                                .get_challenge_by_challenge(
-                                                    old_proof.challenge.to_vec(),
-                                                )
+                                            warn!(target: "server_log",
+                                                "Failed to get old/last challenge record from db! Inserting..."
+                                            );
+                                            let missing_challenge = models::InsertChallenge {
+                                                pool_id: mine_config.pool_id,
+                                                challenge: old_proof.challenge.to_vec(),
+                                                rewards_earned: None,
+                                            };
+
+                                            while database
+                                                .add_new_challenge(missing_challenge.clone())
                                                 .await
+                                                .is_err()
                                             {
-                                                submission_challenge = c;
-                                                submission_challenge_id = submission_challenge.id;
-                                                break;
-                                            } else {
-                                                warn!(target: "server_log",
-                                                    "Failed to get old/last challenge record from db! Inserting..."
-                                                );
-                                                let missing_challenge = models::InsertChallenge {
-                                                    pool_id: mine_config.pool_id,
-                                                    challenge: old_proof.challenge.to_vec(),
-                                                    rewards_earned: None,
-                                                };
-
-                                                while let Err(_) = database
-                                                    .add_new_challenge(missing_challenge.clone())
-                                                    .await
-                                                {
-                                                    error!(target: "server_log", "Failed to add old/last challenge record to db.");
-                                                    info!(target: "server_log", "Check if the challenge already exists in db.");
-                                                    if let Ok(c) = database
-                                                        .get_challenge_by_challenge(
-                                                            missing_challenge.challenge.clone(),
-                                                        )
-                                                        .await
-                                                    {
-                                                        submission_challenge = c;
-                                                        submission_challenge_id =
-                                                            submission_challenge.id;
-                                                        info!(target: "server_log", "The challenge already exists, continuing");
-                                                        break 'db_challenge;
-                                                    }
-
-                                                    tokio::time::sleep(Duration::from_millis(
-                                                        1_000,
-                                                    ))
-                                                    .await;
-                                                }
-                                                info!(target: "server_log", "Old/last challenge record successfully added to db");
-                                                // obtain new added challenge id
+                                                error!(target: "server_log", "Failed to add old/last challenge record to db.");
+                                                info!(target: "server_log", "Check if the challenge already exists in db.");
                                                 if let Ok(c) = database
                                                     .get_challenge_by_challenge(
                                                         missing_challenge.challenge.clone(),
@@ -658,156 +1121,161 @@ pub async fn pool_submission_processor<'a>(
                                                     submission_challenge = c;
                                                     submission_challenge_id =
                                                         submission_challenge.id;
+                                                    info!(target: "server_log", "The challenge already exists, continuing");
                                                     break 'db_challenge;
                                                 }
-                                            }
-                                        }
-
-                                        // 2) Add new challenge record to db
-                                        info!(target: "server_log", "Adding new challenge record to db");
-                                        let new_challenge = models::InsertChallenge {
-                                            pool_id: mine_config.pool_id,
-                                            challenge: latest_proof.challenge.to_vec(),
-                                            rewards_earned: None,
-                                        };
 
-                                        while let Err(_) =
-                                            database.add_new_challenge(new_challenge.clone()).await
-                                        {
-                                            error!(target: "server_log",
-                                                "Failed to add new challenge record to db."
-                                            );
-                                            info!(target: "server_log", "Check if new challenge already exists in db.");
-                                            if let Ok(_) = database
+                                                tokio::time::sleep(Duration::from_millis(1_000))
+                                                    .await;
+                                            }
+                                            info!(target: "server_log", "Old/last challenge record successfully added to db");
+                                            // obtain new added challenge id
+                                            if let Ok(c) = database
                                                 .get_challenge_by_challenge(
-                                                    new_challenge.challenge.clone(),
+                                                    missing_challenge.challenge.clone(),
                                                 )
                                                 .await
                                             {
-                                                info!(target: "server_log",
-                                                    "Challenge already exists in db, continuing"
-                                                );
-                                                break;
+                                                submission_challenge = c;
+                                                submission_challenge_id = submission_challenge.id;
+                                                break 'db_challenge;
                                             }
-
-                                            tokio::time::sleep(Duration::from_millis(1_000)).await;
-                                        }
-                                        info!(target: "server_log",
-                                            "New challenge record successfully added to db"
-                                        );
-
-                                        // 3) Insert commissions earning to db
-                                        let commissions_earning = vec![models::InsertEarning {
-                                            miner_id: mine_config.commissions_miner_id,
-                                            pool_id: mine_config.pool_id,
-                                            challenge_id: submission_challenge.id,
-                                            amount: commissions,
-                                        }];
-                                        info!(target: "server_log", "Inserting commissions earning");
-                                        while let Err(_) = database
-                                            .add_new_earnings_batch(commissions_earning.clone())
-                                            .await
-                                        {
-                                            error!(target: "server_log", "Failed to add commmissions earning... retrying...");
-                                            tokio::time::sleep(Duration::from_millis(500)).await;
                                         }
-                                        info!(target: "server_log", "Inserted commissions earning");
+                                    }
 
-                                        let new_commission_rewards = vec![models::UpdateReward {
-                                            miner_id: mine_config.commissions_miner_id,
-                                            balance: commissions,
-                                        }];
+                                    // 2) Add new challenge record to db
+                                    info!(target: "server_log", "Adding new challenge record to db");
+                                    let new_challenge = models::InsertChallenge {
+                                        pool_id: mine_config.pool_id,
+                                        challenge: latest_proof.challenge.to_vec(),
+                                        rewards_earned: None,
+                                    };
 
-                                        info!(target: "server_log", "Updating commissions rewards...");
-                                        while let Err(_) = database
-                                            .update_rewards(new_commission_rewards.clone())
+                                    while database
+                                        .add_new_challenge(new_challenge.clone())
+                                        .await
+                                        .is_err()
+                                    {
+                                        error!(target: "server_log",
+                                            "Failed to add new challenge record to db."
+                                        );
+                                        info!(target: "server_log", "Check if new challenge already exists in db.");
+                                        if database
+                                            .get_challenge_by_challenge(
+                                                new_challenge.challenge.clone(),
+                                            )
                                             .await
+                                            .is_ok()
                                         {
-                                            error!(target: "server_log", "Failed to update commission rewards in db. Retrying...");
-                                            tokio::time::sleep(Duration::from_millis(500)).await;
+                                            info!(target: "server_log",
+                                                "Challenge already exists in db, continuing"
+                                            );
+                                            break;
                                         }
-                                        info!(target: "server_log", "Updated commissions rewards");
-                                        tokio::time::sleep(Duration::from_millis(200)).await;
-                                    }
 
-                                    // Rewards processing step III: send internal mine success message for challenge of rewares earned.
-                                    let mut total_hashpower: u64 = 0;
-                                    let num_contributors: u32 = contributions.len() as u32;
-
-                                    for contribution in contributions.iter() {
-                                        total_hashpower += contribution.1.hashpower;
+                                        tokio::time::sleep(Duration::from_millis(1_000)).await;
                                     }
+                                    info!(target: "server_log",
+                                        "New challenge record successfully added to db"
+                                    );
 
-                                    info!(target: "server_log", "Sending internal mine success for challenge: {}", BASE64_STANDARD.encode(old_proof.challenge));
-                                    let _ = mine_success_sender.send(MessageInternalMineSuccess {
-                                        difficulty,
-                                        total_balance: balance,
-                                        rewards: pool_rewards,
-                                        commissions,
-                                        challenge_id: submission_challenge_id,
-                                        challenge: old_proof.challenge,
-                                        best_nonce: u64::from_le_bytes(best_solution.n),
-                                        total_hashpower,
-                                        ore_config,
-                                        multiplier,
-                                        contributions,
-                                    }); // End of 3 steps for rewards processing
-
-                                    // reset nonce and epoch_hashes
-                                    info!(target: "server_log", "reset nonce and epoch hashes");
-
-                                    // reset nonce
+                                    // 3) Insert commissions earning to db
+                                    let commissions_earning = vec![models::InsertEarning {
+                                        miner_id: mine_config.commissions_miner_id,
+                                        pool_id: mine_config.pool_id,
+                                        challenge_id: submission_challenge.id,
+                                        amount: commissions,
+                                    }];
+                                    info!(target: "server_log", "Inserting commissions earning");
+                                    while database
+                                        .add_new_earnings_batch(commissions_earning.clone())
+                                        .await
+                                        .is_err()
                                     {
-                                        let mut nonce = app_nonce.lock().await;
-                                        *nonce = 0;
+                                        error!(target: "server_log", "Failed to add commmissions earning... retrying...");
+                                        tokio::time::sleep(Duration::from_millis(500)).await;
                                     }
-                                    // reset epoch hashes
+                                    info!(target: "server_log", "Inserted commissions earning");
+
+                                    let new_commission_rewards = vec![models::UpdateReward {
+                                        miner_id: mine_config.commissions_miner_id,
+                                        pool_id: mine_config.pool_id,
+                                        balance: commissions,
+                                    }];
+
+                                    info!(target: "server_log", "Updating commissions rewards...");
+                                    while database
+                                        .update_rewards(new_commission_rewards.clone())
+                                        .await
+                                        .is_err()
                                     {
-                                        let mut mut_epoch_hashes = app_epoch_hashes.write().await;
-                                        mut_epoch_hashes.challenge = latest_proof.challenge;
-                                        mut_epoch_hashes.best_hash.solution = None;
-                                        mut_epoch_hashes.best_hash.difficulty = 0;
-                                        mut_epoch_hashes.contributions = HashMap::new();
+                                        error!(target: "server_log", "Failed to update commission rewards in db. Retrying...");
+                                        tokio::time::sleep(Duration::from_millis(500)).await;
                                     }
-                                    // unset mining pause flag to start new mining mission
-                                    info!(target: "server_log", "resume new mining mission");
-                                    PAUSED.store(false, Relaxed);
+                                    info!(target: "server_log", "Updated commissions rewards");
+                                    tokio::time::sleep(Duration::from_millis(200)).await;
+                                }
 
-                                    // Mission completed, send signal to tx sender
-                                    if let Err(_) = mission_completed_sender.send(0) {
-                                        error!(target: "server_log", "The mission completed receiver dropped.");
-                                    }
+                                // Rewards processing step III: send internal mine success message for challenge of rewares earned.
+                                let mut total_hashpower: u64 = 0;
+                                let num_contributors: u32 = contributions.len() as u32;
 
-                                    // last one, notify slack and other messaging channels if necessary
-                                    if difficulty.ge(&*slack_difficulty)
-                                        || difficulty.ge(&*messaging_diff)
-                                    {
-                                        if messaging_flags.contains(MessagingFlags::SLACK) {
-                                            let _ =
-                                                slack_message_sender.send(RewardsMessage::Rewards(
-                                                    difficulty,
-                                                    dec_pool_rewards,
-                                                    balance,
-                                                    num_active_miners as u32,
-                                                    num_contributors,
-                                                ));
-                                        }
+                                for contribution in contributions.iter() {
+                                    total_hashpower += contribution.1.hashpower;
+                                }
 
-                                        if messaging_flags.contains(MessagingFlags::DISCORD) {
-                                            let _ = discord_message_sender.send(
-                                                RewardsMessage::Rewards(
-                                                    difficulty,
-                                                    dec_pool_rewards,
-                                                    balance,
-                                                    num_active_miners as u32,
-                                                    num_contributors,
-                                                ),
-                                            );
-                                        }
-                                    }
+                                info!(target: "server_log", "Sending internal mine success for challenge: {}", BASE64_STANDARD.encode(old_proof.challenge));
+                                let _ = mine_success_sender.send(MessageInternalMineSuccess {
+                                    difficulty,
+                                    total_balance: balance,
+                                    rewards: pool_rewards,
+                                    commissions,
+                                    challenge_id: submission_challenge_id,
+                                    challenge: old_proof.challenge,
+                                    best_nonce: u64::from_le_bytes(best_solution.n),
+                                    total_hashpower,
+                                    ore_config,
+                                    multiplier,
+                                    contributions,
+                                }); // End of 3 steps for rewards processing
+
+                                // reset nonce and epoch_hashes
+                                info!(target: "server_log", "reset nonce and epoch hashes");
+
+                                // reset nonce
+                                {
+                                    let mut nonce = app_nonce.lock().await;
+                                    *nonce = 0;
+                                }
+                                // reset epoch hashes
+                                {
+                                    let mut mut_epoch_hashes = app_epoch_hashes.write().await;
+                                    mut_epoch_hashes.challenge = latest_proof.challenge;
+                                    mut_epoch_hashes.best_hash.solution = None;
+                                    mut_epoch_hashes.best_hash.difficulty = 0;
+                                    mut_epoch_hashes.contributions = HashMap::new();
+                                }
+                                // unset mining pause flag to start new mining mission
+                                info!(target: "server_log", "resume new mining mission");
+                                app_paused.store(false, Relaxed);
 
-                                    break;
+                                // Mission completed, send signal to tx sender
+                                if mission_completed_sender.send(0).is_err() {
+                                    error!(target: "server_log", "The mission completed receiver dropped.");
                                 }
+
+                                // last one, notify messaging channels, each filters by its own min difficulty
+                                let _ = notification_sender.send(Notification::Rewards(
+                                    RewardsMessage {
+                                        difficulty,
+                                        rewards: dec_pool_rewards,
+                                        balance,
+                                        num_miners: num_active_miners as u32,
+                                        num_contributors,
+                                        signature: landed_signature.clone(),
+                                        priority_fee: landed_fee,
+                                    },
+                                ));
                             });
 
                             // MI: play sound here, meanwhile giving time to start above spawned task
@@ -829,20 +1297,29 @@ pub async fn pool_submission_processor<'a>(
                         tokio::time::sleep(Duration::from_millis(1_000)).await;
                     }
                 }
-                if !success {
+                if simulated {
+                    info!(target: "server_log", "[dry run] Mine tx simulated, next epoch runs on the same challenge.");
+                    dry_run_epoch_at = app_prefetch.chain_timestamp().unwrap_or(now_ms() / 1000);
+                    // MI: the proof never changes in dry run, a simulated epoch stands for its
+                    // update so readiness and the stale proof alert don't fire
+                    proof_source.proof_updated();
+                } else if not_submitted {
+                    info!(target: "server_log", "Solution not submitted, fees are not worth it or the fee budget is spent.");
+                } else if !success {
                     error!(target: "server_log", "❌ Failed to land tx... either reached {SUBMIT_LIMIT} attempts or ix error or invalid solution.");
+                }
+                if !success {
                     info!(target: "server_log", "Discarding and refreshing data...");
                     info!(target: "server_log", "refresh proof");
                     if let Ok(refreshed_proof) =
                         get_mini_pool_proof(&rpc_client, wallet_pubkey).await
                     {
-                        let mut app_proof = app_proof.lock().await;
-                        *app_proof = refreshed_proof;
-                        drop(app_proof);
+                        proof_source.apply(refreshed_proof, ProofOrigin::Rpc).await;
                     }
                     info!(target: "server_log", "reset nonce and epoch hashes");
-                    // reset nonce
-                    {
+                    // reset nonce, in dry run the challenge stays the same so the nonce keeps
+                    // moving on for the next epoch not to replay the same work
+                    if !simulated {
                         let mut nonce = app_nonce.lock().await;
                         *nonce = 0;
                     }
@@ -856,7 +1333,7 @@ pub async fn pool_submission_processor<'a>(
 
                     // unset mining pause flag to start new mining mission
                     info!(target: "server_log", "resume new mining mission");
-                    PAUSED.store(false, Relaxed);
+                    app_paused.store(false, Relaxed);
                 }
                 tokio::time::sleep(Duration::from_millis(500)).await;
             } else {
@@ -879,3 +1356,216 @@ pub async fn pool_submission_processor<'a>(
         };
     }
 }
+
+/// Records a landed mine transaction, retrying until the db accepts it.
+fn record_mine_transaction(
+    database: &Arc<Database>,
+    mine_config: &Arc<MineConfig>,
+    transaction_type: &str,
+    signature: String,
+    fee: u64,
+) {
+    if dbms_enabled() {
+        let itxn = InsertTransaction {
+            transaction_type: transaction_type.to_string(),
+            signature,
+            priority_fee: fee as i32,
+            pool_id: mine_config.pool_id,
+        };
+        let app_db = database.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            while app_db.add_new_transaction(itxn.clone()).await.is_err() {
+                error!(target: "server_log", "Failed to add tx record to db! Retrying...");
+                tokio::time::sleep(Duration::from_millis(1000)).await;
+            }
+            METRICS.observe_db_write("add_new_transaction", started.elapsed());
+        });
+    }
+}
+
+/// A mine transaction the send path built, with the attempt it is recorded as.
+struct MineTxAttempt<'a> {
+    pool: Pubkey,
+    challenge: [u8; 32],
+    attempt: &'a InsertSubmissionAttempt,
+    tx: &'a Transaction,
+}
+
+/// Dry run in place of sending, simulates the mine transaction the send path built and records
+/// the outcome as its attempt.
+async fn simulate_mine_tx(
+    dry_run: &DryRun,
+    rpc_client: &Arc<RpcClient>,
+    database: &Arc<Database>,
+    event_sender: &broadcast::Sender<PoolEvent>,
+    mine_tx: MineTxAttempt<'_>,
+) {
+    let MineTxAttempt { pool, challenge, attempt, tx } = mine_tx;
+    let res = dry_run.simulate(rpc_client, tx).await;
+    dry_run.record(
+        "mine",
+        pool,
+        &tx.signatures[0],
+        attempt.priority_fee as u64,
+        Some(attempt.cu_limit as u32),
+        &res,
+    );
+    record_submission_attempt(
+        database,
+        rpc_client,
+        attempt,
+        challenge,
+        AttemptOutcome::Simulated(res.clone().err()),
+    );
+    publish(
+        event_sender,
+        PoolEvent::SubmissionResult {
+            attempt: attempt.attempt as u32,
+            success: res.is_ok(),
+            signature: Some(tx.signatures[0].to_string()),
+            error: res.err(),
+        },
+    );
+}
+
+enum AttemptOutcome {
+    /// Landed, with the signature when the submission path knows it.
+    Landed(Option<String>),
+    /// Failure kind as counted by the submission failure metric, and the error.
+    Failed(&'static str, String),
+    /// Simulated in dry run, with the error when the simulation failed.
+    Simulated(Option<String>),
+}
+
+/// Records a single submission attempt with its outcome. The challenge id and, for landed
+/// attempts, the slot are resolved in the background so the submission loop never waits on
+/// them.
+fn record_submission_attempt(
+    database: &Arc<Database>,
+    rpc_client: &Arc<RpcClient>,
+    attempt: &InsertSubmissionAttempt,
+    challenge: [u8; 32],
+    outcome: AttemptOutcome,
+) {
+    if !dbms_enabled() {
+        return;
+    }
+
+    let mut attempt = attempt.clone();
+    match outcome {
+        AttemptOutcome::Landed(signature) => {
+            attempt.outcome = "landed".to_string();
+            attempt.confirmed_at_ms = Some(now_ms());
+            if signature.is_some() {
+                attempt.signature = signature;
+            }
+        },
+        AttemptOutcome::Failed(error_code, error) => {
+            attempt.outcome = "failed".to_string();
+            attempt.error_code = Some(error_code.to_string());
+            attempt.error = Some(error);
+        },
+        AttemptOutcome::Simulated(error) => {
+            attempt.outcome =
+                if error.is_some() { "simulate_failed" } else { "simulated" }.to_string();
+            attempt.error = error;
+        },
+    }
+
+    let app_db = database.clone();
+    let rpc_client = rpc_client.clone();
+    tokio::spawn(async move {
+        // MI: the challenge row is added once the epoch is done, may not exist yet
+        if let Ok(challenge) = app_db.get_challenge_by_challenge(challenge.to_vec()).await {
+            attempt.challenge_id = Some(challenge.id);
+        }
+        if attempt.outcome == "landed" {
+            if let Some(signature) =
+                attempt.signature.as_ref().and_then(|sig| Signature::from_str(sig).ok())
+            {
+                if let Ok(statuses) = rpc_client.get_signature_statuses(&[signature]).await {
+                    attempt.slot = statuses
+                        .value
+                        .first()
+                        .and_then(|status| status.as_ref())
+                        .map(|status| status.slot as i64);
+                }
+            }
+        }
+
+        let started = Instant::now();
+        let mut retries = 0;
+        while app_db.add_submission_attempt(attempt.clone()).await.is_err() {
+            retries += 1;
+            if retries >= 3 {
+                error!(target: "server_log", "Failed to add submission attempt record to db, dropped.");
+                return;
+            }
+            error!(target: "server_log", "Failed to add submission attempt record to db! Retrying...");
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+        }
+        METRICS.observe_db_write("add_submission_attempt", started.elapsed());
+    });
+}
+
+/// Records a failed attempt whose transaction was sent and may still land after its confirmation
+/// gave up. Its signature is looked up again once its blockhash expired, a late landing pays
+/// its fees and is recorded as landed, or as `landed_error` when the transaction failed.
+fn record_unconfirmed_attempt(
+    database: &Arc<Database>,
+    rpc_client: &Arc<RpcClient>,
+    profitability: &Arc<ProfitabilityGuard>,
+    attempt: &InsertSubmissionAttempt,
+    challenge: [u8; 32],
+    outcome: AttemptOutcome,
+    fee_lamports: u64,
+) {
+    let Some(signature) = attempt.signature.as_ref().and_then(|sig| Signature::from_str(sig).ok())
+    else {
+        record_submission_attempt(database, rpc_client, attempt, challenge, outcome);
+        return;
+    };
+
+    let database = database.clone();
+    let rpc_client = rpc_client.clone();
+    let profitability = profitability.clone();
+    let attempt = attempt.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(LATE_LANDING_DELAY).await;
+        let status = rpc_client
+            .get_signature_statuses_with_history(&[signature])
+            .await
+            .ok()
+            .and_then(|statuses| statuses.value.into_iter().next().flatten());
+        let outcome = match status {
+            Some(status) => {
+                info!(target: "server_log", "Mine tx {} landed after its confirmation gave up", signature);
+                profitability.record_spend(challenge, fee_lamports);
+                match status.err {
+                    None => AttemptOutcome::Landed(None),
+                    Some(err) => AttemptOutcome::Failed("landed_error", err.to_string()),
+                }
+            },
+            None => outcome,
+        };
+        record_submission_attempt(&database, &rpc_client, &attempt, challenge, outcome);
+    });
+}
+
+fn dbms_enabled() -> bool {
+    let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
+        let key = "POWERED_BY_DBMS";
+        match std::env::var(key) {
+            Ok(val) => {
+                PoweredByDbms::from_str(&val).expect("POWERED_BY_DBMS must be set correctly.")
+            },
+            Err(_) => PoweredByDbms::Unavailable,
+        }
+    });
+    powered_by_dbms == &PoweredByDbms::Postgres || powered_by_dbms == &PoweredByDbms::Sqlite
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
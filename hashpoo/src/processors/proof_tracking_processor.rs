@@ -1,95 +1,126 @@
 use {
-    crate::utils,
-    base64::{prelude::BASE64_STANDARD, Engine},
+    crate::{
+        proof_source::{ProofOrigin, ProofSource, ProofSourceConfig},
+        utils,
+    },
     futures::StreamExt,
     ore_api::state::Proof,
     ore_utils::AccountDeserialize,
     solana_account_decoder::UiAccountEncoding,
-    solana_client::{nonblocking::pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig},
-    solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair, signer::Signer},
+    solana_client::{
+        nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+        rpc_config::RpcAccountInfoConfig,
+    },
+    solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey},
     std::{sync::Arc, time::Duration},
-    tokio::sync::Mutex,
-    tracing::{error, info},
+    tokio::time::{Instant, MissedTickBehavior},
+    tracing::{error, info, warn},
 };
 
+// MI: pace of the rpc poll while a landed tx waits for its new challenge
+const FAST_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Subscribes to the pool proof on every websocket endpoint and polls the rpc when the
+/// subscriptions go stale, all updates go through the proof source.
 pub async fn proof_tracking_processor(
-    ws_url: String,
-    wallet: Arc<Keypair>,
-    proof: Arc<Mutex<Proof>>,
-    last_challenge: Arc<Mutex<[u8; 32]>>,
+    config: ProofSourceConfig,
+    proof_source: Arc<ProofSource>,
+    rpc_client: Arc<RpcClient>,
+    wallet_pubkey: Pubkey,
 ) {
+    for (index, ws_url) in config.ws_urls.iter().enumerate() {
+        let ws_url = ws_url.clone();
+        let proof_source = proof_source.clone();
+        tokio::spawn(async move {
+            subscription_task(index, ws_url, proof_source, wallet_pubkey).await;
+        });
+    }
+
+    let mut interval = tokio::time::interval(FAST_POLL_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_poll = Instant::now();
+    let mut was_stale = false;
     loop {
-        info!(target: "server_log", "Establishing rpc websocket connection...");
-        let mut ps_client = PubsubClient::new(&ws_url).await;
+        interval.tick().await;
 
-        while ps_client.is_err() {
-            error!(target: "server_log", "Failed to connect to websocket, retrying...");
-            ps_client = PubsubClient::new(&ws_url).await;
-            tokio::time::sleep(Duration::from_millis(1000)).await;
+        let stale = proof_source.is_stale(config.stale_secs);
+        if stale != was_stale {
+            if stale {
+                warn!(target: "server_log", "Proof subscriptions are stale, polling the rpc every {}s.", config.poll_interval.as_secs());
+            } else {
+                info!(target: "server_log", "Proof subscriptions recovered, stopped polling the rpc.");
+            }
+            was_stale = stale;
         }
-        info!(target: "server_log", "RPC WS connection established!");
 
-        let app_wallet = wallet.clone();
-        if let Ok(ps_client) = ps_client {
-            // The `PubsubClient` must be `Arc`ed to share it across threads/tasks.
-            let ps_client = Arc::new(ps_client);
-            let account_pubkey = utils::mini_pool_proof_pubkey(app_wallet.pubkey());
-            let ps_client = Arc::clone(&ps_client); // MI
-            let pubsub = ps_client
-                .account_subscribe(
-                    &account_pubkey,
-                    Some(RpcAccountInfoConfig {
-                        encoding: Some(UiAccountEncoding::Base64),
-                        data_slice: None,
-                        commitment: Some(CommitmentConfig::confirmed()),
-                        min_context_slot: None,
-                    }),
-                )
-                .await;
+        let poll_due = stale && last_poll.elapsed() >= config.poll_interval;
+        if !proof_source.has_waiters() && !poll_due {
+            continue;
+        }
+        last_poll = Instant::now();
+        match utils::get_mini_pool_proof(&rpc_client, wallet_pubkey).await {
+            Ok(proof) => {
+                proof_source.apply(proof, ProofOrigin::Rpc).await;
+            },
+            Err(e) => {
+                error!(target: "server_log", "Failed to poll pool proof: {}", e);
+            },
+        }
+    }
+}
+
+async fn subscription_task(
+    index: usize,
+    ws_url: String,
+    proof_source: Arc<ProofSource>,
+    wallet_pubkey: Pubkey,
+) {
+    let endpoint = &proof_source.endpoints[index];
+    let account_pubkey = utils::mini_pool_proof_pubkey(wallet_pubkey);
+    loop {
+        info!(target: "server_log", "Establishing rpc websocket connection to {}...", endpoint.label);
+        let ps_client = match PubsubClient::new(&ws_url).await {
+            Ok(ps_client) => ps_client,
+            Err(e) => {
+                error!(target: "server_log", "Failed to connect to websocket {}: {}, retrying...", endpoint.label, e);
+                tokio::time::sleep(Duration::from_millis(1000)).await;
+                continue;
+            },
+        };
+        info!(target: "server_log", "RPC WS connection to {} established!", endpoint.label);
+
+        let pubsub = ps_client
+            .account_subscribe(
+                &account_pubkey,
+                Some(RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    data_slice: None,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    min_context_slot: None,
+                }),
+            )
+            .await;
 
-            info!(target: "server_log", "Subscribed notification of pool proof updates via websocket");
-            if let Ok((mut account_sub_notifications, _account_unsub)) = pubsub {
+        match pubsub {
+            Ok((mut account_sub_notifications, _account_unsub)) => {
+                info!(target: "server_log", "Subscribed notification of pool proof updates via websocket {}", endpoint.label);
+                endpoint.set_connected(true);
+                proof_source.proof_updated();
                 // MI: vanilla, by design while let will exit when None received
                 while let Some(response) = account_sub_notifications.next().await {
-                    let data = response.value.data.decode();
-                    if let Some(data_bytes) = data {
+                    if let Some(data_bytes) = response.value.data.decode() {
                         if let Ok(new_proof) = Proof::try_from_bytes(&data_bytes) {
-                            info!(target: "server_log",
-                                "Received new proof with challenge: {}",
-                                BASE64_STANDARD.encode(new_proof.challenge)
-                            );
-
-                            let lock = last_challenge.lock().await;
-                            let last_challenge = lock.clone();
-                            drop(lock);
-
-                            if last_challenge.eq(&new_proof.challenge) {
-                                error!(target: "server_log", "Websocket tried to update proof with existing/old challenge!");
-                            } else {
-                                let mut app_proof = proof.lock().await;
-                                *app_proof = *new_proof;
-                                drop(app_proof);
-                            }
+                            proof_source.apply(*new_proof, ProofOrigin::Websocket(index)).await;
                         }
                     }
                 }
-
-                // // MI: use loop, by design while let will exit when None received
-                // loop {
-                //     if let Some(response) = account_sub_notifications.next().await {
-                //         let data = response.value.data.decode();
-                //         if let Some(data_bytes) = data {
-                //             if let Ok(new_proof) = Proof::try_from_bytes(&data_bytes) {
-                //                 {
-                //                     let mut app_proof = proof.lock().await;
-                //                     *app_proof = *new_proof;
-                //                     drop(app_proof);
-                //                 }
-                //             }
-                //         }
-                //     }
-                // }
-            }
+                endpoint.set_connected(false);
+                warn!(target: "server_log", "Proof subscription via websocket {} closed.", endpoint.label);
+            },
+            Err(e) => {
+                error!(target: "server_log", "Failed to subscribe to pool proof via websocket {}: {}", endpoint.label, e);
+                tokio::time::sleep(Duration::from_millis(1000)).await;
+            },
         }
     }
 }
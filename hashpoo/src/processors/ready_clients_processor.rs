@@ -1,23 +1,27 @@
 use {
     crate::{
+        events::{publish, PoolEvent},
         message::ServerMessageStartMining,
+        pools::ClientNonceRanges,
+        profitability::ProfitabilityGuard,
         utils::{get_cutoff, get_cutoff_with_risk},
-        AppState, EpochHashes, PAUSED,
+        AppState, EpochHashes, OPERATOR_PAUSED,
     },
     axum::extract::ws::Message,
     base64::{prelude::BASE64_STANDARD, Engine},
     futures::SinkExt,
     ore_api::state::Proof,
     solana_client::nonblocking::rpc_client::RpcClient,
-    solana_sdk::pubkey::Pubkey,
     std::{
-        collections::{HashMap, HashSet},
+        collections::HashSet,
         net::SocketAddr,
-        ops::Range,
-        sync::{atomic::Ordering::Relaxed, Arc},
+        sync::{
+            atomic::{AtomicBool, Ordering::Relaxed},
+            Arc,
+        },
         time::Duration,
     },
-    tokio::sync::{Mutex, RwLock},
+    tokio::sync::{broadcast, Mutex, RwLock},
     tracing::{error, info},
 };
 
@@ -28,11 +32,14 @@ pub async fn ready_clients_processor(
     shared_state: Arc<RwLock<AppState>>,
     app_proof: Arc<Mutex<Proof>>,
     epoch_hashes: Arc<RwLock<EpochHashes>>,
+    paused: Arc<AtomicBool>,
     ready_clients: Arc<Mutex<HashSet<SocketAddr>>>,
     app_nonce: Arc<Mutex<u64>>,
-    client_nonce_ranges: Arc<RwLock<HashMap<Pubkey, Range<u64>>>>,
+    client_nonce_ranges: ClientNonceRanges,
     buffer_time: Arc<u64>,
     risk_time: Arc<u64>,
+    profitability: Arc<ProfitabilityGuard>,
+    event_sender: broadcast::Sender<PoolEvent>,
 ) {
     loop {
         let mut clients = Vec::new();
@@ -44,7 +51,12 @@ pub async fn ready_clients_processor(
             drop(ready_clients_lock);
         };
 
-        if !PAUSED.load(Relaxed) && clients.len() > 0 {
+        // MI: with the daily fee budget spent no solution could be submitted anyway
+        if !paused.load(Relaxed)
+            && !OPERATOR_PAUSED.load(Relaxed)
+            && !profitability.budget_spent()
+            && !clients.is_empty()
+        {
             let lock = app_proof.lock().await;
             let proof = lock.clone();
             drop(lock);
@@ -82,6 +94,14 @@ pub async fn ready_clients_processor(
                 info!(target: "contribution_log", "Mission to clients with challenge: {}", BASE64_STANDARD.encode(challenge));
                 info!(target: "server_log", "and cutoff in: {}s", cutoff);
                 info!(target: "contribution_log", "and cutoff in: {}s", cutoff);
+                publish(
+                    &event_sender,
+                    PoolEvent::MissionDispatched {
+                        challenge: BASE64_STANDARD.encode(challenge),
+                        cutoff,
+                        num_clients,
+                    },
+                );
                 let shared_state = shared_state.read().await;
                 let sockets = shared_state.sockets.clone();
                 drop(shared_state);
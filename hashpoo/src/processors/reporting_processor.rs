@@ -1,18 +1,26 @@
 use {
     crate::{
         database::{Database, PoweredByDbms},
-        utils::ORE_TOKEN_DECIMALS,
-        MineConfig, POWERED_BY_DBMS,
+        hashrate::{format_hashrate, HashrateEstimator},
+        notification::Notification,
+        pools::Pools,
+        reports::{self, window_label, ReportOutput, ReportsConfig},
+        POWERED_BY_DBMS,
     },
     chrono::Local,
-    std::{ops::Div, str::FromStr, sync::Arc, time::Duration},
-    tokio::time::Instant,
+    std::{str::FromStr, sync::Arc, time::Duration},
+    tokio::{
+        sync::{mpsc::UnboundedSender, RwLock},
+        time::Instant,
+    },
     tracing::{error, info, warn},
 };
 pub async fn reporting_processor(
-    interval_in_hrs: u64,
-    mine_config: Arc<MineConfig>,
+    config: ReportsConfig,
+    pools: Arc<Pools>,
     database: Arc<Database>,
+    hashrate_estimator: Arc<RwLock<HashrateEstimator>>,
+    notification_sender: UnboundedSender<Notification>,
 ) {
     // initial report starts in 5 mins(300s)
     let mut time_to_next_reporting: u64 = 300;
@@ -20,6 +28,37 @@ pub async fn reporting_processor(
     loop {
         let current_timestamp = timer.elapsed().as_secs();
         if current_timestamp.ge(&time_to_next_reporting) {
+            // hashrate is tracked in memory, so it is reported with or without dbms
+            if config.outputs.contains(&ReportOutput::Log) {
+                let (pool_hashrate, miners_hashrate) = {
+                    let reader = hashrate_estimator.read().await;
+                    (reader.pool_hashrate(), reader.miners_hashrate())
+                };
+                let report_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                info!(target: "server_log",
+                    "[{report_time}] Pool hashrate (10m / 1h / 24h): {} / {} / {}, miners: {}, workers: {}",
+                    format_hashrate(pool_hashrate.hashrate.hashrate_10m),
+                    format_hashrate(pool_hashrate.hashrate.hashrate_1h),
+                    format_hashrate(pool_hashrate.hashrate.hashrate_24h),
+                    pool_hashrate.num_miners,
+                    pool_hashrate.num_workers,
+                );
+                info!(target: "server_log", "miner_pubkey     workers   hashrate_10m     hashrate_1h      hashrate_24h");
+                for miner in miners_hashrate {
+                    let mp = miner.pubkey;
+                    let len = mp.len();
+                    let short_mp = format!("{}...{}", &mp[0..6], &mp[len - 4..len]);
+                    info!(target: "server_log",
+                        "{}    {:7}   {:>14}   {:>14}   {:>14}",
+                        short_mp,
+                        miner.workers.len(),
+                        format_hashrate(miner.hashrate.hashrate_10m),
+                        format_hashrate(miner.hashrate.hashrate_1h),
+                        format_hashrate(miner.hashrate.hashrate_24h),
+                    );
+                }
+            }
+
             let powered_by_dbms = POWERED_BY_DBMS.get_or_init(|| {
                 let key = "POWERED_BY_DBMS";
                 match std::env::var(key) {
@@ -31,55 +70,35 @@ pub async fn reporting_processor(
             if powered_by_dbms == &PoweredByDbms::Postgres
                 || powered_by_dbms == &PoweredByDbms::Sqlite
             {
-                info!(target: "server_log", "Preparing client summaries for last 24 hours.");
-                let summaries_last_24_hrs =
-                    database.get_summaries_for_last_24_hours(mine_config.pool_id).await;
-
-                match summaries_last_24_hrs {
-                    Ok(summaries) => {
-                        // printing report header
-                        let report_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-                        let report_title = "Miner summaries for last 24 hours:";
-                        info!(target: "server_log", "[{report_time}] {report_title}");
-                        // println!("[{report_time}] {report_title}");
-                        let line_header = format!(
-                            "miner_pubkey     num_contributions   min_diff   avg_diff   max_diff   earning_sub_total   percent"
-                        );
-                        info!(target: "server_log", "{line_header}");
-                        // println!("{line_header}");
-
-                        let decimals = 10f64.powf(ORE_TOKEN_DECIMALS as f64);
-                        for summary in summaries {
-                            let mp = summary.miner_pubkey;
-                            let len = mp.len();
-                            let short_mp = format!("{}...{}", &mp[0..6], &mp[len - 4..len]);
-                            let earned_rewards_dec =
-                                (summary.earning_sub_total as f64).div(decimals);
-                            let line = format!(
-                                "{}    {:17}   {:8}   {:8}   {:8}       {:.11}   {:>6.2}%",
-                                short_mp,
-                                summary.num_of_contributions,
-                                summary.min_diff,
-                                summary.avg_diff,
-                                summary.max_diff,
-                                earned_rewards_dec,
-                                summary.percent
-                            );
-
-                            info!(target: "server_log", "{line}");
-                            // println!("{line}");
+                for pool in pools.iter() {
+                    for window_hours in config.windows.iter() {
+                        info!(target: "server_log", "Preparing pool {} report for last {}.", pool.authority, window_label(*window_hours));
+                        match reports::build_report(
+                            &database,
+                            &pool.authority,
+                            pool.mine_config.pool_id,
+                            pool.mine_config.commissions_miner_id,
+                            *window_hours,
+                        )
+                        .await
+                        {
+                            Ok(report) => reports::publish_report(
+                                &report,
+                                &config.outputs,
+                                &config.dir,
+                                &notification_sender,
+                            ),
+                            Err(e) => {
+                                error!(target: "server_log", "Failed to prepare pool {} report: {e:?}", pool.authority);
+                            },
                         }
-                    },
-                    Err(e) => {
-                        error!(target: "server_log", "Failed to prepare summary report: {e:?}");
-                    },
+                    }
                 }
-                time_to_next_reporting = interval_in_hrs * 3600; // in seconds
-                timer = Instant::now();
             } else {
-                warn!(target: "server_log", "Reporting system cannot be used when POWERED_BY_DBMS disabled. Exiting reporting system.");
-                return;
+                warn!(target: "server_log", "Pool reports are unavailable when POWERED_BY_DBMS disabled.");
             }
+            time_to_next_reporting = config.interval_in_hrs * 3600; // in seconds
+            timer = Instant::now();
         } else {
             tokio::time::sleep(Duration::from_secs(
                 time_to_next_reporting.saturating_sub(current_timestamp),
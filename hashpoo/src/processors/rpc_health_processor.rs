@@ -0,0 +1,18 @@
+use {
+    crate::rpc_pool::RpcPool,
+    std::{sync::Arc, time::Duration},
+    tokio::time::MissedTickBehavior,
+};
+
+// MI: also paces how soon a recovered endpoint gets its reads back
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Keeps the health and slot lag of every rpc endpoint up to date between requests.
+pub async fn rpc_health_processor(rpc_pool: Arc<RpcPool>) {
+    let mut interval = tokio::time::interval(PROBE_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        rpc_pool.probe().await;
+    }
+}
@@ -0,0 +1,19 @@
+use {
+    crate::prefetch::SubmissionPrefetch,
+    std::{sync::Arc, time::Duration},
+    tokio::time::MissedTickBehavior,
+};
+
+// MI: each value has its own refresh period, this only paces the checks
+const PREFETCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps the blockhash, config, clock offset and fee estimate of a pool's next mine
+/// transaction fresh, so the submission loop does not fetch them at cutoff.
+pub async fn submission_prefetch_processor(prefetch: Arc<SubmissionPrefetch>) {
+    let mut interval = tokio::time::interval(PREFETCH_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        prefetch.refresh().await;
+    }
+}
@@ -0,0 +1,392 @@
+use {
+    crate::{
+        metrics::write_gauge, models::FeeSpend, reports::BASE_FEE_LAMPORTS,
+        utils::ORE_TOKEN_DECIMALS,
+    },
+    axum::{Extension, Json},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    chrono::{Local, Utc},
+    ore_api::state::Config,
+    serde::Serialize,
+    solana_sdk::native_token::{lamports_to_sol, LAMPORTS_PER_SOL},
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::{info, warn},
+};
+
+pub const BUDGET_WINDOW_HOURS: u64 = 24;
+const BUDGET_WINDOW_SECS: i64 = BUDGET_WINDOW_HOURS as i64 * 60 * 60;
+// MI: spend of a challenge never solved is dropped after this
+const PENDING_TTL: Duration = Duration::from_secs(600);
+const HISTORY_LEN: usize = 100;
+
+pub struct ProfitabilityConfig {
+    /// ORE price in SOL, None leaves fees uncapped by the expected reward.
+    pub ore_price_sol: Option<f64>,
+    /// Max share of the expected reward value a mine transaction may spend on fees.
+    pub max_fee_fraction: f64,
+    /// Max fees spent over the last 24 hours, None for no budget.
+    pub daily_budget_lamports: Option<u64>,
+}
+
+/// Fees spent on a solved challenge against what it earned.
+#[derive(Serialize, Clone)]
+pub struct ChallengeSpend {
+    pub settled_at: String,
+    pub pool_id: i32,
+    pub challenge: String,
+    pub difficulty: u32,
+    pub attempts: u32,
+    pub spent_sol: f64,
+    pub expected_reward: f64,
+    pub reward: f64,
+    /// Reward value in SOL, None without an ORE price.
+    pub reward_value_sol: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct ProfitabilitySummary {
+    ore_price_sol: Option<f64>,
+    max_fee_fraction: f64,
+    daily_budget_sol: Option<f64>,
+    spent_24h_sol: f64,
+    challenges: Vec<ChallengeSpend>,
+}
+
+/// Why a mine transaction is not sent.
+#[derive(Debug, PartialEq)]
+pub enum FeeGuardError {
+    /// The expected reward is not worth the signature fees.
+    Unprofitable(String),
+    /// What is left of the daily budget can't cover the signature fees.
+    BudgetSpent(String),
+}
+
+impl std::fmt::Display for FeeGuardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeeGuardError::Unprofitable(msg) | FeeGuardError::BudgetSpent(msg) => {
+                write!(f, "{}", msg)
+            },
+        }
+    }
+}
+
+/// Fees a mine transaction may pay.
+#[derive(Debug, PartialEq)]
+pub struct FeeLimits {
+    /// The tip, lowered to what the expected reward and the budget leave for it.
+    pub tip: u64,
+    /// Max compute unit price in micro-lamports, None when nothing caps it.
+    pub max_priority_fee: Option<u64>,
+}
+
+/// Keeps the fees of mine transactions within a share of the expected reward and within the
+/// daily budget, shared by all pools as they share the fee wallet.
+pub struct ProfitabilityGuard {
+    config: ProfitabilityConfig,
+    /// Unix timestamp and lamports of each paid transaction of the budget window.
+    spends: Mutex<VecDeque<(i64, u64)>>,
+    /// Lamports and paid attempts per challenge not settled yet.
+    pending: Mutex<HashMap<[u8; 32], (Instant, u64, u32)>>,
+    history: Mutex<VecDeque<ChallengeSpend>>,
+}
+
+/// ORE grains the solution earns, the base reward rate doubled for each difficulty above the
+/// min difficulty, times the stake multiplier of the proof balance.
+pub fn expected_reward(config: &Config, difficulty: u32, proof_balance: u64) -> u64 {
+    let doublings = (difficulty as u64).saturating_sub(config.min_difficulty).min(63) as u32;
+    let reward = config.base_reward_rate.saturating_mul(2u64.saturating_pow(doublings));
+    if config.top_balance > 0 {
+        let multiplier = 1.0 + (proof_balance as f64 / config.top_balance as f64).min(1.0);
+        (reward as f64 * multiplier) as u64
+    } else {
+        reward
+    }
+}
+
+fn ore(grains: u64) -> f64 {
+    grains as f64 / 10f64.powf(ORE_TOKEN_DECIMALS as f64)
+}
+
+impl ProfitabilityGuard {
+    pub fn new(config: ProfitabilityConfig) -> Self {
+        ProfitabilityGuard {
+            config,
+            spends: Mutex::new(VecDeque::new()),
+            pending: Mutex::new(HashMap::new()),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "ore price {} SOL, max fee fraction {}, daily budget {} SOL",
+            self.config.ore_price_sol.map_or("unset".to_string(), |price| price.to_string()),
+            self.config.max_fee_fraction,
+            self.config
+                .daily_budget_lamports
+                .map_or("unset".to_string(), |budget| lamports_to_sol(budget).to_string()),
+        )
+    }
+
+    /// Limits the fees of a transaction of `cu_limit` compute units paying `base_lamports` for
+    /// its signatures and `tip`. The tip is lowered and the priority fee capped to fit in the
+    /// share of the expected reward and in the daily budget, Err when not even the signature
+    /// fees fit.
+    pub fn limit_fees(
+        &self,
+        expected_reward: Option<u64>,
+        cu_limit: u32,
+        base_lamports: u64,
+        tip: u64,
+    ) -> Result<FeeLimits, FeeGuardError> {
+        let mut max_lamports: Option<u64> = None;
+
+        if let (Some(price), Some(expected_reward)) = (self.config.ore_price_sol, expected_reward) {
+            let value_lamports = ore(expected_reward) * price * LAMPORTS_PER_SOL as f64;
+            let max_fee_lamports = (value_lamports * self.config.max_fee_fraction) as u64;
+            if max_fee_lamports < base_lamports {
+                return Err(FeeGuardError::Unprofitable(format!(
+                    "Expected reward of {:.11} ORE allows {} SOL of fees, less than the {} SOL of signatures",
+                    ore(expected_reward),
+                    lamports_to_sol(max_fee_lamports),
+                    lamports_to_sol(base_lamports)
+                )));
+            }
+            max_lamports = Some(max_fee_lamports);
+        }
+
+        if let Some(budget) = self.config.daily_budget_lamports {
+            let remaining = budget.saturating_sub(self.spent_24h());
+            if remaining < base_lamports {
+                return Err(FeeGuardError::BudgetSpent(format!(
+                    "Daily fee budget of {} SOL is spent, {} SOL left",
+                    lamports_to_sol(budget),
+                    lamports_to_sol(remaining)
+                )));
+            }
+            max_lamports = Some(max_lamports.map_or(remaining, |max| max.min(remaining)));
+        }
+
+        let Some(max_lamports) = max_lamports else {
+            return Ok(FeeLimits { tip, max_priority_fee: None });
+        };
+        let tip = tip.min(max_lamports - base_lamports);
+        Ok(FeeLimits {
+            tip,
+            max_priority_fee: Some(
+                (max_lamports - base_lamports - tip).saturating_mul(1_000_000)
+                    / (cu_limit.max(1) as u64),
+            ),
+        })
+    }
+
+    /// Whether what is left of the daily budget can't pay another transaction, mining pauses
+    /// until older spends leave the window.
+    pub fn budget_spent(&self) -> bool {
+        self.config
+            .daily_budget_lamports
+            .is_some_and(|budget| budget.saturating_sub(self.spent_24h()) < 2 * BASE_FEE_LAMPORTS)
+    }
+
+    /// Fees of a mine transaction that landed, whether it succeeded or not.
+    pub fn record_spend(&self, challenge: [u8; 32], lamports: u64) {
+        self.push_spend(Utc::now().timestamp(), lamports);
+
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, (at, _, _)| now.duration_since(*at) < PENDING_TTL);
+        let entry = pending.entry(challenge).or_insert((now, 0, 0));
+        entry.1 += lamports;
+        entry.2 += 1;
+    }
+
+    /// Reloads the spends of the budget window recorded before a restart.
+    pub fn restore(&self, spends: &[FeeSpend]) {
+        let now = Utc::now().timestamp();
+        for spend in spends {
+            self.push_spend(now - spend.age_secs, spend.lamports.max(0) as u64 + BASE_FEE_LAMPORTS);
+        }
+        info!(target: "server_log", "Restored {} SOL of fees spent over the last {}h", lamports_to_sol(self.spent_24h()), BUDGET_WINDOW_HOURS);
+    }
+
+    fn push_spend(&self, at: i64, lamports: u64) {
+        let now = Utc::now().timestamp();
+        let mut spends = self.spends.lock().unwrap();
+        spends.push_back((at, lamports));
+        spends.make_contiguous().sort_by_key(|(at, _)| *at);
+        while spends.front().is_some_and(|(at, _)| now - *at > BUDGET_WINDOW_SECS) {
+            spends.pop_front();
+        }
+    }
+
+    /// Closes the books of a solved challenge and logs its spend against its reward.
+    pub fn settle(
+        &self,
+        pool_id: i32,
+        challenge: [u8; 32],
+        difficulty: u32,
+        expected_reward: Option<u64>,
+        reward: u64,
+    ) {
+        let (_, spent, attempts) =
+            self.pending.lock().unwrap().remove(&challenge).unwrap_or((Instant::now(), 0, 0));
+        let reward_value_sol = self.config.ore_price_sol.map(|price| ore(reward) * price);
+
+        let spend = ChallengeSpend {
+            settled_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            pool_id,
+            challenge: BASE64_STANDARD.encode(challenge),
+            difficulty,
+            attempts,
+            spent_sol: lamports_to_sol(spent),
+            expected_reward: expected_reward.map_or(0.0, ore),
+            reward: ore(reward),
+            reward_value_sol,
+        };
+        match reward_value_sol {
+            Some(value) if value > 0.0 => {
+                info!(target: "server_log",
+                    "Challenge {} spent {:.9} SOL in {} txns for {:.11} ORE (~{:.9} SOL), fees at {:.1}% of reward",
+                    spend.challenge, spend.spent_sol, attempts, spend.reward, value, spend.spent_sol / value * 100.0
+                );
+                if spend.spent_sol > value {
+                    warn!(target: "server_log", "Challenge {} cost more in fees than it earned", spend.challenge);
+                }
+            },
+            _ => {
+                info!(target: "server_log",
+                    "Challenge {} spent {:.9} SOL in {} txns for {:.11} ORE",
+                    spend.challenge, spend.spent_sol, attempts, spend.reward
+                );
+            },
+        }
+
+        let mut history = self.history.lock().unwrap();
+        history.push_back(spend);
+        while history.len() > HISTORY_LEN {
+            history.pop_front();
+        }
+    }
+
+    pub fn spent_24h(&self) -> u64 {
+        let now = Utc::now().timestamp();
+        self.spends
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(at, _)| now - *at <= BUDGET_WINDOW_SECS)
+            .map(|(_, lamports)| lamports)
+            .sum()
+    }
+
+    pub fn write_metrics(&self, out: &mut String) {
+        write_gauge(
+            out,
+            "hashpoo_fee_spent_24h_sol",
+            "Fees spent on mine transactions over the last 24 hours.",
+            lamports_to_sol(self.spent_24h()),
+        );
+        if let Some(budget) = self.config.daily_budget_lamports {
+            write_gauge(
+                out,
+                "hashpoo_fee_budget_24h_sol",
+                "Daily fee budget of mine transactions.",
+                lamports_to_sol(budget),
+            );
+        }
+    }
+}
+
+pub async fn get_profitability(
+    Extension(guard): Extension<Arc<ProfitabilityGuard>>,
+) -> Json<ProfitabilitySummary> {
+    Json(ProfitabilitySummary {
+        ore_price_sol: guard.config.ore_price_sol,
+        max_fee_fraction: guard.config.max_fee_fraction,
+        daily_budget_sol: guard.config.daily_budget_lamports.map(lamports_to_sol),
+        spent_24h_sol: lamports_to_sol(guard.spent_24h()),
+        challenges: guard.history.lock().unwrap().iter().rev().cloned().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_ORE: u64 = 100_000_000_000;
+
+    fn guard(ore_price_sol: Option<f64>, daily_budget_lamports: Option<u64>) -> ProfitabilityGuard {
+        ProfitabilityGuard::new(ProfitabilityConfig {
+            ore_price_sol,
+            max_fee_fraction: 0.5,
+            daily_budget_lamports,
+        })
+    }
+
+    #[test]
+    fn doubles_the_reward_per_difficulty_and_applies_the_stake_multiplier() {
+        let config = Config {
+            base_reward_rate: 1_000,
+            last_reset_at: 0,
+            min_difficulty: 10,
+            top_balance: 0,
+        };
+        assert_eq!(expected_reward(&config, 8, 0), 1_000);
+        assert_eq!(expected_reward(&config, 12, 0), 4_000);
+
+        let config = Config { top_balance: 100, ..config };
+        assert_eq!(expected_reward(&config, 10, 50), 1_500);
+        assert_eq!(expected_reward(&config, 10, 500), 2_000);
+    }
+
+    #[test]
+    fn leaves_fees_uncapped_without_price_or_budget() {
+        let limits = guard(None, None).limit_fees(Some(ONE_ORE), 400_000, 10_000, 1_000);
+        assert_eq!(limits, Ok(FeeLimits { tip: 1_000, max_priority_fee: None }));
+    }
+
+    #[test]
+    fn caps_the_tip_and_priority_fee_to_the_reward_share() {
+        // MI: 1 ORE at 0.001 SOL leaves 500_000 lamports of fees
+        let guard = guard(Some(0.001), None);
+        let limits = guard.limit_fees(Some(ONE_ORE), 400_000, 10_000, 100_000);
+        assert_eq!(limits, Ok(FeeLimits { tip: 100_000, max_priority_fee: Some(975_000) }));
+
+        let limits = guard.limit_fees(Some(ONE_ORE), 400_000, 10_000, 1_000_000);
+        assert_eq!(limits, Ok(FeeLimits { tip: 490_000, max_priority_fee: Some(0) }));
+    }
+
+    #[test]
+    fn refuses_a_reward_not_worth_the_signatures() {
+        let limits = guard(Some(0.001), None).limit_fees(Some(1_000_000), 400_000, 10_000, 0);
+        assert!(matches!(limits, Err(FeeGuardError::Unprofitable(_))));
+    }
+
+    #[test]
+    fn stops_at_a_spent_budget() {
+        let guard = guard(None, Some(100_000));
+        let limits = guard.limit_fees(None, 400_000, 10_000, 0);
+        assert_eq!(limits, Ok(FeeLimits { tip: 0, max_priority_fee: Some(225_000) }));
+        assert!(!guard.budget_spent());
+
+        guard.record_spend([0; 32], 95_000);
+        let limits = guard.limit_fees(None, 400_000, 10_000, 0);
+        assert!(matches!(limits, Err(FeeGuardError::BudgetSpent(_))));
+        assert!(guard.budget_spent());
+    }
+
+    #[test]
+    fn restores_the_spends_of_the_window() {
+        let guard = guard(None, Some(100_000));
+        guard.restore(&[
+            FeeSpend { age_secs: 60 * 60, lamports: 40_000 },
+            FeeSpend { age_secs: 2 * BUDGET_WINDOW_SECS, lamports: 40_000 },
+        ]);
+        assert_eq!(guard.spent_24h(), 40_000 + BASE_FEE_LAMPORTS);
+    }
+}
@@ -0,0 +1,411 @@
+use {
+    crate::metrics::write_header,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    ore_api::state::Proof,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::BTreeMap,
+        fmt::Write,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tokio::sync::{watch, Mutex},
+    tracing::{debug, info},
+};
+
+pub struct ProofSourceConfig {
+    pub ws_urls: Vec<String>,
+    /// A subscription without updates for this long is no longer trusted on its own.
+    pub stale_secs: u64,
+    pub poll_interval: Duration,
+}
+
+impl ProofSourceConfig {
+    /// RPC_WS_URL may list several comma separated endpoints, each gets its own subscription.
+    pub fn ws_urls_from_env() -> Vec<String> {
+        let mut ws_urls: Vec<String> = Vec::new();
+        if let Ok(val) = std::env::var("RPC_WS_URL") {
+            for url in val.split(',').map(|url| url.trim()).filter(|url| !url.is_empty()) {
+                if !ws_urls.iter().any(|existing| existing == url) {
+                    ws_urls.push(url.to_string());
+                }
+            }
+        }
+        ws_urls
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofOrigin {
+    /// Index into the websocket endpoints.
+    Websocket(usize),
+    Rpc,
+}
+
+pub struct ProofEndpoint {
+    /// Host only, the full url may carry an api key.
+    pub label: String,
+    connected: AtomicBool,
+    last_update: AtomicU64,
+    num_updates: AtomicU64,
+}
+
+impl ProofEndpoint {
+    fn new(index: usize, url: &str) -> Self {
+        let label = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_else(|| format!("ws-{}", index));
+        ProofEndpoint {
+            label,
+            connected: AtomicBool::new(false),
+            last_update: AtomicU64::new(0),
+            num_updates: AtomicU64::new(0),
+        }
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+        if connected {
+            // MI: a fresh subscription counts as up to date until it proves otherwise
+            self.last_update.store(now_secs(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+}
+
+/// Single owner of the pool proof. Websocket subscriptions and rpc polls all feed `apply`,
+/// which dedupes them by challenge, and waiters are woken once a new challenge lands.
+pub struct ProofSource {
+    proof: Arc<Mutex<Proof>>,
+    challenge: watch::Sender<[u8; 32]>,
+    pub endpoints: Vec<ProofEndpoint>,
+    waiters: AtomicUsize,
+    last_poll_ok: AtomicU64,
+    /// Last time the proof changed or a subscription came up, read by the health checks.
+    last_update: AtomicU64,
+    /// Milliseconds between the last hash on-chain and the pool receiving the new challenge.
+    last_latency_ms: AtomicU64,
+    first_by: std::sync::Mutex<BTreeMap<String, u64>>,
+}
+
+impl ProofSource {
+    pub fn new(proof: Arc<Mutex<Proof>>, initial: &Proof, ws_urls: &[String]) -> Self {
+        let (challenge, _) = watch::channel(initial.challenge);
+        ProofSource {
+            proof,
+            challenge,
+            endpoints: ws_urls
+                .iter()
+                .enumerate()
+                .map(|(i, url)| ProofEndpoint::new(i, url))
+                .collect(),
+            waiters: AtomicUsize::new(0),
+            last_poll_ok: AtomicU64::new(0),
+            // MI: a freshly started pool is not reported stale
+            last_update: AtomicU64::new(now_secs()),
+            last_latency_ms: AtomicU64::new(0),
+            first_by: std::sync::Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn origin_label(&self, origin: ProofOrigin) -> String {
+        match origin {
+            ProofOrigin::Websocket(index) => self.endpoints[index].label.clone(),
+            ProofOrigin::Rpc => "rpc".to_string(),
+        }
+    }
+
+    /// Returns true when the update carried a new challenge.
+    pub async fn apply(&self, new_proof: Proof, origin: ProofOrigin) -> bool {
+        let now = now_secs();
+        if let ProofOrigin::Websocket(index) = origin {
+            let endpoint = &self.endpoints[index];
+            endpoint.last_update.store(now, Ordering::Relaxed);
+            endpoint.num_updates.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.last_poll_ok.store(now, Ordering::Relaxed);
+        }
+
+        let mut app_proof = self.proof.lock().await;
+        if new_proof.challenge.eq(&app_proof.challenge) {
+            // MI: same challenge, a balance change from a claim or a slower endpoint repeating it
+            let changed = new_proof.balance != app_proof.balance;
+            *app_proof = new_proof;
+            drop(app_proof);
+            self.challenge.send_if_modified(|challenge| {
+                let modified = *challenge != new_proof.challenge;
+                *challenge = new_proof.challenge;
+                modified
+            });
+            if changed {
+                self.proof_updated();
+            }
+            return false;
+        }
+        if new_proof.last_hash_at < app_proof.last_hash_at {
+            debug!(target: "server_log",
+                "Dropped outdated proof from {} with challenge: {}",
+                self.origin_label(origin),
+                BASE64_STANDARD.encode(new_proof.challenge)
+            );
+            return false;
+        }
+        *app_proof = new_proof;
+        drop(app_proof);
+        self.challenge.send_replace(new_proof.challenge);
+        self.proof_updated();
+
+        let received_at_ms =
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let latency_ms = received_at_ms.saturating_sub(new_proof.last_hash_at.max(0) as u64 * 1000);
+        self.last_latency_ms.store(latency_ms, Ordering::Relaxed);
+
+        let label = self.origin_label(origin);
+        *self.first_by.lock().unwrap().entry(label.clone()).or_default() += 1;
+        info!(target: "server_log",
+            "Received new proof from {} with challenge: {}",
+            label,
+            BASE64_STANDARD.encode(new_proof.challenge)
+        );
+        true
+    }
+
+    /// Resolves with the new proof once the challenge moves on from `old_challenge`, or None
+    /// after `timeout`. The poller checks the rpc at a fast pace while anyone is waiting.
+    pub async fn wait_for_new_challenge(
+        &self,
+        old_challenge: [u8; 32],
+        timeout: Duration,
+    ) -> Option<Proof> {
+        let mut receiver = self.challenge.subscribe();
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+        // MI: the watch Ref is not Send, it is dropped before awaiting the proof lock
+        let changed = tokio::time::timeout(
+            timeout,
+            receiver.wait_for(|challenge| challenge.ne(&old_challenge)),
+        )
+        .await
+        .is_ok_and(|res| res.is_ok());
+        self.waiters.fetch_sub(1, Ordering::Relaxed);
+
+        if changed {
+            Some(*self.proof.lock().await)
+        } else {
+            None
+        }
+    }
+
+    pub fn proof_updated(&self) {
+        self.last_update.store(now_secs(), Ordering::Relaxed);
+    }
+
+    /// Seconds since the proof last changed.
+    pub fn update_age(&self) -> u64 {
+        now_secs().saturating_sub(self.last_update.load(Ordering::Relaxed))
+    }
+
+    pub fn has_waiters(&self) -> bool {
+        self.waiters.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn connected_endpoints(&self) -> usize {
+        self.endpoints.iter().filter(|endpoint| endpoint.is_connected()).count()
+    }
+
+    /// Stale when no subscription is connected or none has delivered for `stale_secs`.
+    pub fn is_stale(&self, stale_secs: u64) -> bool {
+        let now = now_secs();
+        !self.endpoints.iter().any(|endpoint| {
+            endpoint.is_connected()
+                && now.saturating_sub(endpoint.last_update.load(Ordering::Relaxed)) <= stale_secs
+        })
+    }
+
+    pub fn last_latency(&self) -> Duration {
+        Duration::from_millis(self.last_latency_ms.load(Ordering::Relaxed))
+    }
+
+    /// Seconds since the last successful rpc poll, None before the first one.
+    pub fn last_poll_age(&self) -> Option<u64> {
+        match self.last_poll_ok.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(now_secs().saturating_sub(at)),
+        }
+    }
+
+    /// Writes the proof source metrics of each pool, labelled by pool authority.
+    pub fn write_metrics(sources: &[(Pubkey, &ProofSource)], out: &mut String) {
+        write_header(
+            out,
+            "hashpoo_proof_ws_connected_endpoints",
+            "Websocket endpoints with a live proof subscription.",
+            "gauge",
+        );
+        for (pool, source) in sources {
+            let _ = writeln!(
+                out,
+                "hashpoo_proof_ws_connected_endpoints{{pool=\"{}\"}} {}",
+                pool,
+                source.connected_endpoints()
+            );
+        }
+        write_header(
+            out,
+            "hashpoo_proof_update_latency_seconds",
+            "Time from the last hash on-chain to the pool receiving the new challenge.",
+            "gauge",
+        );
+        for (pool, source) in sources {
+            let _ = writeln!(
+                out,
+                "hashpoo_proof_update_latency_seconds{{pool=\"{}\"}} {}",
+                pool,
+                source.last_latency().as_secs_f64()
+            );
+        }
+
+        let now = now_secs();
+        write_header(
+            out,
+            "hashpoo_proof_ws_last_update_age_seconds",
+            "Seconds since each websocket endpoint last delivered a proof update.",
+            "gauge",
+        );
+        for (pool, source) in sources {
+            for endpoint in source.endpoints.iter() {
+                let _ = writeln!(
+                    out,
+                    "hashpoo_proof_ws_last_update_age_seconds{{pool=\"{}\",endpoint=\"{}\"}} {}",
+                    pool,
+                    endpoint.label,
+                    now.saturating_sub(endpoint.last_update.load(Ordering::Relaxed))
+                );
+            }
+        }
+        write_header(
+            out,
+            "hashpoo_proof_ws_updates_total",
+            "Proof updates received per websocket endpoint, duplicates included.",
+            "counter",
+        );
+        for (pool, source) in sources {
+            for endpoint in source.endpoints.iter() {
+                let _ = writeln!(
+                    out,
+                    "hashpoo_proof_ws_updates_total{{pool=\"{}\",endpoint=\"{}\"}} {}",
+                    pool,
+                    endpoint.label,
+                    endpoint.num_updates.load(Ordering::Relaxed)
+                );
+            }
+        }
+        write_header(
+            out,
+            "hashpoo_proof_new_challenges_total",
+            "New challenges by the source that delivered them first.",
+            "counter",
+        );
+        for (pool, source) in sources {
+            for (origin, count) in source.first_by.lock().unwrap().iter() {
+                let _ = writeln!(
+                    out,
+                    "hashpoo_proof_new_challenges_total{{pool=\"{}\",source=\"{}\"}} {}",
+                    pool, origin, count
+                );
+            }
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    fn proof(challenge: u8, last_hash_at: i64, balance: u64) -> Proof {
+        Proof {
+            authority: Pubkey::default(),
+            balance,
+            challenge: [challenge; 32],
+            last_hash: [0; 32],
+            last_hash_at,
+            last_stake_at: 0,
+            miner: Pubkey::default(),
+            total_hashes: 0,
+            total_rewards: 0,
+        }
+    }
+
+    fn source(initial: &Proof) -> ProofSource {
+        let urls =
+            vec!["wss://a.example.com/?api-key=1".to_string(), "wss://b.example.com".to_string()];
+        ProofSource::new(Arc::new(Mutex::new(*initial)), initial, &urls)
+    }
+
+    #[tokio::test]
+    async fn applies_each_challenge_once() {
+        let initial = proof(1, 100, 0);
+        let source = source(&initial);
+
+        assert!(source.apply(proof(2, 160, 0), ProofOrigin::Websocket(1)).await);
+        assert!(!source.apply(proof(2, 160, 0), ProofOrigin::Websocket(0)).await);
+        assert!(!source.apply(proof(2, 160, 0), ProofOrigin::Rpc).await);
+
+        assert_eq!(source.proof.lock().await.challenge, [2; 32]);
+        let first_by = source.first_by.lock().unwrap().clone();
+        assert_eq!(first_by, BTreeMap::from([("b.example.com".to_string(), 1)]));
+        assert_eq!(source.endpoints[0].num_updates.load(Ordering::Relaxed), 1);
+        assert!(source.last_poll_age().is_some());
+    }
+
+    #[tokio::test]
+    async fn keeps_balance_updates_of_the_same_challenge() {
+        let initial = proof(1, 100, 0);
+        let source = source(&initial);
+
+        assert!(!source.apply(proof(1, 100, 42), ProofOrigin::Rpc).await);
+        assert_eq!(source.proof.lock().await.balance, 42);
+    }
+
+    #[tokio::test]
+    async fn drops_outdated_proofs() {
+        let initial = proof(1, 100, 0);
+        let source = source(&initial);
+
+        assert!(source.apply(proof(2, 160, 0), ProofOrigin::Websocket(0)).await);
+        // a lagging endpoint still delivering the previous challenge
+        assert!(!source.apply(proof(1, 100, 0), ProofOrigin::Websocket(1)).await);
+        assert_eq!(source.proof.lock().await.challenge, [2; 32]);
+    }
+
+    #[tokio::test]
+    async fn wakes_waiters_on_a_new_challenge() {
+        let initial = proof(1, 100, 0);
+        let source = Arc::new(source(&initial));
+
+        let waiter = {
+            let source = source.clone();
+            tokio::spawn(async move {
+                source.wait_for_new_challenge([1; 32], Duration::from_secs(5)).await
+            })
+        };
+        while !source.has_waiters() {
+            tokio::task::yield_now().await;
+        }
+        source.apply(proof(2, 160, 0), ProofOrigin::Rpc).await;
+
+        let new_proof = waiter.await.unwrap().unwrap();
+        assert_eq!(new_proof.challenge, [2; 32]);
+        assert!(source.wait_for_new_challenge([2; 32], Duration::from_millis(10)).await.is_none());
+    }
+}
@@ -0,0 +1,300 @@
+use {
+    crate::AppState,
+    axum::{
+        body::Body,
+        extract::{ConnectInfo, Request, State},
+        http::{header, HeaderValue, Response, StatusCode},
+        middleware::Next,
+        response::IntoResponse,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        net::{IpAddr, SocketAddr},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tokio::sync::RwLock,
+    tracing::warn,
+};
+
+const WS_PATH: &str = "/v1/ws";
+const API_KEY_HEADER: &str = "x-api-key";
+// MI: drop idle buckets once the table grows past this many keys
+const MAX_BUCKETS: usize = 10_000;
+// MI: a reserved connection slot the websocket upgrade never took is freed after this long
+const PENDING_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket per key, refilled continuously at `per_minute` tokens a minute up to `burst`.
+/// A `per_minute` of 0 disables the limiter.
+pub struct RateLimiter {
+    burst: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        RateLimiter {
+            burst: burst.max(1) as f64,
+            refill_per_sec: per_minute as f64 / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes one token for `key`, otherwise returns how long until the next token is available.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        if self.refill_per_sec == 0.0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_BUCKETS {
+            let (burst, refill_per_sec) = (self.burst, self.refill_per_sec);
+            buckets.retain(|_, bucket| {
+                bucket.tokens
+                    + now.duration_since(bucket.refilled_at).as_secs_f64() * refill_per_sec
+                    < burst
+            });
+        }
+
+        let bucket = buckets
+            .entry(key.to_string())
+            .or_insert(TokenBucket { tokens: self.burst, refilled_at: now });
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.burst);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec))
+        }
+    }
+}
+
+pub struct RateLimitConfig {
+    pub http_per_minute: u32,
+    pub ws_per_minute: u32,
+    pub api_key_per_minute: u32,
+    pub max_connections_per_ip: usize,
+    pub max_connections_per_pubkey: usize,
+    pub api_keys: HashSet<String>,
+}
+
+pub struct RateLimits {
+    config: RateLimitConfig,
+    http_by_ip: RateLimiter,
+    http_by_pubkey: RateLimiter,
+    ws_by_ip: RateLimiter,
+    ws_by_pubkey: RateLimiter,
+    http_by_api_key: RateLimiter,
+}
+
+impl RateLimits {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimits {
+            http_by_ip: RateLimiter::new(config.http_per_minute, config.http_per_minute),
+            http_by_pubkey: RateLimiter::new(config.http_per_minute, config.http_per_minute),
+            ws_by_ip: RateLimiter::new(config.ws_per_minute, config.ws_per_minute),
+            ws_by_pubkey: RateLimiter::new(config.ws_per_minute, config.ws_per_minute),
+            http_by_api_key: RateLimiter::new(config.api_key_per_minute, config.api_key_per_minute),
+            config,
+        }
+    }
+
+    /// Takes a token from the per pubkey limit of http requests. Only for requests `pubkey` signed,
+    /// anyone may put any pubkey in an unauthenticated one.
+    pub fn check_http_pubkey(&self, pubkey: &Pubkey) -> Result<(), (StatusCode, &'static str)> {
+        check_pubkey(&self.http_by_pubkey, pubkey)
+    }
+
+    /// Same as [`RateLimits::check_http_pubkey`] for websocket upgrades.
+    pub fn check_ws_pubkey(&self, pubkey: &Pubkey) -> Result<(), (StatusCode, &'static str)> {
+        check_pubkey(&self.ws_by_pubkey, pubkey)
+    }
+
+    /// Checks the per IP and per pubkey caps on concurrent websocket connections, counting the
+    /// connections being upgraded as well.
+    fn check_connection_caps(
+        &self,
+        app_state: &AppState,
+        ip: IpAddr,
+        pubkey: &Pubkey,
+    ) -> Result<(), &'static str> {
+        let connections = app_state
+            .sockets
+            .iter()
+            .map(|(addr, client_connection)| (addr, &client_connection.pubkey))
+            .chain(
+                app_state.pending_connections.iter().map(|(addr, (pending, _))| (addr, pending)),
+            );
+        let (mut by_ip, mut by_pubkey) = (0, 0);
+        for (addr, connection_pubkey) in connections {
+            if addr.ip() == ip {
+                by_ip += 1;
+            }
+            if connection_pubkey.eq(pubkey) {
+                by_pubkey += 1;
+            }
+        }
+
+        if self.config.max_connections_per_ip > 0 && by_ip >= self.config.max_connections_per_ip {
+            return Err("Too many connections from this IP");
+        }
+        if self.config.max_connections_per_pubkey > 0
+            && by_pubkey >= self.config.max_connections_per_pubkey
+        {
+            return Err("Too many connections for this pubkey");
+        }
+
+        Ok(())
+    }
+}
+
+fn check_pubkey(limiter: &RateLimiter, pubkey: &Pubkey) -> Result<(), (StatusCode, &'static str)> {
+    limiter.check(&pubkey.to_string()).map_err(|_| {
+        warn!(target: "server_log", "Rate limited pubkey {}", pubkey);
+        (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded for pubkey")
+    })
+}
+
+fn too_many_requests(retry_after: Duration, message: &str) -> Response<Body> {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, message.to_string()).into_response();
+    let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    if let Ok(value) = HeaderValue::from_str(&secs.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+pub async fn rate_limit_middleware(
+    State(limits): State<Arc<RateLimits>>,
+    request: Request,
+    next: Next,
+) -> Response<Body> {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_default();
+    let is_ws = request.uri().path() == WS_PATH;
+
+    // trusted dashboards get their own, higher, limit instead of the per IP/pubkey ones
+    if !is_ws && !limits.config.api_keys.is_empty() {
+        if let Some(api_key) = request.headers().get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+            if limits.config.api_keys.contains(api_key) {
+                if let Err(retry_after) = limits.http_by_api_key.check(api_key) {
+                    return too_many_requests(retry_after, "Rate limit exceeded for api key");
+                }
+                return next.run(request).await;
+            } else {
+                return (StatusCode::UNAUTHORIZED, "Invalid api key").into_response();
+            }
+        }
+    }
+
+    // MI: requests are not authenticated yet, the per pubkey limits are taken once they are
+    let by_ip = if is_ws { &limits.ws_by_ip } else { &limits.http_by_ip };
+    if let Err(retry_after) = by_ip.check(&ip) {
+        warn!(target: "server_log", "Rate limited {} on {}", ip, request.uri().path());
+        return too_many_requests(retry_after, "Rate limit exceeded");
+    }
+
+    next.run(request).await
+}
+
+/// Reserves a connection slot for `pubkey` from `addr` within the connection caps. Checked and
+/// reserved under one write lock, so concurrent upgrades can't all pass the same count. The slot
+/// turns into the socket once the upgrade completes, see `handle_socket`.
+pub async fn reserve_connection(
+    limits: &RateLimits,
+    app_state: &Arc<RwLock<AppState>>,
+    addr: SocketAddr,
+    pubkey: Pubkey,
+) -> Result<(), (StatusCode, &'static str)> {
+    let now = Instant::now();
+    let mut writer = app_state.write().await;
+    writer.pending_connections.retain(|_, (_, reserved_at)| {
+        now.duration_since(*reserved_at) < PENDING_CONNECTION_TIMEOUT
+    });
+    if let Err(msg) = limits.check_connection_caps(&writer, addr.ip(), &pubkey) {
+        drop(writer);
+        warn!(target: "server_log", "Refused connection from {} for {}: {}", addr, pubkey, msg);
+        return Err((StatusCode::TOO_MANY_REQUESTS, msg));
+    }
+    writer.pending_connections.insert(addr, (pubkey, now));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_limiter_never_limits() {
+        let limiter = RateLimiter::new(0, 0);
+        for _ in 0..1000 {
+            assert!(limiter.check("1.2.3.4").is_ok());
+        }
+    }
+
+    #[test]
+    fn limits_each_key_to_its_burst() {
+        let limiter = RateLimiter::new(60, 3);
+        for _ in 0..3 {
+            assert!(limiter.check("a").is_ok());
+        }
+        let retry_after = limiter.check("a").unwrap_err();
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+        assert!(limiter.check("b").is_ok());
+    }
+
+    #[test]
+    fn refills_over_time() {
+        // 6000 a minute is a token every 10ms
+        let limiter = RateLimiter::new(6000, 1);
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_err());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(limiter.check("a").is_ok());
+    }
+
+    #[tokio::test]
+    async fn reserves_connection_slots_within_the_caps() {
+        let limits = RateLimits::new(RateLimitConfig {
+            http_per_minute: 0,
+            ws_per_minute: 0,
+            api_key_per_minute: 0,
+            max_connections_per_ip: 2,
+            max_connections_per_pubkey: 1,
+            api_keys: HashSet::new(),
+        });
+        let app_state = Arc::new(RwLock::new(AppState {
+            sockets: HashMap::new(),
+            pending_connections: HashMap::new(),
+        }));
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let addr = |port| SocketAddr::from(([1, 2, 3, 4], port));
+
+        assert!(reserve_connection(&limits, &app_state, addr(1), a).await.is_ok());
+        // the upgrade of the first one is still in progress
+        assert!(reserve_connection(&limits, &app_state, addr(2), a).await.is_err());
+        assert!(reserve_connection(&limits, &app_state, addr(2), b).await.is_ok());
+        assert!(reserve_connection(&limits, &app_state, addr(3), Pubkey::new_unique())
+            .await
+            .is_err());
+
+        app_state.write().await.pending_connections.clear();
+        assert!(reserve_connection(&limits, &app_state, addr(1), a).await.is_ok());
+    }
+}
@@ -0,0 +1,431 @@
+use {
+    crate::{
+        database::{Database, DatabaseError, PoweredByDbms},
+        notification::{self, Notification},
+        utils::ORE_TOKEN_DECIMALS,
+        POWERED_BY_DBMS,
+    },
+    chrono::Local,
+    clap::ValueEnum,
+    serde::Serialize,
+    solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey},
+    std::{
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+    tokio::sync::mpsc::UnboundedSender,
+    tracing::{error, info},
+};
+
+// MI: lamports per signature
+pub(crate) const BASE_FEE_LAMPORTS: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportOutput {
+    Log,
+    Json,
+    Csv,
+    Messaging,
+}
+
+pub struct ReportsConfig {
+    pub interval_in_hrs: u64,
+    pub windows: Vec<u64>,
+    pub outputs: Vec<ReportOutput>,
+    pub dir: PathBuf,
+}
+
+impl ReportsConfig {
+    pub fn from_env() -> Result<Self, String> {
+        ReportsConfig::parse(
+            std::env::var("REPORTS_INTERVAL_IN_HOURS").ok().as_deref(),
+            std::env::var("REPORTS_WINDOWS").ok().as_deref(),
+            std::env::var("REPORTS_OUTPUTS").ok().as_deref(),
+            std::env::var("REPORTS_DIR").ok(),
+        )
+    }
+
+    fn parse(
+        interval_in_hrs: Option<&str>,
+        windows: Option<&str>,
+        outputs: Option<&str>,
+        dir: Option<String>,
+    ) -> Result<Self, String> {
+        let interval_in_hrs: u64 = match interval_in_hrs {
+            Some(val) => val
+                .trim()
+                .parse()
+                .map_err(|_| "REPORTS_INTERVAL_IN_HOURS must be a positive number".to_string())?,
+            None => 6,
+        };
+        let windows = match windows {
+            Some(val) => val
+                .split(',')
+                .map(|window| {
+                    window.trim().parse::<u64>().ok().filter(|hours| *hours > 0).ok_or_else(|| {
+                        "REPORTS_WINDOWS must be comma separated positive hours".to_string()
+                    })
+                })
+                .collect::<Result<Vec<u64>, String>>()?,
+            None => vec![24],
+        };
+        let outputs = match outputs {
+            Some(val) => val
+                .split(',')
+                .map(|output| {
+                    ReportOutput::from_str(output.trim(), true).map_err(|_| {
+                        "REPORTS_OUTPUTS must be comma separated log, json, csv or messaging"
+                            .to_string()
+                    })
+                })
+                .collect::<Result<Vec<ReportOutput>, String>>()?,
+            None => vec![ReportOutput::Log],
+        };
+        let dir = dir.unwrap_or_else(|| "./reports".to_string());
+
+        Ok(ReportsConfig {
+            interval_in_hrs: interval_in_hrs.max(1),
+            windows,
+            outputs,
+            dir: dir.into(),
+        })
+    }
+}
+
+/// 1h, 24h, 7d...
+pub fn window_label(window_hours: u64) -> String {
+    let days = window_hours / 24;
+    if days >= 2 && days * 24 == window_hours {
+        format!("{}d", days)
+    } else {
+        format!("{}h", window_hours)
+    }
+}
+
+fn ore(grains: i64) -> f64 {
+    grains as f64 / 10f64.powf(ORE_TOKEN_DECIMALS as f64)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolReport {
+    /// Authority of the reported pool
+    pub pool: String,
+    pub window_hours: u64,
+    pub num_challenges: i64,
+    pub num_solved: i64,
+    /// ORE, commissions included
+    pub rewards: f64,
+    /// ORE
+    pub commissions: f64,
+    pub num_miners: i64,
+    pub num_mine_transactions: i64,
+    /// SOL, base fees, priority fees and tips of every submission attempt that landed, with or
+    /// without an error
+    pub fees_spent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MinerReport {
+    pub pubkey: String,
+    pub num_contributions: i32,
+    pub min_diff: i16,
+    pub avg_diff: f64,
+    pub max_diff: i16,
+    /// ORE
+    pub earned: f64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub generated_at: String,
+    pub pool: PoolReport,
+    pub miners: Vec<MinerReport>,
+}
+
+/// Lamports spent by the attempts that paid fees, the priority fees and tips already summed up
+/// per attempt from its own compute unit price, limit and tip.
+fn fees_spent(num_paid_attempts: i64, attempt_fees: i64) -> u64 {
+    num_paid_attempts.max(0) as u64 * BASE_FEE_LAMPORTS + attempt_fees.max(0) as u64
+}
+
+pub async fn build_report(
+    database: &Database,
+    pool_authority: &Pubkey,
+    pool_id: i32,
+    commissions_miner_id: i64,
+    window_hours: u64,
+) -> Result<Report, DatabaseError> {
+    let hours = window_hours as i32;
+    let totals = database.get_pool_report_totals(pool_id, commissions_miner_id, hours).await?;
+    let summaries = database.get_miner_summaries(pool_id, hours).await?;
+
+    let fees_spent = fees_spent(totals.num_paid_attempts, totals.attempt_fees);
+
+    Ok(Report {
+        generated_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        pool: PoolReport {
+            pool: pool_authority.to_string(),
+            window_hours,
+            num_challenges: totals.num_challenges,
+            num_solved: totals.num_solved,
+            rewards: ore(totals.rewards),
+            commissions: ore(totals.commissions),
+            num_miners: totals.num_miners,
+            num_mine_transactions: totals.num_mine_transactions,
+            fees_spent: lamports_to_sol(fees_spent),
+        },
+        miners: summaries
+            .into_iter()
+            .map(|summary| MinerReport {
+                pubkey: summary.miner_pubkey,
+                num_contributions: summary.num_of_contributions,
+                min_diff: summary.min_diff,
+                avg_diff: summary.avg_diff,
+                max_diff: summary.max_diff,
+                earned: ore(summary.earning_sub_total),
+                percent: summary.percent,
+            })
+            .collect(),
+    })
+}
+
+pub fn log_report(report: &Report) {
+    let pool = &report.pool;
+    let label = window_label(pool.window_hours);
+    info!(target: "server_log",
+        "[{}] Pool {} report for last {}: challenges solved {}/{}, rewards {:.11} ORE, commissions {:.11} ORE, miners {}, mine txns {}, fees spent ~{:.9} SOL",
+        report.generated_at,
+        pool.pool,
+        label,
+        pool.num_solved,
+        pool.num_challenges,
+        pool.rewards,
+        pool.commissions,
+        pool.num_miners,
+        pool.num_mine_transactions,
+        pool.fees_spent,
+    );
+
+    info!(target: "server_log", "Miner summaries of pool {} for last {}:", pool.pool, label);
+    info!(target: "server_log", "miner_pubkey     num_contributions   min_diff   avg_diff   max_diff   earning_sub_total   percent");
+    for miner in report.miners.iter() {
+        let mp = &miner.pubkey;
+        let len = mp.len();
+        let short_mp = format!("{}...{}", &mp[0..6], &mp[len - 4..len]);
+        info!(target: "server_log",
+            "{}    {:17}   {:8}   {:8}   {:8}       {:.11}   {:>6.2}%",
+            short_mp,
+            miner.num_contributions,
+            miner.min_diff,
+            miner.avg_diff,
+            miner.max_diff,
+            miner.earned,
+            miner.percent
+        );
+    }
+}
+
+fn report_path(dir: &Path, report: &Report, suffix: &str) -> PathBuf {
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+    dir.join(format!(
+        "report-{}-{}-{}{}",
+        report.pool.pool,
+        window_label(report.pool.window_hours),
+        timestamp,
+        suffix
+    ))
+}
+
+pub fn write_json(dir: &Path, report: &Report) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = report_path(dir, report, ".json");
+    fs::write(&path, serde_json::to_vec_pretty(report)?)?;
+    Ok(path)
+}
+
+/// Pool totals and miner summaries don't share columns, so they go to two files.
+pub fn write_csv(dir: &Path, report: &Report) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let pool = &report.pool;
+
+    let pool_path = report_path(dir, report, "-pool.csv");
+    let mut file = fs::File::create(&pool_path)?;
+    writeln!(
+        file,
+        "generated_at,pool,window_hours,num_challenges,num_solved,rewards,commissions,num_miners,num_mine_transactions,fees_spent"
+    )?;
+    writeln!(
+        file,
+        "{},{},{},{},{},{:.11},{:.11},{},{},{:.9}",
+        report.generated_at,
+        pool.pool,
+        pool.window_hours,
+        pool.num_challenges,
+        pool.num_solved,
+        pool.rewards,
+        pool.commissions,
+        pool.num_miners,
+        pool.num_mine_transactions,
+        pool.fees_spent
+    )?;
+
+    let miners_path = report_path(dir, report, "-miners.csv");
+    let mut file = fs::File::create(&miners_path)?;
+    writeln!(file, "pubkey,num_contributions,min_diff,avg_diff,max_diff,earned,percent")?;
+    for miner in report.miners.iter() {
+        writeln!(
+            file,
+            "{},{},{},{},{},{:.11},{:.2}",
+            miner.pubkey,
+            miner.num_contributions,
+            miner.min_diff,
+            miner.avg_diff,
+            miner.max_diff,
+            miner.earned,
+            miner.percent
+        )?;
+    }
+
+    Ok(vec![pool_path, miners_path])
+}
+
+/// Sends the report to every output, a failing output doesn't stop the others.
+pub fn publish_report(
+    report: &Report,
+    outputs: &[ReportOutput],
+    dir: &Path,
+    notification_sender: &UnboundedSender<Notification>,
+) {
+    for output in outputs {
+        match output {
+            ReportOutput::Log => log_report(report),
+            ReportOutput::Json => match write_json(dir, report) {
+                Ok(path) => info!(target: "server_log", "Report written to {}", path.display()),
+                Err(e) => error!(target: "server_log", "Failed to write json report: {}", e),
+            },
+            ReportOutput::Csv => match write_csv(dir, report) {
+                Ok(paths) => {
+                    for path in paths {
+                        info!(target: "server_log", "Report written to {}", path.display());
+                    }
+                },
+                Err(e) => error!(target: "server_log", "Failed to write csv report: {}", e),
+            },
+            ReportOutput::Messaging => {
+                let _ = notification_sender.send(Notification::Report(report.pool.clone()));
+            },
+        }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ReportArgs {
+    #[arg(
+        long,
+        value_name = "HOURS",
+        help = "Report window in hours, e.g. 1, 24 or 168.",
+        default_value = "24"
+    )]
+    window: u64,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "OUTPUT",
+        help = "Where to send the report, may be repeated.",
+        default_value = "log"
+    )]
+    output: Vec<ReportOutput>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory of the json and csv reports, defaults to REPORTS_DIR or ./reports."
+    )]
+    dir: Option<PathBuf>,
+}
+
+/// `hps report`, builds one report per pool from the database and exits.
+pub async fn report_command(
+    args: &ReportArgs,
+    database: &Database,
+    pool_authorities: &[Pubkey],
+    commissions_pubkey: Pubkey,
+    default_min_difficulty: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let powered_by_dbms = POWERED_BY_DBMS.get().unwrap_or(&PoweredByDbms::Unavailable);
+    if powered_by_dbms == &PoweredByDbms::Unavailable {
+        return Err("Reports require POWERED_BY_DBMS enabled.".into());
+    }
+    if args.window == 0 {
+        return Err("The report window must be at least 1 hour.".into());
+    }
+
+    // MI: without a commissions receiver in db yet, no earnings are commissions
+    let commissions_miner_id = database
+        .get_miner_by_pubkey_str(commissions_pubkey.to_string())
+        .await
+        .map(|miner| miner.id)
+        .unwrap_or(i64::MAX);
+    let dir = match args.dir.clone() {
+        Some(dir) => dir,
+        None => ReportsConfig::from_env()?.dir,
+    };
+
+    let (notification_sender, notification_receiver) = tokio::sync::mpsc::unbounded_channel();
+    for pool_authority in pool_authorities {
+        let pool = database
+            .get_pool_by_authority_pubkey(pool_authority.to_string())
+            .await
+            .map_err(|e| format!("Failed to load the mining pool {pool_authority}: {e:?}"))?;
+
+        let report =
+            build_report(database, pool_authority, pool.id, commissions_miner_id, args.window)
+                .await
+                .map_err(|e| format!("Failed to build report: {e:?}"))?;
+        publish_report(&report, &args.output, &dir, &notification_sender);
+    }
+    drop(notification_sender);
+
+    if args.output.contains(&ReportOutput::Messaging) {
+        let channels = notification::load_notification_channels(default_min_difficulty);
+        notification::notification_processor(channels, notification_receiver).await;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_reports_config() {
+        let config = ReportsConfig::parse(None, None, None, None).unwrap();
+        assert_eq!(config.interval_in_hrs, 6);
+        assert_eq!(config.windows, vec![24]);
+        assert_eq!(config.outputs, vec![ReportOutput::Log]);
+
+        let config =
+            ReportsConfig::parse(Some("0"), Some("1, 24,168"), Some("json,CSV"), None).unwrap();
+        assert_eq!(config.interval_in_hrs, 1);
+        assert_eq!(config.windows, vec![1, 24, 168]);
+        assert_eq!(config.outputs, vec![ReportOutput::Json, ReportOutput::Csv]);
+    }
+
+    #[test]
+    fn rejects_invalid_reports_config() {
+        assert!(ReportsConfig::parse(Some("-1"), None, None, None).is_err());
+        assert!(ReportsConfig::parse(None, Some("24,0"), None, None).is_err());
+        assert!(ReportsConfig::parse(None, Some("day"), None, None).is_err());
+        assert!(ReportsConfig::parse(None, None, Some("log,pdf"), None).is_err());
+    }
+
+    #[test]
+    fn adds_base_fees_to_the_attempt_fees() {
+        assert_eq!(fees_spent(0, 0), 0);
+        assert_eq!(fees_spent(3, 12_345), 3 * BASE_FEE_LAMPORTS + 12_345);
+    }
+}
@@ -1,27 +1,44 @@
 use {
     crate::{
-        models, rr_database,
+        events::PoolEvent,
+        hashrate::{HashrateEstimator, MinerHashrate, PoolHashrate},
+        models,
+        pools::{PoolParam, Pools},
+        rr_database,
         utils::{get_mini_pool_proof, get_ore_mint},
-        ChallengeWithDifficulty, MineConfig, WALLET_PUBKEY,
+        ChallengeWithDifficulty, MineConfig, PubkeyParam,
     },
     axum::{
+        extract::Query,
         http::{Response, StatusCode},
-        response::IntoResponse,
+        response::{
+            sse::{Event, KeepAlive, Sse},
+            Html, IntoResponse,
+        },
         Extension, Json,
     },
+    futures::Stream,
     rr_database::RrDatabase,
     solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::pubkey::Pubkey,
     spl_associated_token_account::get_associated_token_address,
-    std::sync::Arc,
+    std::{convert::Infallible, str::FromStr, sync::Arc},
+    tokio::sync::{
+        broadcast::{self, error::RecvError},
+        RwLock,
+    },
     tracing::error,
 };
 
 pub async fn get_challenges(
+    query_params: Query<PoolParam>,
     Extension(rr_database): Extension<Arc<RrDatabase>>,
     Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(pools): Extension<Arc<Pools>>,
 ) -> Result<Json<Vec<ChallengeWithDifficulty>>, String> {
     if mine_config.stats_enabled {
-        let res = rr_database.get_challenges().await;
+        let pool = pools.select(query_params.pool.as_deref())?;
+        let res = rr_database.get_challenges(pool.mine_config.pool_id).await;
 
         match res {
             Ok(challenges) => Ok(Json(challenges)),
@@ -33,11 +50,14 @@ pub async fn get_challenges(
 }
 
 pub async fn get_latest_mine_transaction(
+    query_params: Query<PoolParam>,
     Extension(rr_database): Extension<Arc<RrDatabase>>,
     Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(pools): Extension<Arc<Pools>>,
 ) -> Result<Json<models::Transaction>, String> {
     if mine_config.stats_enabled {
-        let res = rr_database.get_latest_mine_transaction().await;
+        let pool = pools.select(query_params.pool.as_deref())?;
+        let res = rr_database.get_latest_mine_transaction(pool.mine_config.pool_id).await;
 
         match res {
             Ok(txn) => Ok(Json(txn)),
@@ -49,11 +69,13 @@ pub async fn get_latest_mine_transaction(
 }
 
 pub async fn get_pool(
+    query_params: Query<PoolParam>,
     Extension(rr_database): Extension<Arc<RrDatabase>>,
     Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(pools): Extension<Arc<Pools>>,
 ) -> Result<Json<crate::models::Pool>, String> {
     if mine_config.stats_enabled {
-        let pubkey = *WALLET_PUBKEY.get().unwrap();
+        let pubkey = pools.select(query_params.pool.as_deref())?.authority;
         let res = rr_database.get_pool_by_authority_pubkey(pubkey.to_string()).await;
 
         match res {
@@ -66,11 +88,16 @@ pub async fn get_pool(
 }
 
 pub async fn get_pool_staked(
+    query_params: Query<PoolParam>,
     Extension(mine_config): Extension<Arc<MineConfig>>,
     Extension(rpc_client): Extension<Arc<RpcClient>>,
+    Extension(pools): Extension<Arc<Pools>>,
 ) -> impl IntoResponse {
     if mine_config.stats_enabled {
-        let pubkey = *WALLET_PUBKEY.get().unwrap();
+        let pubkey = match pools.select(query_params.pool.as_deref()) {
+            Ok(pool) => pool.authority,
+            Err(e) => return Err(e),
+        };
         let proof = if let Ok(loaded_proof) = get_mini_pool_proof(&rpc_client, pubkey).await {
             loaded_proof
         } else {
@@ -85,11 +112,16 @@ pub async fn get_pool_staked(
 }
 
 pub async fn get_pool_balance(
+    query_params: Query<PoolParam>,
     Extension(mine_config): Extension<Arc<MineConfig>>,
     Extension(rpc_client): Extension<Arc<RpcClient>>,
+    Extension(pools): Extension<Arc<Pools>>,
 ) -> impl IntoResponse {
     if mine_config.stats_enabled {
-        let pubkey = *WALLET_PUBKEY.get().unwrap();
+        let pubkey = match pools.select(query_params.pool.as_deref()) {
+            Ok(pool) => pool.authority,
+            Err(e) => return Response::builder().status(StatusCode::BAD_REQUEST).body(e).unwrap(),
+        };
         let miner_token_account = get_associated_token_address(&pubkey, &get_ore_mint());
         if let Ok(response) = rpc_client.get_token_account_balance(&miner_token_account).await {
             return Response::builder()
@@ -109,3 +141,175 @@ pub async fn get_pool_balance(
             .unwrap();
     }
 }
+
+pub async fn get_pool_hashrate(
+    Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(hashrate_estimator): Extension<Arc<RwLock<HashrateEstimator>>>,
+) -> Result<Json<PoolHashrate>, String> {
+    if mine_config.stats_enabled {
+        let pool_hashrate = hashrate_estimator.read().await.pool_hashrate();
+        Ok(Json(pool_hashrate))
+    } else {
+        Err("Stats not enabled for this server.".to_string())
+    }
+}
+
+pub async fn get_miner_hashrate(
+    query_params: Query<PubkeyParam>,
+    Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(hashrate_estimator): Extension<Arc<RwLock<HashrateEstimator>>>,
+) -> Result<Json<MinerHashrate>, String> {
+    if mine_config.stats_enabled {
+        if let Ok(user_pubkey) = Pubkey::from_str(&query_params.pubkey) {
+            let miner_hashrate = hashrate_estimator.read().await.miner_hashrate(&user_pubkey);
+            Ok(Json(miner_hashrate))
+        } else {
+            Err("Invalid public key".to_string())
+        }
+    } else {
+        Err("Stats not enabled for this server.".to_string())
+    }
+}
+
+/// Solves the contributed hashes are expected to give at the network difficulty, each hash
+/// reaches difficulty d with probability 2^-d.
+fn expected_solves(contributed_hashes: f64, difficulty: u32) -> f64 {
+    contributed_hashes / 2f64.powi(difficulty as i32)
+}
+
+/// Actual solves over expected solves, in percent. 100% means the pool solved exactly what
+/// its hashes predict, above 100% it solved more.
+fn luck(expected_solves: f64, actual_solves: i64) -> f64 {
+    if expected_solves > 0.0 {
+        actual_solves as f64 / expected_solves * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Rewards of the 24h window per hour of it the data covers. The oldest challenge stands for
+/// the minute of mining before it.
+fn rewards_per_hour(rewards: i64, covered_secs: f64) -> i64 {
+    let covered_hours = ((covered_secs + 60.0) / 3_600.0).min(24.0);
+    (rewards as f64 / covered_hours) as i64
+}
+
+pub async fn get_pool_stats(
+    query_params: Query<PoolParam>,
+    Extension(rr_database): Extension<Arc<RrDatabase>>,
+    Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(pools): Extension<Arc<Pools>>,
+) -> Result<Json<models::PoolStats>, String> {
+    if !mine_config.stats_enabled {
+        return Err("Stats not enabled for this server.".to_string());
+    }
+
+    let pool_id = pools.select(query_params.pool.as_deref())?.mine_config.pool_id;
+    let challenges_per_hour = rr_database
+        .get_solved_challenges_per_hour(pool_id)
+        .await
+        .map_err(|_| "Failed to get solved challenges per hour".to_string())?;
+    let challenges_per_day = rr_database
+        .get_solved_challenges_per_day(pool_id)
+        .await
+        .map_err(|_| "Failed to get solved challenges per day".to_string())?;
+    let difficulty_histogram = rr_database
+        .get_best_difficulty_histogram(pool_id)
+        .await
+        .map_err(|_| "Failed to get best difficulty histogram".to_string())?;
+    let summary = rr_database
+        .get_pool_stats_summary(pool_id, mine_config.luck_difficulty)
+        .await
+        .map_err(|_| "Failed to get pool stats summary".to_string())?;
+
+    let submission_success_rate = if summary.num_challenges > 0 {
+        summary.num_mine_transactions as f64 / summary.num_challenges as f64
+    } else {
+        0.0
+    };
+    let average_reward_per_challenge =
+        if summary.num_solved > 0 { summary.rewards / summary.num_solved } else { 0 };
+
+    let expected_solves = expected_solves(summary.contributed_hashes, mine_config.luck_difficulty);
+    let luck = luck(expected_solves, summary.num_solves);
+
+    Ok(Json(models::PoolStats {
+        challenges_per_hour,
+        challenges_per_day,
+        difficulty_histogram,
+        num_challenges_24h: summary.num_challenges,
+        num_solved_24h: summary.num_solved,
+        submission_success_rate,
+        average_reward_per_challenge,
+        rewards_per_hour: rewards_per_hour(summary.rewards, summary.covered_secs),
+        rewards_per_day: summary.rewards,
+        average_best_difficulty: summary.avg_best_difficulty,
+        luck_difficulty: mine_config.luck_difficulty,
+        expected_solves,
+        actual_solves: summary.num_solves,
+        luck,
+    }))
+}
+
+pub async fn get_events(
+    Extension(mine_config): Extension<Arc<MineConfig>>,
+    Extension(event_sender): Extension<broadcast::Sender<PoolEvent>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, String> {
+    if !mine_config.stats_enabled {
+        return Err("Stats not enabled for this server.".to_string());
+    }
+
+    let receiver = event_sender.subscribe();
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((Ok(event.to_sse()), receiver)),
+                // a slow subscriber only misses the events it lagged behind on
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+// MI: single self-contained page, only talks to the public stats endpoints.
+const DASHBOARD_HTML: &str = include_str!("../assets/dashboard/index.html");
+
+pub async fn get_dashboard(
+    Extension(mine_config): Extension<Arc<MineConfig>>,
+) -> Result<Html<&'static str>, (StatusCode, String)> {
+    if mine_config.dashboard_enabled && mine_config.stats_enabled {
+        Ok(Html(DASHBOARD_HTML))
+    } else {
+        Err((StatusCode::NOT_FOUND, "Dashboard not enabled for this server.".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_solves_follow_the_network_difficulty() {
+        assert_eq!(expected_solves(2f64.powi(30), 24), 64.0);
+        assert_eq!(expected_solves(0.0, 24), 0.0);
+    }
+
+    #[test]
+    fn luck_compares_actual_and_expected_solves() {
+        assert_eq!(luck(64.0, 64), 100.0);
+        assert_eq!(luck(32.0, 64), 200.0);
+        assert_eq!(luck(128.0, 64), 50.0);
+        assert_eq!(luck(10.0, 0), 0.0);
+        assert_eq!(luck(0.0, 3), 0.0);
+    }
+
+    #[test]
+    fn rewards_per_hour_of_the_covered_window() {
+        assert_eq!(rewards_per_hour(2_400, 86_400.0), 100);
+        assert_eq!(rewards_per_hour(2_400, 2.0 * 3_600.0 - 60.0), 1_200);
+        assert_eq!(rewards_per_hour(0, 0.0), 0);
+    }
+}
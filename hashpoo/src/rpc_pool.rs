@@ -0,0 +1,438 @@
+use {
+    crate::{metrics::write_header, tpu},
+    async_trait::async_trait,
+    futures::future::join_all,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+        rpc_config::RpcSendTransactionConfig,
+        rpc_request::{RpcError, RpcRequest},
+    },
+    solana_rpc_client::{
+        http_sender::HttpSender,
+        nonblocking::rpc_client::RpcClient,
+        rpc_sender::{RpcSender, RpcTransportStats},
+    },
+    solana_sdk::{
+        commitment_config::CommitmentConfig, signature::Signature, transaction::Transaction,
+    },
+    std::{
+        fmt::Write,
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    },
+    tokio::time::Instant,
+    tracing::{info, warn},
+};
+
+// MI: json-rpc server error of a node that is behind or otherwise unhealthy
+const NODE_UNHEALTHY: i64 = -32005;
+
+const RPC_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+// MI: consecutive transport failures before an endpoint is taken out, and for how long
+const FAILURES_TO_DOWN: u32 = 3;
+const DOWN_COOLDOWN: Duration = Duration::from_secs(30);
+const MAX_SLOT_LAG: u64 = 50;
+const LATENCY_EWMA_WEIGHT: f64 = 0.2;
+// MI: racing resends to all endpoints at this pace until the blockhash is about to expire
+const RACE_RESEND_INTERVAL: Duration = Duration::from_secs(2);
+const RACE_STATUS_INTERVAL: Duration = Duration::from_millis(500);
+const RACE_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// RPC_URL may list several comma separated endpoints, the first one is preferred.
+pub fn rpc_urls_from_env() -> Vec<String> {
+    let mut rpc_urls: Vec<String> = Vec::new();
+    if let Ok(val) = std::env::var("RPC_URL") {
+        for url in val.split(',').map(|url| url.trim()).filter(|url| !url.is_empty()) {
+            if !rpc_urls.iter().any(|existing| existing == url) {
+                rpc_urls.push(url.to_string());
+            }
+        }
+    }
+    rpc_urls
+}
+
+#[derive(Default)]
+struct EndpointHealth {
+    latency_ms: f64,
+    consecutive_failures: u32,
+    failures: u64,
+    slot: u64,
+    down_until: Option<Instant>,
+}
+
+pub struct RpcEndpoint {
+    /// Host only, the full url may carry an api key.
+    pub label: String,
+    url: String,
+    /// Plain client of this endpoint alone, for probes and racing.
+    pub client: Arc<RpcClient>,
+    health: Mutex<EndpointHealth>,
+}
+
+impl RpcEndpoint {
+    fn new(index: usize, url: &str) -> Self {
+        let label = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_else(|| format!("rpc-{}", index));
+        RpcEndpoint {
+            label,
+            url: url.to_string(),
+            client: Arc::new(RpcClient::new_with_commitment(
+                url.to_string(),
+                CommitmentConfig::confirmed(),
+            )),
+            health: Mutex::new(EndpointHealth::default()),
+        }
+    }
+
+    pub fn record_success(&self, elapsed: Duration) {
+        let mut health = self.health.lock().unwrap();
+        let latency_ms = elapsed.as_secs_f64() * 1000.0;
+        health.latency_ms = if health.latency_ms == 0.0 {
+            latency_ms
+        } else {
+            health.latency_ms * (1.0 - LATENCY_EWMA_WEIGHT) + latency_ms * LATENCY_EWMA_WEIGHT
+        };
+        health.consecutive_failures = 0;
+        health.down_until = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut health = self.health.lock().unwrap();
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= FAILURES_TO_DOWN {
+            health.down_until = Some(Instant::now() + DOWN_COOLDOWN);
+        }
+    }
+
+    fn is_up(&self) -> bool {
+        match self.health.lock().unwrap().down_until {
+            Some(down_until) => Instant::now() >= down_until,
+            None => true,
+        }
+    }
+}
+
+/// The RPC_URL endpoints with their health. Reads go to the preferred healthy endpoint and
+/// fail over to the next ones, mine transactions may be raced to all of them.
+pub struct RpcPool {
+    pub endpoints: Vec<RpcEndpoint>,
+    current: AtomicUsize,
+    max_slot: AtomicU64,
+    failovers: AtomicU64,
+}
+
+impl RpcPool {
+    pub fn new(rpc_urls: &[String]) -> Self {
+        assert!(!rpc_urls.is_empty(), "RPC_URL must be set.");
+        RpcPool {
+            endpoints: rpc_urls
+                .iter()
+                .enumerate()
+                .map(|(i, url)| RpcEndpoint::new(i, url))
+                .collect(),
+            current: AtomicUsize::new(0),
+            max_slot: AtomicU64::new(0),
+            failovers: AtomicU64::new(0),
+        }
+    }
+
+    /// Lower is better, the latency in ms plus penalties for slot lag and recent failures.
+    fn score(&self, index: usize) -> f64 {
+        let health = self.endpoints[index].health.lock().unwrap();
+        let slot_lag = self.max_slot.load(Ordering::Relaxed).saturating_sub(health.slot);
+        health.latency_ms + slot_lag as f64 * 400.0 + health.consecutive_failures as f64 * 1000.0
+    }
+
+    fn is_healthy(&self, index: usize) -> bool {
+        let endpoint = &self.endpoints[index];
+        let slot = endpoint.health.lock().unwrap().slot;
+        endpoint.is_up()
+            && self.max_slot.load(Ordering::Relaxed).saturating_sub(slot) <= MAX_SLOT_LAG
+    }
+
+    /// Endpoint indexes in the order a request tries them. The current endpoint is kept while
+    /// it is healthy and not much worse than the best one, endpoints taken out come last.
+    fn order(&self) -> Vec<usize> {
+        let current = self.current.load(Ordering::Relaxed);
+        let mut healthy: Vec<(usize, f64)> = (0..self.endpoints.len())
+            .filter(|index| self.is_healthy(*index))
+            .map(|index| (index, self.score(index)))
+            .collect();
+        healthy.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let preferred = match healthy.first() {
+            Some((best, best_score)) => match healthy.iter().find(|(index, _)| *index == current) {
+                Some((_, current_score)) if *current_score <= best_score * 2.0 + 100.0 => current,
+                _ => *best,
+            },
+            None => current,
+        };
+        if preferred != current {
+            self.switch_to(current, preferred);
+        }
+
+        let mut order = vec![preferred];
+        order.extend(healthy.iter().map(|(index, _)| *index).filter(|index| *index != preferred));
+        let down: Vec<usize> =
+            (0..self.endpoints.len()).filter(|index| !order.contains(index)).collect();
+        order.extend(down);
+        order
+    }
+
+    fn switch_to(&self, from: usize, to: usize) {
+        if self.current.compare_exchange(from, to, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+            self.failovers.fetch_add(1, Ordering::Relaxed);
+            warn!(target: "server_log",
+                "RPC failover from {} to {}",
+                self.endpoints[from].label,
+                self.endpoints[to].label
+            );
+        }
+    }
+
+    /// Probes every endpoint with getSlot, called periodically by the rpc health processor.
+    pub async fn probe(&self) {
+        let probes = self.endpoints.iter().map(|endpoint| async move {
+            let started = Instant::now();
+            match tokio::time::timeout(RPC_REQUEST_TIMEOUT, endpoint.client.get_slot()).await {
+                Ok(Ok(slot)) => {
+                    endpoint.record_success(started.elapsed());
+                    endpoint.health.lock().unwrap().slot = slot;
+                    Some(slot)
+                },
+                Ok(Err(e)) => {
+                    warn!(target: "server_log", "RPC probe of {} failed: {}", endpoint.label, e);
+                    endpoint.record_failure();
+                    None
+                },
+                Err(_) => {
+                    warn!(target: "server_log", "RPC probe of {} timed out.", endpoint.label);
+                    endpoint.record_failure();
+                    None
+                },
+            }
+        });
+        let slots = join_all(probes).await;
+        if let Some(max_slot) = slots.into_iter().flatten().max() {
+            self.max_slot.fetch_max(max_slot, Ordering::Relaxed);
+        }
+        // MI: reevaluate the preferred endpoint even when no request is going on
+        self.order();
+    }
+
+    /// Sends the signed transaction to every endpoint at once, and to the tpu when `with_tpu`,
+    /// resending until one of the endpoints sees it confirmed.
+    pub async fn race_send_and_confirm(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        transaction: &Transaction,
+        config: RpcSendTransactionConfig,
+        with_tpu: bool,
+    ) -> ClientResult<Signature> {
+        let signature = transaction.signatures[0];
+        if with_tpu {
+            let rpc_client = rpc_client.clone();
+            let transaction = transaction.clone();
+            tokio::spawn(async move {
+                if let Err(e) = tpu::send_transaction(&rpc_client, &transaction).await {
+                    warn!(target: "server_log", "Failed to race tx via tpu: {}", e);
+                }
+            });
+        }
+
+        let started = Instant::now();
+        let mut last_send: Option<Instant> = None;
+        while started.elapsed() < RACE_TIMEOUT {
+            let resend = match last_send {
+                Some(at) => at.elapsed() >= RACE_RESEND_INTERVAL,
+                None => true,
+            };
+            if resend {
+                let sends = self.endpoints.iter().map(|endpoint| {
+                    endpoint.client.send_transaction_with_config(transaction, config)
+                });
+                let results = join_all(sends).await;
+                if last_send.is_none() && results.iter().all(|res| res.is_err()) {
+                    // MI: nobody took it, hand the first error to the caller
+                    return Err(results.into_iter().find_map(|res| res.err()).unwrap());
+                }
+                last_send = Some(Instant::now());
+            }
+
+            tokio::time::sleep(RACE_STATUS_INTERVAL).await;
+            let signatures = [signature];
+            let checks = self
+                .endpoints
+                .iter()
+                .map(|endpoint| endpoint.client.get_signature_statuses(&signatures));
+            for (endpoint, res) in self.endpoints.iter().zip(join_all(checks).await) {
+                let Ok(response) = res else {
+                    continue;
+                };
+                let Some(Some(status)) = response.value.into_iter().next() else {
+                    continue;
+                };
+                if let Some(err) = status.err {
+                    return Err(ClientErrorKind::TransactionError(err).into());
+                }
+                if status.satisfies_commitment(rpc_client.commitment()) {
+                    info!(target: "server_log", "Raced tx {} confirmed first by {}", signature, endpoint.label);
+                    return Ok(signature);
+                }
+            }
+        }
+
+        Err(ClientErrorKind::Custom(format!(
+            "Raced tx {} was not confirmed within {}s",
+            signature,
+            RACE_TIMEOUT.as_secs()
+        ))
+        .into())
+    }
+
+    pub fn write_metrics(&self, out: &mut String) {
+        write_header(
+            out,
+            "hashpoo_rpc_failovers_total",
+            "Times reads moved to another rpc endpoint.",
+            "counter",
+        );
+        let _ =
+            writeln!(out, "hashpoo_rpc_failovers_total {}", self.failovers.load(Ordering::Relaxed));
+
+        let current = self.current.load(Ordering::Relaxed);
+        let max_slot = self.max_slot.load(Ordering::Relaxed);
+        write_header(
+            out,
+            "hashpoo_rpc_endpoint_up",
+            "1 when the rpc endpoint is healthy, 2 when it also serves the reads.",
+            "gauge",
+        );
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let up = if !self.is_healthy(index) {
+                0
+            } else if index == current {
+                2
+            } else {
+                1
+            };
+            let _ =
+                writeln!(out, "hashpoo_rpc_endpoint_up{{endpoint=\"{}\"}} {}", endpoint.label, up);
+        }
+        write_header(
+            out,
+            "hashpoo_rpc_endpoint_latency_seconds",
+            "Moving average latency of each rpc endpoint.",
+            "gauge",
+        );
+        for endpoint in self.endpoints.iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_rpc_endpoint_latency_seconds{{endpoint=\"{}\"}} {}",
+                endpoint.label,
+                endpoint.health.lock().unwrap().latency_ms / 1000.0
+            );
+        }
+        write_header(
+            out,
+            "hashpoo_rpc_endpoint_slot_lag",
+            "Slots each rpc endpoint is behind the most advanced one.",
+            "gauge",
+        );
+        for endpoint in self.endpoints.iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_rpc_endpoint_slot_lag{{endpoint=\"{}\"}} {}",
+                endpoint.label,
+                max_slot.saturating_sub(endpoint.health.lock().unwrap().slot)
+            );
+        }
+        write_header(
+            out,
+            "hashpoo_rpc_endpoint_failures_total",
+            "Failed requests and probes per rpc endpoint.",
+            "counter",
+        );
+        for endpoint in self.endpoints.iter() {
+            let _ = writeln!(
+                out,
+                "hashpoo_rpc_endpoint_failures_total{{endpoint=\"{}\"}} {}",
+                endpoint.label,
+                endpoint.health.lock().unwrap().failures
+            );
+        }
+    }
+}
+
+/// Json-rpc transport of the shared RpcClient. Each request goes to the preferred endpoint of
+/// the pool and moves on to the next one on transport errors or an unhealthy node.
+pub struct FailoverSender {
+    pool: Arc<RpcPool>,
+    senders: Vec<HttpSender>,
+}
+
+impl FailoverSender {
+    pub fn new(pool: Arc<RpcPool>) -> Self {
+        let senders = pool
+            .endpoints
+            .iter()
+            .map(|endpoint| HttpSender::new_with_timeout(endpoint.url.clone(), RPC_REQUEST_TIMEOUT))
+            .collect();
+        FailoverSender { pool, senders }
+    }
+}
+
+fn should_fail_over(err: &ClientError) -> bool {
+    match &err.kind {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == NODE_UNHEALTHY
+        },
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl RpcSender for FailoverSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> ClientResult<serde_json::Value> {
+        let order = self.pool.order();
+        let mut last_err = None;
+        for (attempt, index) in order.iter().enumerate() {
+            let endpoint = &self.pool.endpoints[*index];
+            let started = Instant::now();
+            match self.senders[*index].send(request, params.clone()).await {
+                Err(err) if should_fail_over(&err) => {
+                    endpoint.record_failure();
+                    warn!(target: "server_log", "RPC {} via {} failed: {}", request, endpoint.label, err);
+                    last_err = Some(err);
+                },
+                res => {
+                    endpoint.record_success(started.elapsed());
+                    if attempt > 0 {
+                        self.pool.switch_to(order[0], *index);
+                    }
+                    return res;
+                },
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.senders[self.pool.current.load(Ordering::Relaxed)].get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.pool.endpoints[self.pool.current.load(Ordering::Relaxed)].url.clone()
+    }
+}
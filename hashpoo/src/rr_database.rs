@@ -74,11 +74,16 @@ impl RrDatabase {
         self.connection_pool.get().await.map_err(|err| err.to_string())
     }
 
+    pub async fn ping(&self) -> Result<(), String> {
+        let db_conn = self.get_connection().await?;
+        db_conn.simple_query("SELECT 1").await.map(|_| ()).map_err(|err| err.to_string())
+    }
+
     pub async fn get_miner_rewards(
         &self,
         miner_pubkey: String,
     ) -> Result<models::Reward, DatabaseError> {
-        let sql = "SELECT r.balance, r.miner_id FROM miners m JOIN rewards r ON m.id = r.miner_id WHERE m.pubkey = $1";
+        let sql = "SELECT SUM(r.balance)::BIGINT, r.miner_id FROM miners m JOIN rewards r ON m.id = r.miner_id WHERE m.pubkey = $1 GROUP BY r.miner_id";
 
         if let Ok(db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
@@ -96,6 +101,7 @@ impl RrDatabase {
 
     pub async fn get_last_challenge_contributions(
         &self,
+        pool_id: i32,
     ) -> Result<Vec<ContributionWithPubkey>, DatabaseError> {
         let sql = r#"
 SELECT
@@ -112,7 +118,7 @@ SELECT
             INNER JOIN challenges c ON s.challenge_id = c.id
     WHERE c.id = (
         SELECT id FROM challenges 
-            WHERE rewards_earned IS NOT NULL
+            WHERE pool_id = $1 AND rewards_earned IS NOT NULL
             ORDER BY id DESC LIMIT 1
     )
     ORDER BY id ASC
@@ -121,7 +127,7 @@ SELECT
         if let Ok(db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
             let mut contributions = vec![];
-            let c_iter = db_conn.query(&stmt, &[]).await.unwrap().into_iter().map(|row| {
+            let c_iter = db_conn.query(&stmt, &[&pool_id]).await.unwrap().into_iter().map(|row| {
                 ContributionWithPubkey {
                     id: row.get("id"),
                     miner_id: row.get("miner_id"),
@@ -171,24 +177,27 @@ SELECT
         }
     }
 
-    pub async fn get_challenges(&self) -> Result<Vec<ChallengeWithDifficulty>, DatabaseError> {
+    pub async fn get_challenges(
+        &self,
+        pool_id: i32,
+    ) -> Result<Vec<ChallengeWithDifficulty>, DatabaseError> {
         let sql = r#"
 SELECT
         c.id                   AS id,
         c.rewards_earned       AS rewards_earned,
         c.updated              AS updated,
-        s.difficulty           AS difficulty,
+        s.difficulty           AS difficulty
     FROM
         challenges c
             INNER JOIN contributions s ON c.contribution_id = s.id
-    WHERE c.contribution_id IS NOT NULL
+    WHERE c.pool_id = $1 AND c.contribution_id IS NOT NULL
     ORDER BY c.id DESC LIMIT 1440
 "#;
 
         if let Ok(db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
             let mut challenges = vec![];
-            let c_iter = db_conn.query(&stmt, &[]).await.unwrap().into_iter().map(|row| {
+            let c_iter = db_conn.query(&stmt, &[&pool_id]).await.unwrap().into_iter().map(|row| {
                 ChallengeWithDifficulty {
                     id: row.get("id"),
                     rewards_earned: row.get("rewards_earned"),
@@ -233,13 +242,16 @@ SELECT
         }
     }
 
-    pub async fn get_latest_mine_transaction(&self) -> Result<models::Transaction, DatabaseError> {
-        let sql = "SELECT * FROM transactions WHERE transaction_type = $1 ORDER BY id DESC LIMIT 1";
+    pub async fn get_latest_mine_transaction(
+        &self,
+        pool_id: i32,
+    ) -> Result<models::Transaction, DatabaseError> {
+        let sql = "SELECT * FROM transactions WHERE transaction_type = $1 AND pool_id = $2 ORDER BY id DESC LIMIT 1";
 
         if let Ok(db_conn) = self.get_connection().await {
             let stmt = db_conn.prepare_cached(sql).await.unwrap();
             let res = db_conn
-                .query_one(&stmt, &[&"mine"])
+                .query_one(&stmt, &[&"mine", &pool_id])
                 .await
                 .map(|row| models::Transaction {
                     id: row.get("id"),
@@ -276,4 +288,172 @@ SELECT
             return Err(DatabaseError::FailedToGetConnectionFromPool);
         }
     }
+
+    pub async fn get_solved_challenges_per_hour(
+        &self,
+        pool_id: i32,
+    ) -> Result<Vec<ChallengesInPeriod>, DatabaseError> {
+        let sql = r#"
+SELECT
+        date_trunc('hour', c.created)::timestamp    AS period,
+        COUNT(c.id)::bigint                         AS num_solved,
+        COALESCE(SUM(c.rewards_earned), 0)::bigint  AS rewards
+    FROM
+        challenges c
+    WHERE
+        c.pool_id = $1 AND
+        c.contribution_id IS NOT NULL AND
+        c.created >= NOW() - INTERVAL '24 hour'
+    GROUP BY period
+    ORDER BY period DESC
+"#;
+
+        self.get_solved_challenges_in_periods(sql, pool_id).await
+    }
+
+    pub async fn get_solved_challenges_per_day(
+        &self,
+        pool_id: i32,
+    ) -> Result<Vec<ChallengesInPeriod>, DatabaseError> {
+        let sql = r#"
+SELECT
+        date_trunc('day', c.created)::timestamp     AS period,
+        COUNT(c.id)::bigint                         AS num_solved,
+        COALESCE(SUM(c.rewards_earned), 0)::bigint  AS rewards
+    FROM
+        challenges c
+    WHERE
+        c.pool_id = $1 AND
+        c.contribution_id IS NOT NULL AND
+        c.created >= NOW() - INTERVAL '30 day'
+    GROUP BY period
+    ORDER BY period DESC
+"#;
+
+        self.get_solved_challenges_in_periods(sql, pool_id).await
+    }
+
+    async fn get_solved_challenges_in_periods(
+        &self,
+        sql: &str,
+        pool_id: i32,
+    ) -> Result<Vec<ChallengesInPeriod>, DatabaseError> {
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn
+                .query(&stmt, &[&pool_id])
+                .await
+                .map(|rows| {
+                    rows.into_iter()
+                        .map(|row| ChallengesInPeriod {
+                            period: row.get("period"),
+                            num_solved: row.get("num_solved"),
+                            rewards: row.get("rewards"),
+                        })
+                        .collect()
+                })
+                .map_err(From::from);
+
+            res
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    pub async fn get_best_difficulty_histogram(
+        &self,
+        pool_id: i32,
+    ) -> Result<Vec<DifficultyCount>, DatabaseError> {
+        let sql = r#"
+SELECT
+        s.difficulty           AS difficulty,
+        COUNT(c.id)::bigint    AS num_challenges
+    FROM
+        challenges c
+            INNER JOIN contributions s ON c.contribution_id = s.id
+    WHERE
+        c.pool_id = $1 AND
+        c.created >= NOW() - INTERVAL '24 hour'
+    GROUP BY s.difficulty
+    ORDER BY s.difficulty ASC
+"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn
+                .query(&stmt, &[&pool_id])
+                .await
+                .map(|rows| {
+                    rows.into_iter()
+                        .map(|row| DifficultyCount {
+                            difficulty: row.get("difficulty"),
+                            num_challenges: row.get("num_challenges"),
+                        })
+                        .collect()
+                })
+                .map_err(From::from);
+
+            res
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
+
+    pub async fn get_pool_stats_summary(
+        &self,
+        pool_id: i32,
+        luck_difficulty: u32,
+    ) -> Result<PoolStatsSummary, DatabaseError> {
+        let sql = r#"
+SELECT
+        COUNT(c.id)::bigint                             AS num_challenges,
+        COUNT(c.contribution_id)::bigint                AS num_solved,
+        COALESCE(SUM(c.rewards_earned), 0)::bigint      AS rewards,
+        COALESCE(AVG(s.difficulty), 0)::float8          AS avg_best_difficulty,
+        COUNT(s.id) FILTER (WHERE s.difficulty >= $2)::bigint AS num_solves,
+        (SELECT COUNT(t.id)
+            FROM transactions t
+            WHERE
+                t.pool_id = $1 AND
+                t.transaction_type = 'mine' AND
+                t.created >= NOW() - INTERVAL '24 hour'
+        )::bigint                                       AS num_mine_transactions,
+        (SELECT COALESCE(SUM(POWER(2::float8, cs.difficulty)), 0)
+            FROM contributions cs
+                INNER JOIN challenges cc ON cs.challenge_id = cc.id
+            WHERE
+                cc.pool_id = $1 AND
+                cc.created >= NOW() - INTERVAL '24 hour'
+        )::float8                                       AS contributed_hashes,
+        COALESCE(EXTRACT(EPOCH FROM NOW() - MIN(c.created)), 0)::float8 AS covered_secs
+    FROM
+        challenges c
+            LEFT JOIN contributions s ON c.contribution_id = s.id
+    WHERE
+        c.pool_id = $1 AND
+        c.created >= NOW() - INTERVAL '24 hour'
+"#;
+
+        if let Ok(db_conn) = self.get_connection().await {
+            let stmt = db_conn.prepare_cached(sql).await.unwrap();
+            let res = db_conn
+                .query_one(&stmt, &[&pool_id, &(luck_difficulty as i16)])
+                .await
+                .map(|row| PoolStatsSummary {
+                    num_challenges: row.get("num_challenges"),
+                    num_solved: row.get("num_solved"),
+                    num_mine_transactions: row.get("num_mine_transactions"),
+                    rewards: row.get("rewards"),
+                    avg_best_difficulty: row.get("avg_best_difficulty"),
+                    contributed_hashes: row.get("contributed_hashes"),
+                    num_solves: row.get("num_solves"),
+                    covered_secs: row.get("covered_secs"),
+                })
+                .map_err(From::from);
+
+            res
+        } else {
+            Err(DatabaseError::FailedToGetConnectionFromPool)
+        }
+    }
 }
@@ -0,0 +1,186 @@
+use {
+    axum::http::{Method, StatusCode},
+    axum_extra::headers::{authorization::Basic, Authorization},
+    solana_sdk::{hash::hash, pubkey::Pubkey, signature::Signature},
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::Mutex,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+// MI: signed authentication messages are only valid for 30 seconds
+const VALID_SECS: u64 = 30;
+
+/// The message a signed request carries the signature of: the http method, the path with its
+/// query string, the sha256 of the body and the little-endian `timestamp` query param.
+pub fn signed_message(
+    method: &Method,
+    path_and_query: &str,
+    body: &[u8],
+    timestamp: u64,
+) -> Vec<u8> {
+    let mut msg = vec![];
+    msg.extend(method.as_str().as_bytes());
+    msg.extend(path_and_query.as_bytes());
+    msg.extend(hash(body).to_bytes());
+    msg.extend(timestamp.to_le_bytes());
+    msg
+}
+
+/// Signatures accepted within the validity window. A client signs each request once, so a
+/// signature seen again is a captured request being replayed.
+#[derive(Default)]
+pub struct SignedRequests {
+    used: Mutex<HashMap<Signature, u64>>,
+}
+
+impl SignedRequests {
+    /// Verifies basic auth with the signer pubkey as username and, as password, its signature over
+    /// [`signed_message`]. Returns the signer.
+    pub fn verify(
+        &self,
+        auth_header: &Authorization<Basic>,
+        method: &Method,
+        path_and_query: &str,
+        body: &[u8],
+        timestamp: u64,
+    ) -> Result<Pubkey, (StatusCode, String)> {
+        let now =
+            SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_secs();
+        self.verify_at(now, auth_header, method, path_and_query, body, timestamp)
+    }
+
+    fn verify_at(
+        &self,
+        now: u64,
+        auth_header: &Authorization<Basic>,
+        method: &Method,
+        path_and_query: &str,
+        body: &[u8],
+        timestamp: u64,
+    ) -> Result<Pubkey, (StatusCode, String)> {
+        if now.abs_diff(timestamp) >= VALID_SECS {
+            return Err((StatusCode::UNAUTHORIZED, "Timestamp too old.".to_string()));
+        }
+
+        let Ok(signer) = Pubkey::from_str(auth_header.username()) else {
+            return Err((StatusCode::UNAUTHORIZED, "Invalid pubkey".to_string()));
+        };
+        let Ok(signature) = Signature::from_str(auth_header.password()) else {
+            return Err((StatusCode::UNAUTHORIZED, "Invalid signature".to_string()));
+        };
+
+        let msg = signed_message(method, path_and_query, body, timestamp);
+        if !signature.verify(&signer.to_bytes(), &msg) {
+            return Err((StatusCode::UNAUTHORIZED, "Sig verification failed".to_string()));
+        }
+
+        let mut used = self.used.lock().unwrap();
+        used.retain(|_, signed_at| now.abs_diff(*signed_at) < VALID_SECS);
+        if used.insert(signature, timestamp).is_some() {
+            return Err((StatusCode::UNAUTHORIZED, "Signature already used".to_string()));
+        }
+
+        Ok(signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::signature::{Keypair, Signer},
+    };
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn auth(keypair: &Keypair, msg: &[u8]) -> Authorization<Basic> {
+        Authorization::basic(&keypair.pubkey().to_string(), &keypair.sign_message(msg).to_string())
+    }
+
+    #[test]
+    fn accepts_a_signed_request_once() {
+        let keypair = Keypair::new();
+        let msg = signed_message(&Method::POST, "/v1/admin/pause?timestamp=1", b"", NOW);
+        let auth_header = auth(&keypair, &msg);
+        let requests = SignedRequests::default();
+
+        let signer = requests
+            .verify_at(NOW, &auth_header, &Method::POST, "/v1/admin/pause?timestamp=1", b"", NOW)
+            .unwrap();
+        assert_eq!(signer, keypair.pubkey());
+
+        let replay = requests.verify_at(
+            NOW + 5,
+            &auth_header,
+            &Method::POST,
+            "/v1/admin/pause?timestamp=1",
+            b"",
+            NOW,
+        );
+        assert_eq!(replay.unwrap_err().1, "Signature already used");
+    }
+
+    #[test]
+    fn rejects_a_signature_moved_to_another_request() {
+        let keypair = Keypair::new();
+        let msg = signed_message(&Method::POST, "/v1/admin/broadcast", b"hello", NOW);
+        let auth_header = auth(&keypair, &msg);
+        let requests = SignedRequests::default();
+
+        for (method, path, body) in [
+            (Method::DELETE, "/v1/admin/broadcast", &b"hello"[..]),
+            (Method::POST, "/v1/admin/pause", &b"hello"[..]),
+            (Method::POST, "/v1/admin/broadcast", &b"bye"[..]),
+        ] {
+            let res = requests.verify_at(NOW, &auth_header, &method, path, body, NOW);
+            assert_eq!(res.unwrap_err().1, "Sig verification failed");
+        }
+        let res = requests.verify_at(
+            NOW,
+            &auth_header,
+            &Method::POST,
+            "/v1/admin/broadcast",
+            b"hello",
+            NOW + 1,
+        );
+        assert_eq!(res.unwrap_err().1, "Sig verification failed");
+    }
+
+    #[test]
+    fn rejects_stale_timestamps_and_forgets_expired_signatures() {
+        let keypair = Keypair::new();
+        let msg = signed_message(&Method::GET, "/v1/admin/state", b"", NOW);
+        let auth_header = auth(&keypair, &msg);
+        let requests = SignedRequests::default();
+
+        let stale =
+            requests.verify_at(NOW + 30, &auth_header, &Method::GET, "/v1/admin/state", b"", NOW);
+        assert_eq!(stale.unwrap_err().1, "Timestamp too old.");
+
+        requests.verify_at(NOW, &auth_header, &Method::GET, "/v1/admin/state", b"", NOW).unwrap();
+        let other = signed_message(&Method::GET, "/v1/admin/state", b"", NOW + 40);
+        requests
+            .verify_at(
+                NOW + 40,
+                &auth(&keypair, &other),
+                &Method::GET,
+                "/v1/admin/state",
+                b"",
+                NOW + 40,
+            )
+            .unwrap();
+        assert_eq!(requests.used.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn signs_the_body_digest() {
+        let msg = signed_message(&Method::POST, "/p", b"body", 7);
+        let mut expected = b"POST/p".to_vec();
+        expected.extend(hash(b"body").to_bytes());
+        expected.extend(7u64.to_le_bytes());
+        assert_eq!(msg, expected);
+    }
+}
@@ -1,90 +1,104 @@
 use {
     solana_client::{
-        connection_cache::ConnectionCache,
+        client_error::{ClientErrorKind, Result as ClientResult},
         nonblocking::tpu_client::TpuClient,
-        send_and_confirm_transactions_in_parallel::{
-            send_and_confirm_transactions_in_parallel, SendAndConfirmConfig,
-        },
         tpu_client::TpuClientConfig,
     },
     solana_rpc_client::nonblocking::rpc_client::RpcClient,
-    solana_sdk::{
-        instruction::Instruction,
-        message::Message,
-        signature::{Keypair, Signer},
-        // signers::Signers,
+    solana_sdk::{signature::Signature, transaction::Transaction},
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
     },
-    // solana_tpu_client::tpu_client::{Result, TpuSenderError},
-    std::sync::Arc,
-    tracing::error,
+    tracing::warn,
 };
 
+// MI: resends to the leaders at this pace until the tx is confirmed or its blockhash expires
+const RESEND_INTERVAL: Duration = Duration::from_secs(2);
+// MI: a blockhash expires after 150 slots, about a minute
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Sends the signed transaction to the upcoming leaders, resending it until the rpc sees it
+/// confirmed or its blockhash expires. The transaction is never resigned, so its signature is
+/// the one that lands.
 pub async fn send_and_confirm(
     rpc_client: &Arc<RpcClient>,
-    instructions: &[Instruction],
-    // signers: &T,
-    signer: &Keypair,
-    config: SendAndConfirmConfig,
-    // ) -> Result<Vec<Option<TransactionError>>> {
-) -> Result<(), Box<dyn std::error::Error>> {
-    let blockhash = rpc_client.get_latest_blockhash().await?;
+    transaction: &Transaction,
+) -> ClientResult<Signature> {
     let websocket_url = rpc_client.url().replace("https", "wss");
-    // let config = SendAndConfirmConfig { resign_txs_count: Some(5), with_spinner: true };
-
-    let message = Message::new_with_blockhash(instructions, Some(&signer.pubkey()), &blockhash);
-    let messages = &[message];
-
-    // let use_quic = true;
-    // let connection_cache = if use_quic {
-    //     ConnectionCache::new_quic("connection_cache_ore_ppl_quic", 1)
-    // } else {
-    //     ConnectionCache::with_udp("connection_cache_ore_ppl_udp", 1)
-    // };
+    let mut tpu_client = TpuClient::new(
+        "connection_cache_ore_ppl_quic",
+        rpc_client.clone(),
+        websocket_url.as_str(),
+        TpuClientConfig::default(),
+    )
+    .await
+    .map_err(|err| ClientErrorKind::Custom(format!("Failed to create tpu client: {err}")))?;
 
-    let connection_cache = ConnectionCache::new_quic("connection_cache_ore_ppl_quic", 1);
+    let signature = transaction.signatures[0];
+    let signatures = [signature];
+    let started = Instant::now();
+    let res = loop {
+        if started.elapsed() >= CONFIRM_TIMEOUT {
+            break Err(ClientErrorKind::Custom(format!(
+                "Tpu tx {} was not confirmed within {}s",
+                signature,
+                CONFIRM_TIMEOUT.as_secs()
+            ))
+            .into());
+        }
+        if !tpu_client.send_transaction(transaction).await {
+            warn!(target: "server_log", "tpu send_transaction of {} failed, retrying...", signature);
+        }
+        tokio::time::sleep(RESEND_INTERVAL).await;
 
-    let transaction_errors = match connection_cache {
-        // ConnectionCache::Udp(cache) => {
-        //     TpuClient::new_with_connection_cache(
-        //         rpc_client.clone(),
-        //         websocket_url.clone().as_str(),
-        //         TpuClientConfig::default(),
-        //         cache,
-        //     )
-        //     .await?
-        //     .send_and_confirm_messages_with_spinner(messages, &[signer])
-        //     .await
-        // },
-        ConnectionCache::Quic(cache) => {
-            let tpu_client = TpuClient::new_with_connection_cache(
-                rpc_client.clone(),
-                websocket_url.clone().as_str(),
-                TpuClientConfig::default(),
-                cache,
-            )
-            .await?;
-            send_and_confirm_transactions_in_parallel(
-                rpc_client.clone(),
-                Some(tpu_client),
-                messages,
-                &[signer],
-                config,
-            )
+        let status = match rpc_client.get_signature_statuses(&signatures).await {
+            Ok(statuses) => statuses.value.into_iter().next().flatten(),
+            Err(err) => {
+                warn!(target: "server_log", "Failed to get status of tpu tx {}: {}", signature, err);
+                continue;
+            },
+        };
+        if let Some(status) = status {
+            if let Some(err) = status.err {
+                break Err(ClientErrorKind::TransactionError(err).into());
+            }
+            if status.satisfies_commitment(rpc_client.commitment()) {
+                break Ok(signature);
+            }
+        } else if let Ok(false) = rpc_client
+            .is_blockhash_valid(&transaction.message.recent_blockhash, rpc_client.commitment())
             .await
-        },
+        {
+            break Err(ClientErrorKind::Custom(format!(
+                "Blockhash of tpu tx {} expired before it was confirmed",
+                signature
+            ))
+            .into());
+        }
+    };
+    tpu_client.shutdown().await;
+    res
+}
 
-        _ => return Err(format!("unsupported connection cache").into()),
-    }
-    .map_err(|err| format!("Data writes to account on-chain failed: {err}"))?
-    .into_iter()
-    .flatten()
-    .collect::<Vec<_>>();
+/// Fire and forget send of an already signed transaction to the upcoming leaders.
+pub async fn send_transaction(
+    rpc_client: &Arc<RpcClient>,
+    transaction: &Transaction,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let websocket_url = rpc_client.url().replace("https", "wss");
+    let mut tpu_client = TpuClient::new(
+        "connection_cache_ore_ppl_quic",
+        rpc_client.clone(),
+        websocket_url.as_str(),
+        TpuClientConfig::default(),
+    )
+    .await?;
 
-    if !transaction_errors.is_empty() {
-        for transaction_error in &transaction_errors {
-            error!(target: "server_log", "Transaction Err: {:?}", transaction_error);
-        }
-        return Err(format!("{} write transactions failed", transaction_errors.len()).into());
+    let sent = tpu_client.send_transaction(transaction).await;
+    tpu_client.shutdown().await;
+    if !sent {
+        return Err("tpu send_transaction failed".into());
     }
 
     Ok(())
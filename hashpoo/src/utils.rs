@@ -318,6 +318,15 @@ pub async fn get_cutoff_with_risk(
     }
 }
 
+/// Same cutoff as `get_cutoff_with_risk`, for a known on-chain unix timestamp.
+pub fn cutoff_at(proof: Proof, buffer_time: u64, risk_time: u64, unix_timestamp: i64) -> i64 {
+    if unix_timestamp > proof.last_hash_at {
+        proof.last_hash_at + 60 + risk_time as i64 - buffer_time as i64 - unix_timestamp
+    } else {
+        60 + risk_time as i64 - buffer_time as i64
+    }
+}
+
 // MI
 // #[cached]
 pub fn play_sound() {